@@ -1,9 +1,11 @@
 //! Image augmentation and transformation pipeline.
 
 use crate::types::{
-    CacheableTransformConfig, DatasetResult, DatasetSample, DetectionLabel, LabelEntry, ResizeMode,
+    BurnDatasetError, CacheableTransformConfig, DatasetResult, DatasetSample, DetectionLabel,
+    LabelEntry, ResizeMode,
 };
 use image::imageops::FilterType;
+use image::{ImageBuffer, Pixel};
 use rand::{Rng, SeedableRng};
 use std::cmp::max;
 
@@ -173,6 +175,7 @@ impl TransformPipeline {
                         width,
                         height,
                         boxes,
+                        box_classes(&meta.labels),
                         meta.frame_id,
                         self.max_boxes,
                     );
@@ -210,23 +213,7 @@ impl TransformPipeline {
                         })
                         .collect::<Vec<_>>();
 
-                    maybe_hflip(&mut resized_img, &mut boxes, self.flip_horizontal_prob, rng);
-                    maybe_jitter(
-                        &mut resized_img,
-                        self.color_jitter_prob,
-                        self.color_jitter_strength,
-                        rng,
-                    );
-                    maybe_scale_jitter(
-                        &mut resized_img,
-                        &mut boxes,
-                        self.scale_jitter_prob,
-                        self.scale_jitter_min,
-                        self.scale_jitter_max,
-                        rng,
-                    );
-                    maybe_noise(&mut resized_img, self.noise_prob, self.noise_strength, rng);
-                    maybe_blur(&mut resized_img, self.blur_prob, self.blur_sigma, rng);
+                    self.augment(&mut resized_img, &mut boxes, rng);
 
                     if boxes.len() > self.max_boxes {
                         boxes.truncate(self.max_boxes);
@@ -237,6 +224,7 @@ impl TransformPipeline {
                         w,
                         h,
                         boxes,
+                        box_classes(&meta.labels),
                         meta.frame_id,
                         self.max_boxes,
                     );
@@ -246,26 +234,39 @@ impl TransformPipeline {
 
         let mut boxes = normalize_boxes(&meta.labels, width, height);
         let mut img = img;
-        maybe_hflip(&mut img, &mut boxes, self.flip_horizontal_prob, rng);
-        maybe_jitter(
-            &mut img,
-            self.color_jitter_prob,
-            self.color_jitter_strength,
-            rng,
-        );
+        self.augment(&mut img, &mut boxes, rng);
+        let sample = build_sample_from_image(
+            img,
+            width,
+            height,
+            boxes,
+            box_classes(&meta.labels),
+            meta.frame_id,
+            self.max_boxes,
+        )?;
+        Ok(sample)
+    }
+
+    /// Apply the per-sample augmentations (flip, color jitter, scale jitter, noise, blur) to an
+    /// already-sized image and its normalized boxes, drawing from `rng`.
+    pub fn augment(
+        &self,
+        img: &mut image::RgbImage,
+        boxes: &mut [[f32; 4]],
+        rng: &mut dyn rand::RngCore,
+    ) {
+        maybe_hflip(img, boxes, self.flip_horizontal_prob, rng);
+        maybe_jitter(img, self.color_jitter_prob, self.color_jitter_strength, rng);
         maybe_scale_jitter(
-            &mut img,
-            &mut boxes,
+            img,
+            boxes,
             self.scale_jitter_prob,
             self.scale_jitter_min,
             self.scale_jitter_max,
             rng,
         );
-        maybe_noise(&mut img, self.noise_prob, self.noise_strength, rng);
-        maybe_blur(&mut img, self.blur_prob, self.blur_sigma, rng);
-        let sample =
-            build_sample_from_image(img, width, height, boxes, meta.frame_id, self.max_boxes)?;
-        Ok(sample)
+        maybe_noise(img, self.noise_prob, self.noise_strength, rng);
+        maybe_blur(img, self.blur_prob, self.blur_sigma, rng);
     }
 }
 
@@ -340,6 +341,7 @@ fn build_sample_from_image(
     width: u32,
     height: u32,
     mut boxes: Vec<[f32; 4]>,
+    mut classes: Vec<u32>,
     frame_id: u64,
     max_boxes: usize,
 ) -> DatasetResult<DatasetSample> {
@@ -355,6 +357,7 @@ fn build_sample_from_image(
     if boxes.len() > max_boxes {
         boxes.truncate(max_boxes);
     }
+    classes.truncate(boxes.len());
 
     ONCE.call_once(|| {
         if boxes.is_empty() {
@@ -373,6 +376,7 @@ fn build_sample_from_image(
         width,
         height,
         boxes,
+        classes,
     })
 }
 
@@ -396,6 +400,16 @@ fn letterbox_resize(
     Ok((canvas, pad_w, pad_h))
 }
 
+/// Class ids of the labels that carry a box, in the order `normalize_boxes` and
+/// `normalize_boxes_with_px` emit them.
+fn box_classes(labels: &[DetectionLabel]) -> Vec<u32> {
+    labels
+        .iter()
+        .filter(|l| l.bbox_norm.is_some() || l.bbox_px.is_some())
+        .map(|l| l.class_id)
+        .collect()
+}
+
 fn normalize_boxes(labels: &[DetectionLabel], w: u32, h: u32) -> Vec<[f32; 4]> {
     labels
         .iter()
@@ -456,6 +470,166 @@ fn normalize_boxes_with_px(
     (norm, pxs)
 }
 
+/// A deterministic flip and scale applied to an image at test time, with the inverse box
+/// mapping. Scaling keeps the image size the way scale jitter does in training: the resized image
+/// is center-cropped (scale > 1) or center-padded with black (scale < 1).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TtaTransform {
+    pub hflip: bool,
+    pub scale: f32,
+}
+
+impl TtaTransform {
+    pub const IDENTITY: Self = Self {
+        hflip: false,
+        scale: 1.0,
+    };
+
+    /// Transformed copy of `img`.
+    pub fn apply<P>(
+        &self,
+        img: &ImageBuffer<P, Vec<P::Subpixel>>,
+    ) -> ImageBuffer<P, Vec<P::Subpixel>>
+    where
+        P: Pixel + 'static,
+    {
+        let mut out = if self.scale == 1.0 {
+            img.clone()
+        } else {
+            scale_centered(img, &mut [], self.scale)
+        };
+        if self.hflip {
+            hflip(&mut out, &mut []);
+        }
+        out
+    }
+
+    /// Map normalized boxes predicted on the transformed `(w, h)` image back onto the original.
+    pub fn invert_boxes(&self, boxes: &mut [[f32; 4]], size: (u32, u32)) {
+        if self.hflip {
+            hflip_boxes(boxes);
+        }
+        if self.scale != 1.0 {
+            unscale_boxes(boxes, size, self.scale);
+        }
+    }
+
+    /// [`TtaTransform::apply`] on a planar `[3, h, w]` float image (a model input's layout).
+    pub fn apply_chw(&self, chw: &[f32], size: (u32, u32)) -> Vec<f32> {
+        if *self == Self::IDENTITY {
+            return chw.to_vec();
+        }
+        let plane = size.0 as usize * size.1 as usize;
+        let hwc: Vec<f32> = (0..plane)
+            .flat_map(|p| (0..3).map(move |c| chw[c * plane + p]))
+            .collect();
+        let img = image::Rgb32FImage::from_raw(size.0, size.1, hwc)
+            .expect("a [3, h, w] buffer matches the image size");
+        let raw = self.apply(&img).into_raw();
+        let raw = &raw;
+        (0..3)
+            .flat_map(|c| (0..plane).map(move |p| raw[p * 3 + c]))
+            .collect()
+    }
+}
+
+/// The test-time variants of an image: every scale in `scales` (just `1.0` when empty), each
+/// also mirrored when `hflip` is set, in scale order with the unmirrored variant first.
+///
+/// Fails on a scale that is not finite and positive; boxes could not be mapped back from it.
+pub fn tta_transforms(hflip: bool, scales: &[f32]) -> DatasetResult<Vec<TtaTransform>> {
+    if let Some(bad) = scales.iter().find(|s| !(s.is_finite() && **s > 0.0)) {
+        return Err(BurnDatasetError::Other(format!(
+            "TTA scale {bad} must be finite and positive"
+        )));
+    }
+    let scales = if scales.is_empty() {
+        &[1.0][..]
+    } else {
+        scales
+    };
+    let flips: &[bool] = if hflip { &[false, true] } else { &[false] };
+    Ok(scales
+        .iter()
+        .flat_map(|&scale| {
+            flips
+                .iter()
+                .map(move |&hflip| TtaTransform { hflip, scale })
+        })
+        .collect())
+}
+
+/// Mirror an image and its normalized boxes left-to-right.
+pub fn hflip<P: Pixel>(img: &mut ImageBuffer<P, Vec<P::Subpixel>>, boxes: &mut [[f32; 4]]) {
+    image::imageops::flip_horizontal_in_place(img);
+    hflip_boxes(boxes);
+}
+
+/// Mirror normalized boxes left-to-right (the mapping is its own inverse).
+pub fn hflip_boxes(boxes: &mut [[f32; 4]]) {
+    for b in boxes.iter_mut() {
+        let x0 = b[0];
+        let x1 = b[2];
+        b[0] = (1.0 - x1).clamp(0.0, 1.0);
+        b[2] = (1.0 - x0).clamp(0.0, 1.0);
+    }
+}
+
+/// Resize an image by `scale` about its center onto a canvas of the original size, moving its
+/// normalized boxes along (clipped to the canvas).
+pub fn scale_centered<P>(
+    img: &ImageBuffer<P, Vec<P::Subpixel>>,
+    boxes: &mut [[f32; 4]],
+    scale: f32,
+) -> ImageBuffer<P, Vec<P::Subpixel>>
+where
+    P: Pixel + 'static,
+{
+    let (w, h) = img.dimensions();
+    let ((new_w, new_h), (x0, y0)) = scale_geometry((w, h), scale);
+    let resized = image::imageops::resize(img, new_w, new_h, FilterType::Triangle);
+    let mut canvas = ImageBuffer::new(w, h);
+    image::imageops::replace(&mut canvas, &resized, x0, y0);
+    let (wf, hf) = (w as f32, h as f32);
+    for b in boxes.iter_mut() {
+        let px0 = (b[0] * wf * scale + x0 as f32).clamp(0.0, wf);
+        let py0 = (b[1] * hf * scale + y0 as f32).clamp(0.0, hf);
+        let px1 = (b[2] * wf * scale + x0 as f32).clamp(px0, wf);
+        let py1 = (b[3] * hf * scale + y0 as f32).clamp(py0, hf);
+        *b = [px0 / wf, py0 / hf, px1 / wf, py1 / hf];
+    }
+    canvas
+}
+
+/// Inverse of the box mapping in [`scale_centered`] for a `(w, h)` image.
+pub fn unscale_boxes(boxes: &mut [[f32; 4]], (w, h): (u32, u32), scale: f32) {
+    let (_, (x0, y0)) = scale_geometry((w, h), scale);
+    let (wf, hf) = (w as f32, h as f32);
+    for b in boxes.iter_mut() {
+        let nx0 = ((b[0] * wf - x0 as f32) / (wf * scale)).clamp(0.0, 1.0);
+        let ny0 = ((b[1] * hf - y0 as f32) / (hf * scale)).clamp(0.0, 1.0);
+        let nx1 = ((b[2] * wf - x0 as f32) / (wf * scale)).clamp(nx0, 1.0);
+        let ny1 = ((b[3] * hf - y0 as f32) / (hf * scale)).clamp(ny0, 1.0);
+        *b = [nx0, ny0, nx1, ny1];
+    }
+}
+
+/// Resized size and the offset of the resized image on the original canvas: negative when it
+/// is center-cropped, positive when it is center-padded.
+fn scale_geometry((w, h): (u32, u32), scale: f32) -> ((u32, u32), (i64, i64)) {
+    let new_w = max(1, (w as f32 * scale).round() as u32);
+    let new_h = max(1, (h as f32 * scale).round() as u32);
+    let offset = if new_w >= w && new_h >= h {
+        (-(((new_w - w) / 2) as i64), -(((new_h - h) / 2) as i64))
+    } else {
+        (
+            (w.saturating_sub(new_w) / 2) as i64,
+            (h.saturating_sub(new_h) / 2) as i64,
+        )
+    };
+    ((new_w, new_h), offset)
+}
+
 pub(crate) fn maybe_hflip(
     img: &mut image::RgbImage,
     boxes: &mut [[f32; 4]],
@@ -466,13 +640,7 @@ pub(crate) fn maybe_hflip(
         return;
     }
     if rng.random_range(0.0..1.0) < prob {
-        image::imageops::flip_horizontal_in_place(img);
-        for b in boxes.iter_mut() {
-            let x0 = b[0];
-            let x1 = b[2];
-            b[0] = (1.0 - x1).clamp(0.0, 1.0);
-            b[2] = (1.0 - x0).clamp(0.0, 1.0);
-        }
+        hflip(img, boxes);
     }
 }
 
@@ -536,58 +704,7 @@ pub(crate) fn maybe_scale_jitter(
         return;
     }
     let scale = rng.random_range(min_scale..max_scale);
-    let (w, h) = img.dimensions();
-    let new_w = max(1, (w as f32 * scale).round() as u32);
-    let new_h = max(1, (h as f32 * scale).round() as u32);
-
-    let resized = image::imageops::resize(img, new_w, new_h, FilterType::Triangle);
-    let mut canvas = image::RgbImage::new(w, h);
-
-    if new_w >= w && new_h >= h {
-        // crop center
-        let x0 = ((new_w - w) / 2) as i64;
-        let y0 = ((new_h - h) / 2) as i64;
-        image::imageops::replace(&mut canvas, &resized, -x0, -y0);
-        let sx = scale;
-        let sy = scale;
-        for b in boxes.iter_mut() {
-            let mut px0 = b[0] * w as f32 * sx - x0 as f32;
-            let mut py0 = b[1] * h as f32 * sy - y0 as f32;
-            let mut px1 = b[2] * w as f32 * sx - x0 as f32;
-            let mut py1 = b[3] * h as f32 * sy - y0 as f32;
-            px0 = px0.clamp(0.0, w as f32);
-            py0 = py0.clamp(0.0, h as f32);
-            px1 = px1.clamp(px0, w as f32);
-            py1 = py1.clamp(py0, h as f32);
-            b[0] = px0 / w as f32;
-            b[1] = py0 / h as f32;
-            b[2] = px1 / w as f32;
-            b[3] = py1 / h as f32;
-        }
-    } else {
-        // pad center
-        let x0 = ((w - new_w) / 2) as i64;
-        let y0 = ((h - new_h) / 2) as i64;
-        image::imageops::replace(&mut canvas, &resized, x0, y0);
-        let sx = scale;
-        let sy = scale;
-        for b in boxes.iter_mut() {
-            let mut px0 = b[0] * w as f32 * sx + x0 as f32;
-            let mut py0 = b[1] * h as f32 * sy + y0 as f32;
-            let mut px1 = b[2] * w as f32 * sx + x0 as f32;
-            let mut py1 = b[3] * h as f32 * sy + y0 as f32;
-            px0 = px0.clamp(0.0, w as f32);
-            py0 = py0.clamp(0.0, h as f32);
-            px1 = px1.clamp(px0, w as f32);
-            py1 = py1.clamp(py0, h as f32);
-            b[0] = px0 / w as f32;
-            b[1] = py0 / h as f32;
-            b[2] = px1 / w as f32;
-            b[3] = py1 / h as f32;
-        }
-    }
-
-    *img = canvas;
+    *img = scale_centered(img, boxes, scale);
 }
 
 pub(crate) fn maybe_blur(
@@ -607,7 +724,7 @@ pub(crate) fn maybe_blur(
 }
 #[cfg(test)]
 mod aug_tests {
-    use super::maybe_hflip;
+    use super::{maybe_hflip, tta_transforms, TtaTransform};
     use rand::rng;
 
     #[test]
@@ -621,4 +738,74 @@ mod aug_tests {
         assert!((flipped[2] - 0.75).abs() < 1e-6);
         assert!(flipped[0] < flipped[2]);
     }
+
+    #[test]
+    fn tta_boxes_map_back_to_the_original_image() {
+        let bbox = [0.25, 0.25, 0.5, 0.75];
+        let mut img = image::RgbImage::new(40, 20);
+        for y in 5..15 {
+            for x in 10..20 {
+                img.put_pixel(x, y, image::Rgb([255, 255, 255]));
+            }
+        }
+        for transform in tta_transforms(true, &[0.5, 1.0, 1.5]).unwrap() {
+            let out = transform.apply(&img);
+            assert_eq!(out.dimensions(), (40, 20));
+            // Box around the white square as seen in the transformed image.
+            let lit: Vec<(u32, u32)> = out
+                .enumerate_pixels()
+                .filter(|(_, _, p)| p[0] > 127)
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            let mut boxes = [[
+                lit.iter().map(|p| p.0).min().unwrap() as f32 / 40.0,
+                lit.iter().map(|p| p.1).min().unwrap() as f32 / 20.0,
+                (lit.iter().map(|p| p.0).max().unwrap() + 1) as f32 / 40.0,
+                (lit.iter().map(|p| p.1).max().unwrap() + 1) as f32 / 20.0,
+            ]];
+            transform.invert_boxes(&mut boxes, (40, 20));
+            for (got, want) in boxes[0].iter().zip(bbox) {
+                assert!((got - want).abs() < 0.06, "{transform:?}: {boxes:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn tta_chw_matches_the_image_transform() {
+        let img = image::Rgb32FImage::from_fn(4, 2, |x, y| image::Rgb([x as f32, y as f32, 0.5]));
+        let chw: Vec<f32> = (0..3)
+            .flat_map(|c| img.pixels().map(move |p| p[c]))
+            .collect();
+        let transform = TtaTransform {
+            hflip: true,
+            scale: 1.0,
+        };
+        let want: Vec<f32> = {
+            let out = transform.apply(&img);
+            (0..3)
+                .flat_map(|c| out.pixels().map(move |p| p[c]))
+                .collect()
+        };
+        assert_eq!(transform.apply_chw(&chw, (4, 2)), want);
+        assert_eq!(want[..4], [3.0, 2.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn tta_transforms_default_to_the_original_scale() {
+        assert_eq!(
+            tta_transforms(false, &[]).unwrap(),
+            vec![TtaTransform::IDENTITY]
+        );
+        let transforms = tta_transforms(true, &[1.0, 0.8]).unwrap();
+        assert_eq!(transforms.len(), 4);
+        assert_eq!(transforms[0], TtaTransform::IDENTITY);
+        assert!(transforms[1].hflip && transforms[3].hflip && transforms[3].scale == 0.8);
+    }
+
+    #[test]
+    fn tta_transforms_reject_unusable_scales() {
+        for bad in [0.0, -0.5, f32::NAN, f32::INFINITY] {
+            assert!(tta_transforms(true, &[1.0, bad]).is_err(), "{bad}");
+        }
+    }
 }
@@ -46,6 +46,8 @@ pub struct BurnBatch<B: burn::tensor::backend::Backend> {
     pub images: burn::tensor::Tensor<B, 4>,
     pub boxes: burn::tensor::Tensor<B, 3>,
     pub box_mask: burn::tensor::Tensor<B, 2>,
+    /// Class id of each padded box slot as a float (0 in padding; read with `box_mask`).
+    pub classes: burn::tensor::Tensor<B, 2>,
     pub frame_ids: burn::tensor::Tensor<B, 1>,
 }
 
@@ -72,6 +74,7 @@ pub struct BatchIter {
     images_buf: Vec<f32>,
     boxes_buf: Vec<f32>,
     mask_buf: Vec<f32>,
+    classes_buf: Vec<f32>,
     frame_ids_buf: Vec<f32>,
     trace_path: Option<PathBuf>,
     trace_file: Option<std::fs::File>,
@@ -147,6 +150,7 @@ impl BatchIter {
             images_buf: Vec::new(),
             boxes_buf: Vec::new(),
             mask_buf: Vec::new(),
+            classes_buf: Vec::new(),
             frame_ids_buf: Vec::new(),
             trace_path,
             trace_file: None,
@@ -170,6 +174,7 @@ impl BatchIter {
             self.images_buf.clear();
             self.boxes_buf.clear();
             self.mask_buf.clear();
+            self.classes_buf.clear();
             self.frame_ids_buf.clear();
 
             let mut expected_size: Option<(u32, u32)> = None;
@@ -238,6 +243,8 @@ impl BatchIter {
                             .reserve(box_elems - self.boxes_buf.capacity());
                         self.mask_buf
                             .reserve(box_elems / 4 - self.mask_buf.capacity());
+                        self.classes_buf
+                            .reserve(box_elems / 4 - self.classes_buf.capacity());
                     }
                     if self.frame_ids_buf.capacity() < batch_size {
                         self.frame_ids_buf
@@ -250,15 +257,18 @@ impl BatchIter {
 
                 let mut padded = vec![0.0f32; self.cfg.max_boxes * 4];
                 let mut mask = vec![0.0f32; self.cfg.max_boxes];
+                let mut classes = vec![0.0f32; self.cfg.max_boxes];
                 for (i, b) in sample.boxes.iter().take(self.cfg.max_boxes).enumerate() {
                     padded[i * 4] = b[0];
                     padded[i * 4 + 1] = b[1];
                     padded[i * 4 + 2] = b[2];
                     padded[i * 4 + 3] = b[3];
                     mask[i] = 1.0;
+                    classes[i] = sample.classes.get(i).copied().unwrap_or(0) as f32;
                 }
                 self.boxes_buf.extend_from_slice(&padded);
                 self.mask_buf.extend_from_slice(&mask);
+                self.classes_buf.extend_from_slice(&classes);
             }
 
             if self.images_buf.is_empty() {
@@ -295,6 +305,9 @@ impl BatchIter {
             let box_mask =
                 burn::tensor::Tensor::<B, 1>::from_floats(self.mask_buf.as_slice(), device)
                     .reshape(mask_shape);
+            let classes =
+                burn::tensor::Tensor::<B, 1>::from_floats(self.classes_buf.as_slice(), device)
+                    .reshape(mask_shape);
             let frame_ids =
                 burn::tensor::Tensor::<B, 1>::from_floats(self.frame_ids_buf.as_slice(), device)
                     .reshape([batch_len]);
@@ -320,6 +333,7 @@ impl BatchIter {
                 images,
                 boxes,
                 box_mask,
+                classes,
                 frame_ids,
             }));
         }
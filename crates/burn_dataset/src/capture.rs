@@ -5,6 +5,7 @@ use crate::types::{
     BurnDatasetError, DatasetResult, DatasetSample, DatasetSummary, LabelEntry, ResizeMode,
     RunSummary, SampleIndex,
 };
+use data_contracts::RunManifest;
 use image;
 use serde_json;
 use std::fs;
@@ -162,6 +163,49 @@ pub fn index_runs(root: &Path) -> DatasetResult<Vec<SampleIndex>> {
     Ok(indices)
 }
 
+/// Class-name table shared by the runs in `indices`, read from each run's `run_manifest.json`.
+///
+/// Runs without a manifest or without `class_names` are ignored; runs that name their classes
+/// differently are an error, since class ids would then mean different things across runs.
+pub fn load_class_names(indices: &[SampleIndex]) -> DatasetResult<Option<Vec<String>>> {
+    let mut run_dirs: Vec<&Path> = indices.iter().map(|idx| idx.run_dir.as_path()).collect();
+    run_dirs.sort();
+    run_dirs.dedup();
+    let mut names: Option<(PathBuf, Vec<String>)> = None;
+    for run_dir in run_dirs {
+        let path = run_dir.join("run_manifest.json");
+        if !path.exists() {
+            continue;
+        }
+        let raw = fs::read(&path).map_err(|e| BurnDatasetError::Io {
+            path: path.clone(),
+            source: e,
+        })?;
+        let manifest: RunManifest =
+            serde_json::from_slice(&raw).map_err(|e| BurnDatasetError::Json {
+                path: path.clone(),
+                source: e,
+            })?;
+        let Some(run_names) = manifest.class_names else {
+            continue;
+        };
+        match &names {
+            Some((first, existing)) if *existing != run_names => {
+                return Err(BurnDatasetError::Validation {
+                    path,
+                    msg: format!(
+                        "class_names {run_names:?} differ from {existing:?} in {}",
+                        first.display()
+                    ),
+                });
+            }
+            Some(_) => {}
+            None => names = Some((path, run_names)),
+        }
+    }
+    Ok(names.map(|(_, names)| names))
+}
+
 /// Load a capture run into an in-memory vector (eager). Prefer `BatchIter` for large sets.
 pub fn load_run_dataset(run_dir: &Path) -> DatasetResult<Vec<DatasetSample>> {
     let labels_dir = run_dir.join("labels");
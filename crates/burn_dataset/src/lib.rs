@@ -20,14 +20,16 @@ pub mod batch;
 pub mod warehouse;
 
 // Re-export public API
-pub use aug::{DatasetConfig, TransformPipeline, TransformPipelineBuilder};
-pub use capture::{index_runs, load_run_dataset, load_sample_for_etl, summarize_runs};
+pub use aug::{DatasetConfig, TransformPipeline, TransformPipelineBuilder, TtaTransform};
+pub use capture::{
+    index_runs, load_class_names, load_run_dataset, load_sample_for_etl, summarize_runs,
+};
 pub use splits::{count_boxes, split_runs, split_runs_stratified};
 pub use types::*;
 pub use validation::{summarize_root_with_thresholds, summarize_with_thresholds, validate_summary};
 
 #[cfg(feature = "burn-runtime")]
-pub use warehouse::{WarehouseLoaders, WarehouseManifest};
+pub use warehouse::{WarehouseLoaders, WarehouseManifest, SHARD_VERSION_CLASSES};
 
 #[cfg(feature = "burn-runtime")]
 pub use batch::{build_train_val_iters, BatchIter, BurnBatch};
@@ -45,6 +45,8 @@ pub struct DatasetSample {
     pub height: u32,
     /// Normalized bounding boxes: [x_min, y_min, x_max, y_max] in 0..1.
     pub boxes: Vec<[f32; 4]>,
+    /// Class id of each box in `boxes` (`data_contracts::DetectionLabel::class_id`).
+    pub classes: Vec<u32>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -74,6 +76,8 @@ pub(crate) struct LabelEntry {
 pub(crate) struct DetectionLabel {
     pub(crate) bbox_px: Option<[f32; 4]>,
     pub(crate) bbox_norm: Option<[f32; 4]>,
+    #[serde(default)]
+    pub(crate) class_id: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
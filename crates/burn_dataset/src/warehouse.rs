@@ -53,8 +53,16 @@ pub struct WarehouseManifest {
     pub shards: Vec<ShardMetadata>,
     pub summary: DatasetSummary,
     pub thresholds: ValidationThresholds,
+    /// Names of the label classes, indexed by class id (from the source runs' `RunManifest`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_names: Option<Vec<String>>,
 }
 
+/// First shard format version with a per-box class section. Its header extends the v1 layout
+/// with a u64 classes offset at bytes 64..72 pointing at `[samples, max_boxes]` f32 class ids;
+/// v1 shards are still readable and yield class 0 for every box.
+pub const SHARD_VERSION_CLASSES: u32 = 2;
+
 impl WarehouseManifest {
     /// Default code version string (crate version).
     pub fn default_code_version() -> String {
@@ -150,11 +158,12 @@ impl WarehouseManifest {
             version_recipe,
             code_version,
             default_dtype: ShardDType::F32,
-            default_shard_version: 1,
+            default_shard_version: SHARD_VERSION_CLASSES,
             created_at_ms,
             shards,
             summary,
             thresholds,
+            class_names: None,
         }
     }
 }
@@ -173,12 +182,15 @@ enum ShardBacking {
         images: Vec<f32>,
         boxes: Vec<f32>,
         masks: Vec<f32>,
+        /// Empty for v1 shards.
+        classes: Vec<f32>,
     },
     Mmap {
         mmap: std::sync::Arc<memmap2::Mmap>,
         image_offset: usize,
         boxes_offset: usize,
         mask_offset: usize,
+        classes_offset: Option<usize>,
     },
     #[allow(dead_code)]
     Streamed {
@@ -186,6 +198,7 @@ enum ShardBacking {
         image_offset: usize,
         boxes_offset: usize,
         mask_offset: usize,
+        classes_offset: Option<usize>,
         samples: usize,
     }, // placeholder for future extensions
 }
@@ -198,6 +211,7 @@ impl ShardBuffer {
         out_images: &mut Vec<f32>,
         out_boxes: &mut Vec<f32>,
         out_masks: &mut Vec<f32>,
+        out_classes: &mut Vec<f32>,
     ) -> DatasetResult<()> {
         let w = self.width as usize;
         let h = self.height as usize;
@@ -209,6 +223,7 @@ impl ShardBuffer {
                 images,
                 boxes,
                 masks,
+                classes,
             } => {
                 let img_offset = sample_idx
                     .checked_mul(img_elems)
@@ -222,6 +237,11 @@ impl ShardBuffer {
                 out_images.extend_from_slice(&images[img_offset..img_offset + img_elems]);
                 out_boxes.extend_from_slice(&boxes[box_offset..box_offset + box_elems]);
                 out_masks.extend_from_slice(&masks[mask_offset..mask_offset + mask_elems]);
+                if classes.is_empty() {
+                    out_classes.resize(out_classes.len() + mask_elems, 0.0);
+                } else {
+                    out_classes.extend_from_slice(&classes[mask_offset..mask_offset + mask_elems]);
+                }
                 Ok(())
             }
             ShardBacking::Mmap {
@@ -229,6 +249,7 @@ impl ShardBuffer {
                 image_offset,
                 boxes_offset,
                 mask_offset,
+                classes_offset,
             } => {
                 let img_bytes = img_elems
                     .checked_mul(std::mem::size_of::<f32>())
@@ -249,10 +270,18 @@ impl ShardBuffer {
                 let mask_start = mask_offset
                     .checked_add(sample_idx * mask_bytes)
                     .ok_or_else(|| BurnDatasetError::Other("mask offset overflow".into()))?;
+                let classes_start = classes_offset
+                    .map(|offset| {
+                        offset.checked_add(sample_idx * mask_bytes).ok_or_else(|| {
+                            BurnDatasetError::Other("classes offset overflow".into())
+                        })
+                    })
+                    .transpose()?;
 
                 if img_start + img_bytes > mmap.len()
                     || box_start + box_bytes > mmap.len()
                     || mask_start + mask_bytes > mmap.len()
+                    || classes_start.is_some_and(|start| start + mask_bytes > mmap.len())
                 {
                     return Err(BurnDatasetError::Other(
                         "shard mmap truncated for requested sample".into(),
@@ -274,6 +303,16 @@ impl ShardBuffer {
                     arr.copy_from_slice(chunk);
                     out_masks.push(f32::from_le_bytes(arr));
                 }
+                match classes_start {
+                    Some(start) => {
+                        for chunk in mmap[start..start + mask_bytes].chunks_exact(4) {
+                            let mut arr = [0u8; 4];
+                            arr.copy_from_slice(chunk);
+                            out_classes.push(f32::from_le_bytes(arr));
+                        }
+                    }
+                    None => out_classes.resize(out_classes.len() + mask_elems, 0.0),
+                }
                 Ok(())
             }
             ShardBacking::Streamed {
@@ -281,6 +320,7 @@ impl ShardBuffer {
                 image_offset,
                 boxes_offset,
                 mask_offset,
+                classes_offset,
                 samples,
             } => {
                 if sample_idx >= *samples {
@@ -343,6 +383,16 @@ impl ShardBuffer {
                 read_f32s(&mut file, img_start, img_bytes, out_images, path)?;
                 read_f32s(&mut file, boxes_start, box_bytes, out_boxes, path)?;
                 read_f32s(&mut file, mask_start, mask_bytes, out_masks, path)?;
+                match classes_offset {
+                    Some(offset) => {
+                        let classes_start =
+                            offset.checked_add(sample_idx * mask_bytes).ok_or_else(|| {
+                                BurnDatasetError::Other("classes offset overflow".into())
+                            })?;
+                        read_f32s(&mut file, classes_start, mask_bytes, out_classes, path)?;
+                    }
+                    None => out_classes.resize(out_classes.len() + mask_elems, 0.0),
+                }
                 Ok(())
             }
         }
@@ -377,6 +427,7 @@ struct StreamedSample {
     images: Vec<f32>,
     boxes: Vec<f32>,
     masks: Vec<f32>,
+    classes: Vec<f32>,
 }
 
 #[cfg(feature = "burn-runtime")]
@@ -469,7 +520,14 @@ impl StreamingStore {
                 let mut images = Vec::new();
                 let mut boxes = Vec::new();
                 let mut masks = Vec::new();
-                if let Err(e) = shard.copy_sample(sample_idx, &mut images, &mut boxes, &mut masks) {
+                let mut classes = Vec::new();
+                if let Err(e) = shard.copy_sample(
+                    sample_idx,
+                    &mut images,
+                    &mut boxes,
+                    &mut masks,
+                    &mut classes,
+                ) {
                     eprintln!("[warehouse] streaming copy error: {:?}", e);
                     break;
                 }
@@ -478,6 +536,7 @@ impl StreamingStore {
                         images,
                         boxes,
                         masks,
+                        classes,
                     }))
                     .is_err()
                 {
@@ -816,10 +875,17 @@ impl WarehouseBatchIter {
                 let mut images = Vec::new();
                 let mut boxes = Vec::new();
                 let mut masks = Vec::new();
+                let mut classes = Vec::new();
                 let mut frame_ids = Vec::new();
                 for (global_idx, (shard_idx, sample_idx)) in slice.iter().enumerate() {
                     let shard = &shards[*shard_idx];
-                    shard.copy_sample(*sample_idx, &mut images, &mut boxes, &mut masks)?;
+                    shard.copy_sample(
+                        *sample_idx,
+                        &mut images,
+                        &mut boxes,
+                        &mut masks,
+                        &mut classes,
+                    )?;
                     frame_ids.push(global_idx as f32);
                 }
                 let image_shape = [slice.len(), 3, self.height as usize, self.width as usize];
@@ -832,6 +898,8 @@ impl WarehouseBatchIter {
                     .reshape(boxes_shape);
                 let box_mask = burn::tensor::Tensor::<B, 1>::from_floats(masks.as_slice(), device)
                     .reshape(mask_shape);
+                let classes = burn::tensor::Tensor::<B, 1>::from_floats(classes.as_slice(), device)
+                    .reshape(mask_shape);
                 let frame_ids =
                     burn::tensor::Tensor::<B, 1>::from_floats(frame_ids.as_slice(), device)
                         .reshape([slice.len()]);
@@ -840,6 +908,7 @@ impl WarehouseBatchIter {
                     images,
                     boxes,
                     box_mask,
+                    classes,
                     frame_ids,
                 }))
             }
@@ -855,6 +924,7 @@ impl WarehouseBatchIter {
                 let mut images = Vec::new();
                 let mut boxes = Vec::new();
                 let mut masks = Vec::new();
+                let mut classes = Vec::new();
                 let mut frame_ids = Vec::new();
                 let mut pulled = 0usize;
                 while pulled < batch_size {
@@ -863,6 +933,7 @@ impl WarehouseBatchIter {
                             images.extend_from_slice(&sample.images);
                             boxes.extend_from_slice(&sample.boxes);
                             masks.extend_from_slice(&sample.masks);
+                            classes.extend_from_slice(&sample.classes);
                             frame_ids.push(pulled as f32);
                             pulled += 1;
                         }
@@ -889,6 +960,8 @@ impl WarehouseBatchIter {
                     .reshape(boxes_shape);
                 let box_mask = burn::tensor::Tensor::<B, 1>::from_floats(masks.as_slice(), device)
                     .reshape(mask_shape);
+                let classes = burn::tensor::Tensor::<B, 1>::from_floats(classes.as_slice(), device)
+                    .reshape(mask_shape);
                 let frame_ids =
                     burn::tensor::Tensor::<B, 1>::from_floats(frame_ids.as_slice(), device)
                         .reshape([pulled]);
@@ -896,6 +969,7 @@ impl WarehouseBatchIter {
                     images,
                     boxes,
                     box_mask,
+                    classes,
                     frame_ids,
                 }))
             }
@@ -978,6 +1052,58 @@ fn read_u64_le(data: &[u8]) -> u64 {
     u64::from_le_bytes(arr)
 }
 
+/// Reject shards whose header version disagrees with the manifest or that this reader predates.
+#[cfg(feature = "burn-runtime")]
+fn check_shard_version(shard_version: u32, meta: &ShardMetadata, path: &Path) -> DatasetResult<()> {
+    if shard_version != meta.shard_version {
+        return Err(BurnDatasetError::Other(format!(
+            "shard version mismatch {} vs {}",
+            shard_version, meta.shard_version
+        )));
+    }
+    if shard_version == 0 || shard_version > SHARD_VERSION_CLASSES {
+        return Err(BurnDatasetError::Other(format!(
+            "unsupported shard version {} in {}",
+            shard_version,
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Byte offset of the class-id section (`None` for v1 shards, which have none).
+#[cfg(feature = "burn-runtime")]
+fn read_classes_offset(
+    header: &[u8],
+    shard_version: u32,
+    path: &Path,
+) -> DatasetResult<Option<usize>> {
+    if shard_version < SHARD_VERSION_CLASSES {
+        return Ok(None);
+    }
+    if header.len() < 72 {
+        return Err(BurnDatasetError::Other(format!(
+            "shard {} header too small for version {}",
+            path.display(),
+            shard_version
+        )));
+    }
+    Ok(Some(read_u64_le(&header[64..72]) as usize))
+}
+
+/// Fill `buf` from the start of the file, stopping early only at EOF.
+#[cfg(feature = "burn-runtime")]
+fn read_header<R: Read>(file: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read(&mut buf[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
 #[cfg(feature = "burn-runtime")]
 fn load_shard_owned(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuffer> {
     let path = root.join(&meta.relative_path);
@@ -998,12 +1124,7 @@ fn load_shard_owned(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuf
         )));
     }
     let shard_version = read_u32_le(&data[4..8]);
-    if shard_version != meta.shard_version {
-        return Err(BurnDatasetError::Other(format!(
-            "shard version mismatch {} vs {}",
-            shard_version, meta.shard_version
-        )));
-    }
+    check_shard_version(shard_version, meta, &path)?;
     let dtype = read_u32_le(&data[8..12]);
     if dtype != 0 {
         return Err(BurnDatasetError::Other(format!(
@@ -1027,6 +1148,7 @@ fn load_shard_owned(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuf
     let image_offset = read_u64_le(&data[40..48]) as usize;
     let boxes_offset = read_u64_le(&data[48..56]) as usize;
     let mask_offset = read_u64_le(&data[56..64]) as usize;
+    let classes_offset = read_classes_offset(&data, shard_version, &path)?;
 
     let image_elems = samples
         .checked_mul(3)
@@ -1048,6 +1170,7 @@ fn load_shard_owned(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuf
     if image_offset + image_bytes > data.len()
         || boxes_offset + box_bytes > data.len()
         || mask_offset + mask_bytes > data.len()
+        || classes_offset.is_some_and(|offset| offset + mask_bytes > data.len())
     {
         return Err(BurnDatasetError::Other(format!(
             "shard {} truncated",
@@ -1079,6 +1202,18 @@ fn load_shard_owned(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuf
             f32::from_le_bytes(arr)
         })
         .collect();
+    let classes = classes_offset
+        .map(|offset| {
+            data[offset..offset + mask_bytes]
+                .chunks_exact(4)
+                .map(|c| {
+                    let mut arr = [0u8; 4];
+                    arr.copy_from_slice(c);
+                    f32::from_le_bytes(arr)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
 
     Ok(ShardBuffer {
         samples,
@@ -1089,6 +1224,7 @@ fn load_shard_owned(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuf
             images,
             boxes,
             masks,
+            classes,
         },
     })
 }
@@ -1122,12 +1258,7 @@ fn load_shard_mmap(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuff
         )));
     }
     let shard_version = read_u32_le(&data[4..8]);
-    if shard_version != meta.shard_version {
-        return Err(BurnDatasetError::Other(format!(
-            "shard version mismatch {} vs {}",
-            shard_version, meta.shard_version
-        )));
-    }
+    check_shard_version(shard_version, meta, &path)?;
     let dtype = read_u32_le(&data[8..12]);
     if dtype != 0 {
         return Err(BurnDatasetError::Other(format!(
@@ -1151,6 +1282,7 @@ fn load_shard_mmap(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuff
     let image_offset = read_u64_le(&data[40..48]) as usize;
     let boxes_offset = read_u64_le(&data[48..56]) as usize;
     let mask_offset = read_u64_le(&data[56..64]) as usize;
+    let classes_offset = read_classes_offset(data, shard_version, &path)?;
 
     let image_elems = samples
         .checked_mul(3)
@@ -1172,6 +1304,7 @@ fn load_shard_mmap(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuff
     if image_offset + image_bytes > data.len()
         || boxes_offset + box_bytes > data.len()
         || mask_offset + mask_bytes > data.len()
+        || classes_offset.is_some_and(|offset| offset + mask_bytes > data.len())
     {
         return Err(BurnDatasetError::Other(format!(
             "shard {} truncated",
@@ -1189,6 +1322,7 @@ fn load_shard_mmap(root: &Path, meta: &ShardMetadata) -> DatasetResult<ShardBuff
             image_offset,
             boxes_offset,
             mask_offset,
+            classes_offset,
         },
     })
 }
@@ -1200,8 +1334,8 @@ fn load_shard_streamed(root: &Path, meta: &ShardMetadata) -> DatasetResult<Shard
         path: path.clone(),
         source: e,
     })?;
-    let mut header = vec![0u8; 64];
-    let read = file.read(&mut header).map_err(|e| BurnDatasetError::Io {
+    let mut header = vec![0u8; 72];
+    let read = read_header(&mut file, &mut header).map_err(|e| BurnDatasetError::Io {
         path: path.clone(),
         source: e,
     })?;
@@ -1218,12 +1352,7 @@ fn load_shard_streamed(root: &Path, meta: &ShardMetadata) -> DatasetResult<Shard
         )));
     }
     let shard_version = read_u32_le(&header[4..8]);
-    if shard_version != meta.shard_version {
-        return Err(BurnDatasetError::Other(format!(
-            "shard version mismatch {} vs {}",
-            shard_version, meta.shard_version
-        )));
-    }
+    check_shard_version(shard_version, meta, &path)?;
     let dtype = read_u32_le(&header[8..12]);
     if dtype != 0 {
         return Err(BurnDatasetError::Other(format!(
@@ -1247,6 +1376,7 @@ fn load_shard_streamed(root: &Path, meta: &ShardMetadata) -> DatasetResult<Shard
     let image_offset = read_u64_le(&header[40..48]) as usize;
     let boxes_offset = read_u64_le(&header[48..56]) as usize;
     let mask_offset = read_u64_le(&header[56..64]) as usize;
+    let classes_offset = read_classes_offset(&header[..read], shard_version, &path)?;
 
     let img_elems = samples
         .checked_mul(3)
@@ -1285,6 +1415,12 @@ fn load_shard_streamed(root: &Path, meta: &ShardMetadata) -> DatasetResult<Shard
             .checked_add(mask_bytes)
             .map(|v| v > file_len)
             .unwrap_or(true)
+        || classes_offset.is_some_and(|offset| {
+            offset
+                .checked_add(mask_bytes)
+                .map(|v| v > file_len)
+                .unwrap_or(true)
+        })
     {
         return Err(BurnDatasetError::Other(format!(
             "shard {} truncated",
@@ -1302,6 +1438,7 @@ fn load_shard_streamed(root: &Path, meta: &ShardMetadata) -> DatasetResult<Shard
             image_offset,
             boxes_offset,
             mask_offset,
+            classes_offset,
             samples,
         },
     })
@@ -4,9 +4,11 @@
 //! 1. Capture → Warehouse ETL pipeline
 //! 2. Warehouse → Training batch iteration
 //! 3. Capture → Validation → Stratified splits
+//! 4. Run manifests → class names
 
 use burn_dataset::{
-    index_runs, split_runs_stratified, summarize_with_thresholds, ValidationThresholds,
+    index_runs, load_class_names, split_runs_stratified, summarize_with_thresholds,
+    ValidationThresholds,
 };
 
 #[cfg(feature = "burn-runtime")]
@@ -15,6 +17,7 @@ use burn_dataset::{
     ShardDType, ShardMetadata, TransformPipelineBuilder, WarehouseManifest,
 };
 use data_contracts::capture::{CaptureMetadata, DetectionLabel};
+use data_contracts::{RunManifest, RunManifestSchemaVersion};
 use image::{Rgb, RgbImage};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -51,6 +54,7 @@ fn create_synthetic_run(
                 bbox_norm: Some([0.1 + offset * 0.1, 0.1, 0.5, 0.5]),
                 source: None,
                 source_confidence: None,
+                class_id: 0,
             });
         }
 
@@ -481,3 +485,45 @@ fn workflow_capture_validation_and_stratified_split() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn write_run_manifest(run_dir: &Path, class_names: Option<&[&str]>) -> anyhow::Result<()> {
+    let manifest = RunManifest {
+        schema_version: RunManifestSchemaVersion::V1,
+        seed: None,
+        output_root: run_dir.parent().unwrap_or(run_dir).to_path_buf(),
+        run_dir: run_dir.to_path_buf(),
+        started_at_unix: 0.0,
+        max_frames: None,
+        class_names: class_names.map(|names| names.iter().map(|n| n.to_string()).collect()),
+    };
+    fs::write(
+        run_dir.join("run_manifest.json"),
+        serde_json::to_vec(&manifest)?,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn workflow_class_names_from_run_manifests() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let named = create_synthetic_run(root, "run_named", 2, 1)?;
+    let unnamed = create_synthetic_run(root, "run_unnamed", 2, 1)?;
+    create_synthetic_run(root, "run_no_manifest", 2, 1)?;
+    write_run_manifest(&named, Some(&["polyp", "tool"]))?;
+    write_run_manifest(&unnamed, None)?;
+
+    // Runs without a table don't conflict with the one that has it.
+    let indices = index_runs(root)?;
+    assert_eq!(
+        load_class_names(&indices)?,
+        Some(vec!["polyp".to_string(), "tool".to_string()])
+    );
+
+    // A run that names its classes differently is rejected.
+    let other = create_synthetic_run(root, "run_other", 2, 1)?;
+    write_run_manifest(&other, Some(&["tool", "polyp"]))?;
+    let indices = index_runs(root)?;
+    assert!(load_class_names(&indices).is_err());
+    Ok(())
+}
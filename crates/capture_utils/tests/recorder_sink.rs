@@ -22,6 +22,7 @@ fn json_recorder_writes_label_file() {
         bbox_norm: Some([0.0, 0.0, 1.0, 1.0]),
         source: None,
         source_confidence: None,
+        class_id: 0,
     }];
     let record = FrameRecord {
         frame,
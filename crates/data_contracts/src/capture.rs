@@ -16,6 +16,10 @@ pub struct DetectionLabel {
     pub bbox_norm: Option<[f32; 4]>,
     pub source: Option<LabelSource>,
     pub source_confidence: Option<f32>,
+    /// Object class, indexing `RunManifest::class_names` when the run has a table. Labels written
+    /// before classes existed read as class 0.
+    #[serde(default)]
+    pub class_id: u32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -4,6 +4,7 @@
 //! - `CaptureMetadata`: Per-frame capture metadata (labels, timestamps, provenance).
 //! - `RunManifest`: Run-level configuration and metadata.
 //! - `ImageStats`: Preprocessing statistics for normalization.
+//! - `TrainStatus`: Live training progress shared between `train` and the tools TUI.
 //!
 //! These types are used across the CortenForge stack for dataset persistence, validation,
 //! and reproducibility. All types implement `Serialize`/`Deserialize` for JSON storage.
@@ -11,7 +12,9 @@
 pub mod capture;
 pub mod manifest;
 pub mod preprocess;
+pub mod train_status;
 
 pub use capture::{CaptureMetadata, DetectionLabel, LabelSource, ValidationError};
 pub use manifest::{RunManifest, RunManifestSchemaVersion};
 pub use preprocess::{ImageStats, ImageStatsError};
+pub use train_status::{TrainState, TrainStatus};
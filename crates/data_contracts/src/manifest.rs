@@ -14,9 +14,20 @@ pub struct RunManifest {
     pub run_dir: PathBuf,
     pub started_at_unix: f64,
     pub max_frames: Option<u32>,
+    /// Names of the label classes, indexed by `DetectionLabel::class_id`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_names: Option<Vec<String>>,
 }
 
 impl RunManifest {
+    /// Name of `class_id`, if the run has a class table covering it.
+    pub fn class_name(&self, class_id: u32) -> Option<&str> {
+        self.class_names
+            .as_ref()?
+            .get(class_id as usize)
+            .map(String::as_str)
+    }
+
     pub fn validate(&self) -> Result<(), String> {
         if self.started_at_unix.is_nan() || self.started_at_unix < 0.0 {
             return Err("started_at_unix must be non-negative".into());
@@ -26,6 +37,14 @@ impl RunManifest {
                 return Err("max_frames cannot be zero".into());
             }
         }
+        if let Some(names) = &self.class_names {
+            if names.is_empty() {
+                return Err("class_names cannot be empty".into());
+            }
+            if names.iter().any(|name| name.trim().is_empty()) {
+                return Err("class_names cannot contain blank names".into());
+            }
+        }
         Ok(())
     }
 }
@@ -100,6 +100,29 @@ pub fn stats_from_rgba_u8(
     stats_from_sums(width as usize, height as usize, sum, sumsq)
 }
 
+/// Convert an RGBA8 buffer into normalized (0..1) RGB floats in CHW order, dropping alpha.
+pub fn chw_from_rgba_u8(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<f32>, ImageStatsError> {
+    if width == 0 || height == 0 {
+        return Err(ImageStatsError::ZeroDimensions);
+    }
+    let pixels = width as usize * height as usize;
+    let expected = pixels * 4;
+    if rgba.len() != expected {
+        return Err(ImageStatsError::InvalidLength {
+            expected,
+            actual: rgba.len(),
+        });
+    }
+
+    let mut chw = vec![0f32; pixels * 3];
+    for (i, chunk) in rgba.chunks_exact(4).enumerate() {
+        chw[i] = chunk[0] as f32 / 255.0;
+        chw[pixels + i] = chunk[1] as f32 / 255.0;
+        chw[2 * pixels + i] = chunk[2] as f32 / 255.0;
+    }
+    Ok(chw)
+}
+
 pub fn stats_from_chw_f32(
     width: usize,
     height: usize,
@@ -0,0 +1,90 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Lifecycle of a training run as reported in its status file.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrainState {
+    Starting,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl TrainState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TrainState::Starting => "starting",
+            TrainState::Running => "running",
+            TrainState::Completed => "completed",
+            TrainState::Failed => "failed",
+        }
+    }
+}
+
+/// Progress snapshot written by `train --status-file` and polled by the tools TUI.
+///
+/// `epoch` is the zero-based epoch in progress; `step` counts optimizer steps across epochs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrainStatus {
+    #[serde(rename = "status")]
+    pub state: TrainState,
+    pub epoch: usize,
+    pub epochs: usize,
+    pub step: u64,
+    /// Total optimizer steps for the run, when known up front.
+    #[serde(default)]
+    pub total_steps: Option<u64>,
+    pub lr: f64,
+    /// Loss of the most recent step.
+    #[serde(default)]
+    pub loss: Option<f32>,
+    /// Estimated seconds until the run finishes.
+    #[serde(default)]
+    pub eta_secs: Option<f64>,
+    /// Checkpoint path the run writes to.
+    #[serde(default)]
+    pub checkpoint: Option<PathBuf>,
+    /// Failure reason when `state` is `failed`.
+    #[serde(default)]
+    pub error: Option<String>,
+    pub updated_at_unix: f64,
+}
+
+impl TrainStatus {
+    pub fn new(epochs: usize, lr: f64) -> Self {
+        Self {
+            state: TrainState::Starting,
+            epoch: 0,
+            epochs,
+            step: 0,
+            total_steps: None,
+            lr,
+            loss: None,
+            eta_secs: None,
+            checkpoint: None,
+            error: None,
+            updated_at_unix: 0.0,
+        }
+    }
+
+    pub fn read(path: &Path) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        serde_json::from_slice(&data).map_err(io::Error::other)
+    }
+
+    /// Write the status as JSON via a temp file + rename, so readers never see a partial file.
+    pub fn write_atomic(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+        tmp_name.push(".tmp");
+        let tmp = path.with_file_name(tmp_name);
+        let data = serde_json::to_vec_pretty(self).map_err(io::Error::other)?;
+        fs::write(&tmp, data)?;
+        fs::rename(&tmp, path)
+    }
+}
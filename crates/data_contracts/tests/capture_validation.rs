@@ -16,6 +16,7 @@ fn invalid_bbox_norm_rejected() {
             bbox_norm: Some([0.8, 0.2, 0.1, 0.9]),
             source: None,
             source_confidence: None,
+            class_id: 0,
         }],
     };
     let err = meta.validate().unwrap_err();
@@ -38,6 +39,7 @@ fn valid_bbox_passes() {
             bbox_norm: Some([0.1, 0.1, 0.2, 0.2]),
             source: None,
             source_confidence: None,
+            class_id: 0,
         }],
     };
     assert!(meta.validate().is_ok());
@@ -59,9 +61,25 @@ fn provenance_roundtrip_validates() {
             bbox_norm: Some([0.1, 0.1, 0.2, 0.2]),
             source: Some(LabelSource::Model),
             source_confidence: Some(0.75),
+            class_id: 0,
         }],
     };
     let json = serde_json::to_vec(&meta).unwrap();
     let decoded: CaptureMetadata = serde_json::from_slice(&json).unwrap();
     assert!(decoded.validate().is_ok());
 }
+
+#[test]
+fn labels_without_class_id_default_to_class_zero() {
+    let json = r#"{"center_world":[0.0,0.0,0.0],"bbox_px":null,"bbox_norm":[0.1,0.1,0.2,0.2],"source":null,"source_confidence":null}"#;
+    let label: DetectionLabel = serde_json::from_str(json).unwrap();
+    assert_eq!(label.class_id, 0);
+
+    let tagged = DetectionLabel {
+        class_id: 2,
+        ..label
+    };
+    let decoded: DetectionLabel =
+        serde_json::from_str(&serde_json::to_string(&tagged).unwrap()).unwrap();
+    assert_eq!(decoded.class_id, 2);
+}
@@ -16,3 +16,26 @@ fn run_manifest_json_smoke() {
     let manifest: RunManifest = serde_json::from_str(json).expect("valid run manifest JSON");
     manifest.validate().expect("manifest validation");
 }
+
+#[test]
+fn run_manifest_class_names_are_optional() {
+    let json = r#"
+{
+  "schema_version": "V1",
+  "seed": null,
+  "output_root": "artifacts/warehouse",
+  "run_dir": "runs/2026-01-12",
+  "started_at_unix": 0.0,
+  "max_frames": null,
+  "class_names": ["polyp", "instrument", "debris"]
+}
+"#;
+    let manifest: RunManifest = serde_json::from_str(json).expect("valid run manifest JSON");
+    manifest.validate().expect("manifest validation");
+    assert_eq!(manifest.class_name(1), Some("instrument"));
+    assert_eq!(manifest.class_name(3), None);
+
+    let mut blank = manifest.clone();
+    blank.class_names = Some(vec!["polyp".into(), " ".into()]);
+    assert!(blank.validate().is_err());
+}
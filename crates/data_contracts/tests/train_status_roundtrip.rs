@@ -0,0 +1,23 @@
+use data_contracts::{TrainState, TrainStatus};
+
+#[test]
+fn train_status_roundtrips_and_uses_tui_keys() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("logs/train_status.json");
+    let mut status = TrainStatus::new(5, 1e-3);
+    status.state = TrainState::Running;
+    status.epoch = 2;
+    status.step = 40;
+    status.loss = Some(0.25);
+    status.eta_secs = Some(12.0);
+    status.write_atomic(&path).unwrap();
+
+    assert_eq!(TrainStatus::read(&path).unwrap(), status);
+    assert!(!tmp.path().join("logs/train_status.json.tmp").exists());
+
+    let raw: serde_json::Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    assert_eq!(raw["status"], "running");
+    for key in ["epoch", "epochs", "step", "lr", "loss"] {
+        assert!(raw.get(key).is_some(), "missing {key}");
+    }
+}
@@ -1,10 +1,14 @@
-use crate::{InferenceBackend, InferenceModel, InferenceModelConfig};
-use burn::module::Module;
-use burn::tensor::TensorData;
-#[cfg(feature = "convolutional_detector")]
-use data_contracts::preprocess::{stats_from_rgba_u8, ImageStats};
-use std::path::Path;
+use crate::postprocess::{argmax, decode_slots};
+use crate::{InferenceBackend, InferenceModel};
+use burn::tensor::{Tensor, TensorData};
+use data_contracts::preprocess::{chw_from_rgba_u8, stats_from_rgba_u8, ImageStats};
+use models::calibration::Calibration;
+use models::checkpoint::ModelKind;
+use models::input::linear_input;
+use models::input::{FEATURE_DIM, INPUT_GRID};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use vision_core::ensemble::{EnsembleDetector, FusionConfig};
 use vision_core::interfaces::{DetectionResult, Detector, Frame};
 
 /// Thresholds for inference (objectness + IoU).
@@ -40,79 +44,268 @@ impl Detector for HeuristicDetector {
             confidence,
             boxes: Vec::new(),
             scores: Vec::new(),
+            classes: Vec::new(),
         }
     }
 }
 
-struct BurnDetector {
+type Device = <InferenceBackend as burn::tensor::backend::Backend>::Device;
+
+/// Detector backed by a Burn checkpoint; the architecture is chosen at load time.
+///
+/// With a `Calibration`, multibox scores are calibrated before the objectness threshold and NMS,
+/// so thresholds refer to calibrated probabilities; linear classifier scores are never
+/// calibrated. `detect_batch` stacks same-sized frames into one forward pass.
+pub struct BurnDetector {
     model: Arc<Mutex<InferenceModel<InferenceBackend>>>,
+    device: Device,
+    kind: ModelKind,
     obj_thresh: f32,
-    #[allow(dead_code)]
     iou_thresh: f32,
+    calibration: Option<Calibration>,
 }
 
 impl BurnDetector {
-    fn frame_to_tensor(&self, _frame: &Frame) -> TensorData {
-        #[cfg(feature = "convolutional_detector")]
-        {
-            let (w, h) = _frame.size;
-            let stats = if let Some(rgba) = &_frame.rgba {
-                stats_from_rgba_u8(w, h, rgba).unwrap_or_else(|_| ImageStats {
-                    mean: [0.0; 3],
-                    std: [0.0; 3],
-                    aspect: w as f32 / h as f32,
-                })
-            } else {
-                ImageStats {
-                    mean: [0.0; 3],
-                    std: [0.0; 3],
-                    aspect: w as f32 / h as f32,
-                }
-            };
-
-            let mut input = Vec::with_capacity(12);
-            input.extend_from_slice(&[0.0, 0.0, 1.0, 1.0]);
-            input.extend_from_slice(&stats.feature_vector(0.0));
-            TensorData::new(input, [1, 12])
+    pub fn new(model: InferenceModel<InferenceBackend>, thresh: InferenceThresholds) -> Self {
+        Self {
+            kind: model.kind(),
+            model: Arc::new(Mutex::new(model)),
+            device: Device::default(),
+            obj_thresh: thresh.objectness_threshold,
+            iou_thresh: thresh.iou_threshold,
+            calibration: None,
         }
-        #[cfg(not(feature = "convolutional_detector"))]
-        {
-            TensorData::new(vec![0.0, 0.0, 1.0, 1.0], [1, 4])
+    }
+
+    /// Calibrate multibox scores with `calibration` (usually the checkpoint's).
+    pub fn with_calibration(mut self, calibration: Option<Calibration>) -> Self {
+        self.calibration = calibration;
+        self
+    }
+
+    pub fn calibration(&self) -> Option<Calibration> {
+        self.calibration
+    }
+
+    /// Architecture of the loaded checkpoint.
+    pub fn kind(&self) -> ModelKind {
+        self.kind
+    }
+
+    /// Run `model` on `frames.len()` stacked frames and decode one result per frame.
+    fn forward(
+        &self,
+        model: &InferenceModel<InferenceBackend>,
+        images: Tensor<InferenceBackend, 4>,
+        features: Tensor<InferenceBackend, 2>,
+        frames: &[&Frame],
+    ) -> Vec<DetectionResult> {
+        match model {
+            InferenceModel::Multibox(model) => {
+                let (pred_boxes, pred_scores, class_logits) =
+                    model.forward_multibox_images_with_classes(images, features);
+                let slots = pred_scores.dims()[1];
+                let num_classes = class_logits.dims()[2];
+                let pred_boxes = pred_boxes.into_data().to_vec::<f32>().unwrap_or_default();
+                let class_logits = class_logits.into_data().to_vec::<f32>().unwrap_or_default();
+                let mut pred_scores = pred_scores.into_data().to_vec::<f32>().unwrap_or_default();
+                if let Some(calibration) = &self.calibration {
+                    calibration.apply_all(&mut pred_scores);
+                }
+                frames
+                    .iter()
+                    .zip(
+                        pred_boxes
+                            .chunks_exact(slots * 4)
+                            .zip(pred_scores.chunks_exact(slots))
+                            .zip(class_logits.chunks_exact(slots * num_classes)),
+                    )
+                    .map(|(frame, ((frame_boxes, frame_scores), frame_logits))| {
+                        let kept = decode_slots(
+                            frame_boxes,
+                            frame_scores,
+                            self.obj_thresh,
+                            self.iou_thresh,
+                        );
+                        let boxes: Vec<[f32; 4]> = kept
+                            .iter()
+                            .map(|&s| {
+                                let b = &frame_boxes[s * 4..s * 4 + 4];
+                                [b[0], b[1], b[2], b[3]]
+                            })
+                            .collect();
+                        let scores = kept.iter().map(|&s| frame_scores[s]).collect();
+                        let classes = kept
+                            .iter()
+                            .map(|&s| argmax(&frame_logits[s * num_classes..(s + 1) * num_classes]))
+                            .collect();
+                        // Report the best slot even when nothing clears the threshold.
+                        let confidence = frame_scores.iter().copied().fold(0.0f32, f32::max);
+                        DetectionResult {
+                            frame_id: frame.id,
+                            positive: !boxes.is_empty(),
+                            confidence,
+                            boxes,
+                            scores,
+                            classes,
+                        }
+                    })
+                    .collect()
+            }
+            InferenceModel::LinearClassifier(model) => {
+                drop(images);
+                let logits = model.forward(linear_input(features));
+                let scores = logits.into_data().to_vec::<f32>().unwrap_or_default();
+                frames
+                    .iter()
+                    .zip(scores)
+                    .map(|(frame, confidence)| DetectionResult {
+                        frame_id: frame.id,
+                        positive: confidence >= self.obj_thresh,
+                        confidence,
+                        boxes: Vec::new(),
+                        scores: vec![confidence],
+                        classes: Vec::new(),
+                    })
+                    .collect()
+            }
         }
     }
 }
 
+/// A frame decoded into the CHW image and stats row that training's `collate` produces.
+struct DecodedFrame {
+    chw: Vec<f32>,
+    /// `[height, width]` of `chw`.
+    dims: [usize; 2],
+    features: [f32; FEATURE_DIM],
+}
+
+/// Decode a frame's pixels and image stats. Frames without pixels yield a black image with the
+/// frame's aspect ratio.
+fn decode_frame(frame: &Frame) -> DecodedFrame {
+    let (w, h) = frame.size;
+    let decoded = frame.rgba.as_ref().and_then(|rgba| {
+        let stats = stats_from_rgba_u8(w, h, rgba).ok()?;
+        let chw = chw_from_rgba_u8(w, h, rgba).ok()?;
+        Some((stats, chw, [h as usize, w as usize]))
+    });
+    let (stats, chw, dims) = decoded.unwrap_or_else(|| {
+        let stats = ImageStats {
+            mean: [0.0; 3],
+            std: [0.0; 3],
+            aspect: w as f32 / h.max(1) as f32,
+        };
+        (
+            stats,
+            vec![0.0; 3 * INPUT_GRID * INPUT_GRID],
+            [INPUT_GRID, INPUT_GRID],
+        )
+    });
+    DecodedFrame {
+        chw,
+        dims,
+        features: stats.feature_vector(0.0),
+    }
+}
+
+/// Stack same-sized decoded frames into `[N, 3, H, W]` images and `[N, FEATURE_DIM]` stats.
+fn stack_frames(
+    frames: &[&DecodedFrame],
+    device: &Device,
+) -> (Tensor<InferenceBackend, 4>, Tensor<InferenceBackend, 2>) {
+    let n = frames.len();
+    let [h, w] = frames.first().map_or([INPUT_GRID, INPUT_GRID], |f| f.dims);
+    let chw: Vec<f32> = frames.iter().flat_map(|f| f.chw.iter().copied()).collect();
+    let features: Vec<f32> = frames.iter().flat_map(|f| f.features).collect();
+    let images =
+        Tensor::<InferenceBackend, 4>::from_data(TensorData::new(chw, [n, 3, h, w]), device);
+    let features = Tensor::<InferenceBackend, 2>::from_data(
+        TensorData::new(features, [n, FEATURE_DIM]),
+        device,
+    );
+    (images, features)
+}
+
 impl Detector for BurnDetector {
     fn detect(&mut self, frame: &Frame) -> DetectionResult {
-        let input = self.frame_to_tensor(frame);
-        let device = <InferenceBackend as burn::tensor::backend::Backend>::Device::default();
+        self.detect_batch(std::slice::from_ref(frame))
+            .pop()
+            .expect("one result per frame")
+    }
+
+    fn detect_batch(&mut self, frames: &[Frame]) -> Vec<DetectionResult> {
+        // Inputs come from the same `models::input` path used in training.
+        let decoded: Vec<DecodedFrame> = frames.iter().map(decode_frame).collect();
+        // Frames of the same size share one forward pass.
+        let mut groups: Vec<([usize; 2], Vec<usize>)> = Vec::new();
+        for (i, frame) in decoded.iter().enumerate() {
+            match groups.iter_mut().find(|(dims, _)| *dims == frame.dims) {
+                Some((_, members)) => members.push(i),
+                None => groups.push((frame.dims, vec![i])),
+            }
+        }
+
+        let mut results: Vec<Option<DetectionResult>> = vec![None; frames.len()];
         let model = self.model.lock().expect("model mutex poisoned");
-        let logits = model.forward(burn::tensor::Tensor::<InferenceBackend, 2>::from_data(
-            input, &device,
-        ));
-        let scores = logits.into_data().to_vec::<f32>().unwrap_or_default();
-        let confidence = scores.first().copied().unwrap_or(0.0);
-        DetectionResult {
-            frame_id: frame.id,
-            positive: confidence >= self.obj_thresh,
-            confidence,
-            boxes: Vec::new(),
-            scores,
+        for (_, members) in groups {
+            let group: Vec<&DecodedFrame> = members.iter().map(|&i| &decoded[i]).collect();
+            let (images, features) = stack_frames(&group, &self.device);
+            let group_frames: Vec<&Frame> = members.iter().map(|&i| &frames[i]).collect();
+            let outputs = self.forward(&model, images, features, &group_frames);
+            for (i, result) in members.into_iter().zip(outputs) {
+                results[i] = Some(result);
+            }
         }
+        // A forward pass whose outputs could not be read back yields fewer results than frames;
+        // those frames are reported as empty negatives.
+        results
+            .into_iter()
+            .zip(frames)
+            .map(|(result, frame)| {
+                result.unwrap_or_else(|| DetectionResult {
+                    frame_id: frame.id,
+                    positive: false,
+                    confidence: 0.0,
+                    boxes: Vec::new(),
+                    scores: Vec::new(),
+                    classes: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    fn set_thresholds(&mut self, obj: f32, iou: f32) {
+        self.obj_thresh = obj;
+        self.iou_thresh = iou;
     }
 }
 
-/// Factory that will load Burn checkpoints when available; currently returns heuristic.
+/// Builds detectors from Burn checkpoints, falling back to a heuristic detector.
+///
+/// The model architecture is a runtime choice: it is read from the checkpoint's metadata sidecar,
+/// optionally constrained by an explicit `ModelKind`.
 pub struct InferenceFactory;
 
 impl InferenceFactory {
+    /// Build a detector for whatever architecture the checkpoint describes.
     pub fn build(
         &self,
         thresh: InferenceThresholds,
         weights: Option<&Path>,
     ) -> Box<dyn vision_core::interfaces::Detector + Send + Sync> {
-        if let Some(det) = self.try_load_burn_detector(thresh, weights) {
-            return det;
+        self.build_with_kind(thresh, weights, None)
+    }
+
+    /// Like `build`, but rejects checkpoints that are not of `kind` (when given).
+    pub fn build_with_kind(
+        &self,
+        thresh: InferenceThresholds,
+        weights: Option<&Path>,
+        kind: Option<ModelKind>,
+    ) -> Box<dyn vision_core::interfaces::Detector + Send + Sync> {
+        if let Some(det) = self.try_load_burn_detector(thresh, weights, kind) {
+            return Box::new(det);
         }
         eprintln!("InferenceFactory: no valid checkpoint provided; using heuristic detector.");
         Box::new(HeuristicDetector {
@@ -120,38 +313,74 @@ impl InferenceFactory {
         })
     }
 
+    /// Load a Burn-backed detector, surfacing load errors instead of falling back.
+    ///
+    /// A calibration stored in the checkpoint's metadata is applied to multibox scores.
+    pub fn load(
+        &self,
+        thresh: InferenceThresholds,
+        weights: &Path,
+        kind: Option<ModelKind>,
+    ) -> anyhow::Result<BurnDetector> {
+        let device = Device::default();
+        let (model, meta) = InferenceModel::<InferenceBackend>::load(weights, kind, &device)
+            .map_err(|e| e.context(format!("failed to load checkpoint {}", weights.display())))?;
+        Ok(BurnDetector::new(model, thresh).with_calibration(meta.calibration))
+    }
+
+    /// Like `load`, but a checkpoint without a metadata sidecar loads as `kind` with that kind's
+    /// default hyperparameters (`InferenceModel::load_legacy`). Only for explicit opt-ins.
+    pub fn load_legacy(
+        &self,
+        thresh: InferenceThresholds,
+        weights: &Path,
+        kind: ModelKind,
+    ) -> anyhow::Result<BurnDetector> {
+        let device = Device::default();
+        let (model, meta) = InferenceModel::<InferenceBackend>::load_legacy(weights, kind, &device)
+            .map_err(|e| e.context(format!("failed to load checkpoint {}", weights.display())))?;
+        Ok(BurnDetector::new(model, thresh).with_calibration(meta.calibration))
+    }
+
+    /// Load one detector per checkpoint and fuse their outputs; each entry is a checkpoint and
+    /// its fusion weight.
+    pub fn load_ensemble(
+        &self,
+        thresh: InferenceThresholds,
+        members: &[(PathBuf, f32)],
+        fusion: FusionConfig,
+    ) -> anyhow::Result<EnsembleDetector> {
+        if members.is_empty() {
+            anyhow::bail!("an ensemble needs at least one checkpoint");
+        }
+        let mut ensemble = EnsembleDetector::new(fusion);
+        for (weights, weight) in members {
+            if !weight.is_finite() || *weight <= 0.0 {
+                anyhow::bail!(
+                    "ensemble weight for {} must be positive (got {weight})",
+                    weights.display()
+                );
+            }
+            let detector = self.load(thresh, weights, None)?;
+            ensemble = ensemble.with_member(Box::new(detector), *weight);
+        }
+        Ok(ensemble)
+    }
+
     fn try_load_burn_detector(
         &self,
         thresh: InferenceThresholds,
         weights: Option<&Path>,
-    ) -> Option<Box<dyn vision_core::interfaces::Detector + Send + Sync>> {
+        kind: Option<ModelKind>,
+    ) -> Option<BurnDetector> {
         let path = weights?;
         if !path.exists() {
             return None;
         }
-        let device = <InferenceBackend as burn::tensor::backend::Backend>::Device::default();
-        let recorder = burn::record::BinFileRecorder::<burn::record::FullPrecisionSettings>::new();
-        #[cfg(feature = "convolutional_detector")]
-        let config = InferenceModelConfig {
-            input_dim: Some(4 + 8),
-            ..Default::default()
-        };
-        #[cfg(not(feature = "convolutional_detector"))]
-        let config = InferenceModelConfig::default();
-
-        match InferenceModel::<InferenceBackend>::new(config, &device)
-            .load_file(path, &recorder, &device)
-        {
-            Ok(model) => Some(Box::new(BurnDetector {
-                model: Arc::new(Mutex::new(model)),
-                obj_thresh: thresh.objectness_threshold,
-                iou_thresh: thresh.iou_threshold,
-            })),
+        match self.load(thresh, path, kind) {
+            Ok(det) => Some(det),
             Err(err) => {
-                eprintln!(
-                    "Failed to load detector checkpoint {:?}: {err}. Falling back to heuristic.",
-                    path
-                );
+                eprintln!("{err:#}. Falling back to heuristic.");
                 None
             }
         }
@@ -9,34 +9,45 @@
 //! - Default: Falls back to NdArray CPU backend.
 //!
 //! ## Model Selection
-//! - `convolutional_detector`: Uses `MultiboxModel` for multi-box detection.
-//! - Default: Uses `LinearClassifier` for binary classification.
+//! The architecture is chosen at runtime, not by cargo features: `InferenceModel` is an enum over
+//! `LinearClassifier` and `MultiboxModel`, and `InferenceFactory` picks the variant from the
+//! checkpoint's metadata sidecar (optionally constrained to an explicit `ModelKind`). Multibox
+//! outputs are filtered by objectness and class-agnostic NMS (`postprocess`). The
+//! `linear_detector`/`convolutional_detector` features are kept for compatibility and no longer
+//! change which models can be served.
 //!
-//! Type aliases `InferenceModel` and `InferenceModelConfig` adapt to the selected features.
+//! ## Ensembles
+//! `InferenceFactory::load_ensemble` wraps several checkpoints in a `vision_core`
+//! `EnsembleDetector`, which fuses their boxes with NMS, soft-NMS, or weighted box fusion.
+//!
+//! ## Test-Time Augmentation
+//! `TtaDetector` wraps any detector, runs it on mirrored and rescaled copies of each frame
+//! (the `burn_dataset::aug` flip/scale math), maps the boxes back, and fuses them the same way.
 
 #![recursion_limit = "256"]
 
 pub mod factory;
+pub mod model;
+pub mod postprocess;
+pub mod tta;
 
 #[cfg(feature = "backend-wgpu")]
 pub type InferenceBackend = burn_wgpu::Wgpu<f32>;
 #[cfg(not(feature = "backend-wgpu"))]
 pub type InferenceBackend = burn_ndarray::NdArray<f32>;
 
-#[cfg(feature = "convolutional_detector")]
-pub type InferenceModel<B> = models::MultiboxModel<B>;
-#[cfg(feature = "convolutional_detector")]
-pub type InferenceModelConfig = models::MultiboxModelConfig;
-#[cfg(not(feature = "convolutional_detector"))]
-pub type InferenceModel<B> = models::LinearClassifier<B>;
-#[cfg(not(feature = "convolutional_detector"))]
-pub type InferenceModelConfig = models::LinearClassifierConfig;
-
-pub use factory::{InferenceFactory, InferenceThresholds};
+pub use factory::{BurnDetector, InferenceFactory, InferenceThresholds};
+pub use model::{InferenceModel, InferenceModelConfig};
+pub use models::checkpoint::{CheckpointMetadata, ModelKind};
+pub use tta::{TtaConfig, TtaDetector};
+pub use vision_core::ensemble::{EnsembleDetector, FusionConfig, FusionMethod};
 
 pub mod prelude {
-    pub use crate::factory::{InferenceFactory, InferenceThresholds};
-    pub use crate::{InferenceBackend, InferenceModel, InferenceModelConfig};
+    pub use crate::factory::{BurnDetector, InferenceFactory, InferenceThresholds};
+    pub use crate::{
+        EnsembleDetector, FusionConfig, FusionMethod, InferenceBackend, InferenceModel,
+        InferenceModelConfig, ModelKind, TtaConfig, TtaDetector,
+    };
 }
 
 #[cfg(test)]
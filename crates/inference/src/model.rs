@@ -0,0 +1,89 @@
+//! Runtime-dispatched Burn models.
+//!
+//! A single binary can serve any architecture in `models`: the variant is picked when a
+//! checkpoint is loaded, from its metadata sidecar or from an explicit `ModelKind`.
+
+use std::path::Path;
+
+use burn::module::Module;
+use burn::record::{BinFileRecorder, FullPrecisionSettings};
+use burn::tensor::backend::Backend;
+use models::checkpoint::{CheckpointMetadata, ModelConfig, ModelKind};
+use models::{LinearClassifier, MultiboxModel};
+
+/// Config accepted by `InferenceModel::new` (tagged by model kind).
+pub type InferenceModelConfig = ModelConfig;
+
+/// A loaded detector model of any supported architecture.
+///
+/// Models are long-lived and held behind a mutex, so variant size does not matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum InferenceModel<B: Backend> {
+    LinearClassifier(LinearClassifier<B>),
+    Multibox(MultiboxModel<B>),
+}
+
+impl<B: Backend> InferenceModel<B> {
+    /// Build a freshly initialized model for `config`.
+    pub fn new(config: InferenceModelConfig, device: &B::Device) -> Self {
+        match config {
+            ModelConfig::LinearClassifier(cfg) => {
+                Self::LinearClassifier(LinearClassifier::new(cfg, device))
+            }
+            ModelConfig::Multibox(cfg) => Self::Multibox(MultiboxModel::new(cfg, device)),
+        }
+    }
+
+    pub fn kind(&self) -> ModelKind {
+        match self {
+            Self::LinearClassifier(_) => ModelKind::LinearClassifier,
+            Self::Multibox(_) => ModelKind::Multibox,
+        }
+    }
+
+    /// Load a checkpoint, rebuilding the architecture described by its metadata sidecar, and
+    /// return the model with that metadata.
+    ///
+    /// With `expected` set, checkpoints of any other kind are rejected. A checkpoint without a
+    /// sidecar is an error.
+    pub fn load(
+        path: &Path,
+        expected: Option<ModelKind>,
+        device: &B::Device,
+    ) -> anyhow::Result<(Self, CheckpointMetadata)> {
+        let meta = CheckpointMetadata::load(path)?;
+        if let Some(kind) = expected {
+            meta.expect_kind(kind)?;
+        }
+        Ok((Self::load_records(path, &meta, device)?, meta))
+    }
+
+    /// Like `load`, but a checkpoint written before sidecars existed loads as `kind` with that
+    /// kind's default hyperparameters. Only for callers that opt in explicitly.
+    pub fn load_legacy(
+        path: &Path,
+        kind: ModelKind,
+        device: &B::Device,
+    ) -> anyhow::Result<(Self, CheckpointMetadata)> {
+        let meta = CheckpointMetadata::load_or_legacy(path, kind)?;
+        meta.expect_kind(kind)?;
+        Ok((Self::load_records(path, &meta, device)?, meta))
+    }
+
+    /// Weights from `path` in the architecture `meta` describes.
+    fn load_records(
+        path: &Path,
+        meta: &CheckpointMetadata,
+        device: &B::Device,
+    ) -> anyhow::Result<Self> {
+        let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+        let model = match Self::new(meta.model.clone(), device) {
+            Self::LinearClassifier(m) => {
+                Self::LinearClassifier(m.load_file(path, &recorder, device)?)
+            }
+            Self::Multibox(m) => Self::Multibox(m.load_file(path, &recorder, device)?),
+        };
+        Ok(model)
+    }
+}
@@ -0,0 +1,70 @@
+//! Detection post-processing: objectness filtering and class-agnostic NMS.
+//!
+//! Boxes are normalized `[x0, y0, x1, y1]` in 0..1, matching `DetectionResult::boxes`.
+
+pub use vision_core::boxes::{iou_xyxy, nms};
+
+/// Decode flat multibox outputs for one image into sorted, NMS-filtered detections.
+///
+/// `boxes` holds `scores.len() * 4` values laid out as `[x0, y0, x1, y1]` per slot. Slots with a
+/// score below `objectness_threshold` are dropped before NMS. Returns boxes and their aligned
+/// scores, highest score first.
+pub fn decode_detections(
+    boxes: &[f32],
+    scores: &[f32],
+    objectness_threshold: f32,
+    iou_threshold: f32,
+) -> (Vec<[f32; 4]>, Vec<f32>) {
+    let slots = decode_slots(boxes, scores, objectness_threshold, iou_threshold);
+    let out_boxes = slots
+        .iter()
+        .map(|&s| {
+            [
+                boxes[s * 4],
+                boxes[s * 4 + 1],
+                boxes[s * 4 + 2],
+                boxes[s * 4 + 3],
+            ]
+        })
+        .collect();
+    let out_scores = slots.iter().map(|&s| scores[s]).collect();
+    (out_boxes, out_scores)
+}
+
+/// The slots [`decode_detections`] keeps, highest score first, for callers that carry other
+/// per-slot outputs (e.g. class logits) along.
+pub fn decode_slots(
+    boxes: &[f32],
+    scores: &[f32],
+    objectness_threshold: f32,
+    iou_threshold: f32,
+) -> Vec<usize> {
+    let mut cand_slots = Vec::new();
+    let mut cand_boxes = Vec::new();
+    let mut cand_scores = Vec::new();
+    for (slot, &score) in scores.iter().enumerate() {
+        if score.is_nan() || score < objectness_threshold {
+            continue;
+        }
+        let Some(b) = boxes.get(slot * 4..slot * 4 + 4) else {
+            break;
+        };
+        cand_slots.push(slot);
+        cand_boxes.push([b[0], b[1], b[2], b[3]]);
+        cand_scores.push(score);
+    }
+
+    nms(&cand_boxes, &cand_scores, iou_threshold)
+        .into_iter()
+        .map(|i| cand_slots[i])
+        .collect()
+}
+
+/// Index of the largest logit (0 for an empty slice).
+pub fn argmax(logits: &[f32]) -> u32 {
+    logits
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map_or(0, |(i, _)| i as u32)
+}
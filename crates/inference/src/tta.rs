@@ -0,0 +1,119 @@
+//! Test-time augmentation: run a detector on flipped and rescaled copies of each frame and fuse
+//! the boxes mapped back onto the original frame.
+
+use burn_dataset::aug::{tta_transforms, TtaTransform};
+use image::RgbaImage;
+use vision_core::ensemble::{fuse_results, FusionConfig};
+use vision_core::interfaces::{DetectionResult, Detector, Frame};
+
+/// Which variants a [`TtaDetector`] runs and how their detections are fused.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TtaConfig {
+    /// Also run every scale mirrored left-to-right.
+    pub hflip: bool,
+    /// Scales to run (1.0 is the original frame); empty means `[1.0]`.
+    pub scales: Vec<f32>,
+    pub fusion: FusionConfig,
+}
+
+impl Default for TtaConfig {
+    fn default() -> Self {
+        Self {
+            hflip: true,
+            scales: vec![1.0],
+            fusion: FusionConfig::default(),
+        }
+    }
+}
+
+/// Wraps a detector with test-time augmentation.
+///
+/// Every variant of every frame goes through the inner detector in one `detect_batch` call; the
+/// variants' boxes are mapped back and fused with equal weight. Frames without pixels are
+/// passed through once, unaugmented.
+pub struct TtaDetector {
+    inner: Box<dyn Detector + Send + Sync>,
+    transforms: Vec<TtaTransform>,
+    fusion: FusionConfig,
+}
+
+impl TtaDetector {
+    /// Fails when a scale is not finite and positive.
+    pub fn new(inner: Box<dyn Detector + Send + Sync>, config: TtaConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            inner,
+            transforms: tta_transforms(config.hflip, &config.scales)?,
+            fusion: config.fusion,
+        })
+    }
+
+    /// Variants run per frame.
+    pub fn transforms(&self) -> &[TtaTransform] {
+        &self.transforms
+    }
+
+    /// The frame's pixels as an image, if it has a complete RGBA buffer.
+    fn image(frame: &Frame) -> Option<RgbaImage> {
+        let (w, h) = frame.size;
+        RgbaImage::from_raw(w, h, frame.rgba.clone()?)
+    }
+}
+
+impl Detector for TtaDetector {
+    fn detect(&mut self, frame: &Frame) -> DetectionResult {
+        self.detect_batch(std::slice::from_ref(frame))
+            .pop()
+            .expect("one result per frame")
+    }
+
+    fn detect_batch(&mut self, frames: &[Frame]) -> Vec<DetectionResult> {
+        // (frame index, transform applied) for every inner frame, in order.
+        let mut origins: Vec<(usize, TtaTransform)> = Vec::new();
+        let mut variants: Vec<Frame> = Vec::new();
+        for (i, frame) in frames.iter().enumerate() {
+            match Self::image(frame) {
+                Some(img) => {
+                    for transform in &self.transforms {
+                        origins.push((i, *transform));
+                        variants.push(Frame {
+                            id: frame.id,
+                            timestamp: frame.timestamp,
+                            rgba: Some(transform.apply(&img).into_raw()),
+                            size: frame.size,
+                            path: frame.path.clone(),
+                        });
+                    }
+                }
+                None => {
+                    origins.push((i, TtaTransform::IDENTITY));
+                    variants.push(frame.clone());
+                }
+            }
+        }
+
+        let mut per_frame: Vec<Vec<DetectionResult>> = vec![Vec::new(); frames.len()];
+        for ((i, transform), mut result) in
+            origins.into_iter().zip(self.inner.detect_batch(&variants))
+        {
+            transform.invert_boxes(&mut result.boxes, frames[i].size);
+            per_frame[i].push(result);
+        }
+        frames
+            .iter()
+            .zip(per_frame)
+            .map(|(frame, mut results)| {
+                if results.len() == 1 {
+                    return results.pop().expect("one result");
+                }
+                let weighted: Vec<(&DetectionResult, f32)> =
+                    results.iter().map(|r| (r, 1.0)).collect();
+                fuse_results(frame.id, &weighted, &self.fusion)
+            })
+            .collect()
+    }
+
+    /// Forwarded to the inner detector; the fusion IoU stays as configured.
+    fn set_thresholds(&mut self, obj: f32, iou: f32) {
+        self.inner.set_thresholds(obj, iou);
+    }
+}
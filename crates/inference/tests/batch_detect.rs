@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+use burn::module::Module;
+use burn::record::{BinFileRecorder, FullPrecisionSettings};
+use inference::prelude::{
+    FusionConfig, InferenceBackend, InferenceFactory, InferenceModel, InferenceThresholds,
+};
+use models::checkpoint::{CheckpointMetadata, ModelConfig};
+use models::{LinearClassifierConfig, MultiboxModelConfig};
+use vision_core::interfaces::{Detector, Frame};
+
+fn write_checkpoint(dir: &Path, name: &str, config: ModelConfig) -> PathBuf {
+    let ckpt = dir.join(name);
+    let device = <InferenceBackend as burn::tensor::backend::Backend>::Device::default();
+    let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+    match InferenceModel::<InferenceBackend>::new(config.clone(), &device) {
+        InferenceModel::LinearClassifier(m) => m.save_file(&ckpt, &recorder).unwrap(),
+        InferenceModel::Multibox(m) => m.save_file(&ckpt, &recorder).unwrap(),
+    }
+    CheckpointMetadata::new(config, None, "test")
+        .save(&ckpt)
+        .unwrap();
+    ckpt
+}
+
+/// Frames of two sizes, interleaved, plus one without pixels.
+fn frames() -> Vec<Frame> {
+    let frame = |id: u64, (w, h): (u32, u32), shade: u8| Frame {
+        id,
+        timestamp: 0.0,
+        rgba: Some(
+            (0..w * h * 4)
+                .map(|i| shade.wrapping_add(i as u8))
+                .collect(),
+        ),
+        size: (w, h),
+        path: None,
+    };
+    vec![
+        frame(10, (6, 4), 10),
+        frame(11, (8, 8), 60),
+        frame(12, (6, 4), 200),
+        Frame {
+            id: 13,
+            timestamp: 0.0,
+            rgba: None,
+            size: (6, 4),
+            path: None,
+        },
+        frame(14, (8, 8), 120),
+    ]
+}
+
+fn assert_matches_per_frame(detector: &mut dyn Detector) {
+    let frames = frames();
+    let batched = detector.detect_batch(&frames);
+    assert_eq!(batched.len(), frames.len());
+    for (frame, result) in frames.iter().zip(&batched) {
+        let single = detector.detect(frame);
+        assert_eq!(result.frame_id, frame.id);
+        assert_eq!(result.positive, single.positive);
+        assert!((result.confidence - single.confidence).abs() < 1e-5);
+        assert_eq!(result.boxes.len(), single.boxes.len());
+        for (a, b) in result.boxes.iter().zip(&single.boxes) {
+            assert!(a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-5));
+        }
+        for (a, b) in result.scores.iter().zip(&single.scores) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+}
+
+#[test]
+fn burn_detector_batches_match_per_frame_results() {
+    let tmp = tempfile::tempdir().unwrap();
+    let thresh = InferenceThresholds {
+        objectness_threshold: 0.0,
+        iou_threshold: 0.5,
+    };
+    let multibox = write_checkpoint(
+        tmp.path(),
+        "multibox.bin",
+        ModelConfig::Multibox(MultiboxModelConfig {
+            max_boxes: 4,
+            input_dim: Some(models::input::MULTIBOX_INPUT_DIM),
+            ..Default::default()
+        }),
+    );
+    let linear = write_checkpoint(
+        tmp.path(),
+        "linear.bin",
+        ModelConfig::LinearClassifier(LinearClassifierConfig::default()),
+    );
+    for ckpt in [multibox, linear] {
+        let mut detector = InferenceFactory.load(thresh, &ckpt, None).unwrap();
+        assert_matches_per_frame(&mut detector);
+    }
+}
+
+#[test]
+fn default_detect_batch_runs_each_frame() {
+    let mut detector = InferenceFactory.build(InferenceThresholds::default(), None);
+    let ids: Vec<u64> = detector
+        .detect_batch(&frames())
+        .iter()
+        .map(|r| r.frame_id)
+        .collect();
+    assert_eq!(ids, vec![10, 11, 12, 13, 14]);
+    assert!(detector.detect_batch(&[]).is_empty());
+}
+
+#[test]
+fn ensembles_load_every_member_and_reject_bad_weights() {
+    let tmp = tempfile::tempdir().unwrap();
+    let thresh = InferenceThresholds {
+        objectness_threshold: 0.0,
+        iou_threshold: 0.5,
+    };
+    let config = ModelConfig::Multibox(MultiboxModelConfig {
+        max_boxes: 4,
+        input_dim: Some(models::input::MULTIBOX_INPUT_DIM),
+        ..Default::default()
+    });
+    let a = write_checkpoint(tmp.path(), "a.bin", config.clone());
+    let b = write_checkpoint(tmp.path(), "b.bin", config);
+
+    let members = vec![(a.clone(), 1.0), (b.clone(), 2.0)];
+    let mut ensemble = InferenceFactory
+        .load_ensemble(thresh, &members, FusionConfig::default())
+        .unwrap();
+    assert_eq!(ensemble.len(), 2);
+    let results = ensemble.detect_batch(&frames());
+    assert_eq!(results.len(), 5);
+    for result in &results {
+        assert_eq!(result.boxes.len(), result.scores.len());
+    }
+
+    assert!(InferenceFactory
+        .load_ensemble(thresh, &[], FusionConfig::default())
+        .is_err());
+    assert!(InferenceFactory
+        .load_ensemble(thresh, &[(a, 1.0), (b, 0.0)], FusionConfig::default())
+        .is_err());
+}
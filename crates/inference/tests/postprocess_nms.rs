@@ -0,0 +1,83 @@
+use inference::postprocess::{decode_detections, iou_xyxy, nms};
+
+#[test]
+fn nms_suppresses_overlaps_and_sorts_by_score() {
+    let boxes = [
+        [0.10, 0.10, 0.50, 0.50],
+        [0.12, 0.12, 0.52, 0.52], // heavy overlap with 0
+        [0.60, 0.60, 0.90, 0.90], // disjoint
+    ];
+    let scores = [0.6, 0.9, 0.7];
+    let keep = nms(&boxes, &scores, 0.5);
+    assert_eq!(keep, vec![1, 2]);
+
+    // A permissive IoU threshold keeps everything, still score-ordered.
+    let keep = nms(&boxes, &scores, 0.99);
+    assert_eq!(keep, vec![1, 2, 0]);
+}
+
+#[test]
+fn decode_filters_by_objectness_and_aligns_scores() {
+    let flat_boxes = [
+        0.0, 0.0, 0.2, 0.2, // slot 0
+        0.5, 0.5, 0.8, 0.8, // slot 1
+        0.0, 0.0, 0.21, 0.21, // slot 2 (duplicate of 0)
+        0.3, 0.3, 0.4, 0.4, // slot 3 (below threshold)
+    ];
+    let scores = [0.55, 0.8, 0.6, 0.1];
+    let (boxes, kept_scores) = decode_detections(&flat_boxes, &scores, 0.3, 0.5);
+
+    assert_eq!(boxes.len(), kept_scores.len());
+    assert_eq!(kept_scores, vec![0.8, 0.6]);
+    assert_eq!(boxes[0], [0.5, 0.5, 0.8, 0.8]);
+    assert_eq!(boxes[1], [0.0, 0.0, 0.21, 0.21]);
+    assert!(iou_xyxy(boxes[0], boxes[1]) < 0.5);
+}
+
+#[test]
+fn burn_detector_returns_sorted_boxes() {
+    use burn::module::Module;
+    use burn::record::{BinFileRecorder, FullPrecisionSettings};
+    use inference::prelude::{InferenceFactory, InferenceThresholds};
+    use inference::InferenceBackend;
+    use models::checkpoint::{CheckpointMetadata, ModelConfig};
+    use models::{MultiboxModel, MultiboxModelConfig};
+    use vision_core::interfaces::Frame;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let ckpt = tmp.path().join("multibox.bin");
+    let device = <InferenceBackend as burn::tensor::backend::Backend>::Device::default();
+    let config = MultiboxModelConfig {
+        input_dim: Some(models::input::MULTIBOX_INPUT_DIM),
+        ..Default::default()
+    };
+    MultiboxModel::<InferenceBackend>::new(config.clone(), &device)
+        .save_file(&ckpt, &BinFileRecorder::<FullPrecisionSettings>::new())
+        .unwrap();
+    CheckpointMetadata::new(ModelConfig::Multibox(config), None, "test")
+        .save(&ckpt)
+        .unwrap();
+
+    let mut detector = InferenceFactory.build(
+        InferenceThresholds {
+            objectness_threshold: 0.0,
+            iou_threshold: 0.5,
+        },
+        Some(&ckpt),
+    );
+    let result = detector.detect(&Frame {
+        id: 3,
+        timestamp: 0.0,
+        rgba: Some(vec![128; 8 * 8 * 4]),
+        size: (8, 8),
+        path: None,
+    });
+    assert!(!result.boxes.is_empty());
+    assert_eq!(result.boxes.len(), result.scores.len());
+    assert!(result.scores.windows(2).all(|w| w[0] >= w[1]));
+    assert!(result
+        .boxes
+        .iter()
+        .flatten()
+        .all(|v| (0.0..=1.0).contains(v)));
+}
@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+
+use burn::module::Module;
+use burn::record::{BinFileRecorder, FullPrecisionSettings};
+use inference::prelude::{
+    InferenceBackend, InferenceFactory, InferenceModel, InferenceThresholds, ModelKind,
+};
+use models::checkpoint::{CheckpointMetadata, ModelConfig};
+use models::{LinearClassifierConfig, MultiboxModelConfig};
+use vision_core::interfaces::Frame;
+
+fn write_checkpoint(dir: &Path, name: &str, config: ModelConfig) -> PathBuf {
+    let ckpt = dir.join(name);
+    let device = <InferenceBackend as burn::tensor::backend::Backend>::Device::default();
+    let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+    match InferenceModel::<InferenceBackend>::new(config.clone(), &device) {
+        InferenceModel::LinearClassifier(m) => m.save_file(&ckpt, &recorder).unwrap(),
+        InferenceModel::Multibox(m) => m.save_file(&ckpt, &recorder).unwrap(),
+    }
+    CheckpointMetadata::new(config, None, "test")
+        .save(&ckpt)
+        .unwrap();
+    ckpt
+}
+
+fn frame() -> Frame {
+    Frame {
+        id: 1,
+        timestamp: 0.0,
+        rgba: Some(vec![200; 6 * 4 * 4]),
+        size: (6, 4),
+        path: None,
+    }
+}
+
+#[test]
+fn one_process_serves_both_architectures() {
+    let tmp = tempfile::tempdir().unwrap();
+    let linear = write_checkpoint(
+        tmp.path(),
+        "linear.bin",
+        ModelConfig::LinearClassifier(LinearClassifierConfig::default()),
+    );
+    let multibox = write_checkpoint(
+        tmp.path(),
+        "multibox.bin",
+        ModelConfig::Multibox(MultiboxModelConfig {
+            max_boxes: 4,
+            input_dim: Some(models::input::MULTIBOX_INPUT_DIM),
+            ..Default::default()
+        }),
+    );
+
+    let thresh = InferenceThresholds {
+        objectness_threshold: 0.0,
+        iou_threshold: 0.5,
+    };
+    let factory = InferenceFactory;
+    let mut linear_det = factory.load(thresh, &linear, None).unwrap();
+    let mut multibox_det = factory.load(thresh, &multibox, None).unwrap();
+    assert_eq!(linear_det.kind(), ModelKind::LinearClassifier);
+    assert_eq!(multibox_det.kind(), ModelKind::Multibox);
+
+    use vision_core::interfaces::Detector;
+    let linear_result = linear_det.detect(&frame());
+    assert!(linear_result.boxes.is_empty());
+    assert_eq!(linear_result.scores.len(), 1);
+    let multibox_result = multibox_det.detect(&frame());
+    assert!(!multibox_result.boxes.is_empty());
+    assert!(multibox_result.boxes.len() <= 4);
+}
+
+#[test]
+fn explicit_kind_rejects_other_architectures() {
+    let tmp = tempfile::tempdir().unwrap();
+    let linear = write_checkpoint(
+        tmp.path(),
+        "linear.bin",
+        ModelConfig::LinearClassifier(LinearClassifierConfig::default()),
+    );
+    let factory = InferenceFactory;
+    let thresh = InferenceThresholds::default();
+
+    let err = factory
+        .load(thresh, &linear, Some(ModelKind::Multibox))
+        .err()
+        .expect("kind mismatch should fail");
+    assert!(format!("{err:#}").contains("expected multibox"), "{err:#}");
+    assert!(factory
+        .load(thresh, &linear, Some(ModelKind::LinearClassifier))
+        .is_ok());
+}
+
+#[test]
+fn checkpoints_without_metadata_need_legacy_opt_in() {
+    let tmp = tempfile::tempdir().unwrap();
+    let linear = write_checkpoint(
+        tmp.path(),
+        "linear.bin",
+        ModelConfig::LinearClassifier(LinearClassifierConfig::default()),
+    );
+    std::fs::remove_file(models::checkpoint::sidecar_path(&linear)).unwrap();
+    let factory = InferenceFactory;
+    let thresh = InferenceThresholds::default();
+
+    let err = factory
+        .load(thresh, &linear, Some(ModelKind::LinearClassifier))
+        .err()
+        .expect("missing sidecar should fail");
+    assert!(format!("{err:#}").contains("metadata not found"), "{err:#}");
+    let det = factory
+        .load_legacy(thresh, &linear, ModelKind::LinearClassifier)
+        .unwrap();
+    assert_eq!(det.kind(), ModelKind::LinearClassifier);
+}
@@ -0,0 +1,57 @@
+use burn::module::Module;
+use burn::record::{BinFileRecorder, FullPrecisionSettings};
+use inference::prelude::{InferenceBackend, InferenceFactory, InferenceThresholds};
+use models::calibration::{sigmoid, Calibration};
+use models::checkpoint::{CheckpointMetadata, ModelConfig};
+use models::MultiboxModel;
+use models::MultiboxModelConfig;
+use vision_core::interfaces::{Detector, Frame};
+
+fn frame() -> Frame {
+    Frame {
+        id: 1,
+        timestamp: 0.0,
+        rgba: Some(vec![200; 6 * 4 * 4]),
+        size: (6, 4),
+        path: None,
+    }
+}
+
+#[test]
+fn factory_applies_the_checkpoint_calibration() {
+    let tmp = tempfile::tempdir().unwrap();
+    let ckpt = tmp.path().join("multibox.bin");
+    let config = MultiboxModelConfig {
+        max_boxes: 4,
+        input_dim: Some(models::input::MULTIBOX_INPUT_DIM),
+        ..Default::default()
+    };
+    let device = <InferenceBackend as burn::tensor::backend::Backend>::Device::default();
+    MultiboxModel::<InferenceBackend>::new(config.clone(), &device)
+        .save_file(&ckpt, &BinFileRecorder::<FullPrecisionSettings>::new())
+        .unwrap();
+    let mut meta = CheckpointMetadata::new(ModelConfig::Multibox(config), None, "test");
+    meta.save(&ckpt).unwrap();
+
+    let thresh = InferenceThresholds {
+        objectness_threshold: 0.0,
+        iou_threshold: 0.5,
+    };
+    let factory = InferenceFactory;
+    let mut raw = factory.load(thresh, &ckpt, None).unwrap();
+    assert_eq!(raw.calibration(), None);
+    let raw_scores = raw.detect(&frame()).scores;
+    assert!(!raw_scores.is_empty());
+
+    // A flat Platt map sends every score to sigmoid(-4), whatever the raw value.
+    let calibration = Calibration::Platt { a: 0.0, b: -4.0 };
+    meta.calibration = Some(calibration);
+    meta.save(&ckpt).unwrap();
+    let mut calibrated = factory.load(thresh, &ckpt, None).unwrap();
+    assert_eq!(calibrated.calibration(), Some(calibration));
+    let scores = calibrated.detect(&frame()).scores;
+    assert!(!scores.is_empty());
+    for score in scores {
+        assert!((score - sigmoid(-4.0)).abs() < 1e-5, "{score}");
+    }
+}
@@ -0,0 +1,104 @@
+use inference::prelude::{TtaConfig, TtaDetector};
+use vision_core::interfaces::{DetectionResult, Detector, Frame};
+
+/// Boxes the bright pixels of each frame, so its output follows whatever transform was applied.
+struct BrightBox;
+
+impl Detector for BrightBox {
+    fn detect(&mut self, frame: &Frame) -> DetectionResult {
+        let (w, h) = frame.size;
+        let lit: Vec<(u32, u32)> = frame
+            .rgba
+            .as_deref()
+            .unwrap_or_default()
+            .chunks(4)
+            .enumerate()
+            .filter(|(_, px)| px[0] > 127)
+            .map(|(i, _)| (i as u32 % w, i as u32 / w))
+            .collect();
+        let boxes = if lit.is_empty() {
+            Vec::new()
+        } else {
+            vec![[
+                lit.iter().map(|p| p.0).min().unwrap() as f32 / w as f32,
+                lit.iter().map(|p| p.1).min().unwrap() as f32 / h as f32,
+                (lit.iter().map(|p| p.0).max().unwrap() + 1) as f32 / w as f32,
+                (lit.iter().map(|p| p.1).max().unwrap() + 1) as f32 / h as f32,
+            ]]
+        };
+        DetectionResult {
+            frame_id: frame.id,
+            positive: !boxes.is_empty(),
+            confidence: if boxes.is_empty() { 0.0 } else { 0.9 },
+            scores: vec![0.9; boxes.len()],
+            classes: vec![0; boxes.len()],
+            boxes,
+        }
+    }
+}
+
+fn frame(id: u64) -> Frame {
+    let (w, h) = (40u32, 20u32);
+    let mut rgba = vec![0u8; (w * h * 4) as usize];
+    for y in 5..15 {
+        for x in 4..14 {
+            let i = ((y * w + x) * 4) as usize;
+            rgba[i..i + 4].copy_from_slice(&[255, 255, 255, 255]);
+        }
+    }
+    Frame {
+        id,
+        timestamp: 0.0,
+        rgba: Some(rgba),
+        size: (w, h),
+        path: None,
+    }
+}
+
+#[test]
+fn tta_maps_variant_boxes_back_and_fuses_them() {
+    let config = TtaConfig {
+        scales: vec![1.0, 0.5, 1.25],
+        ..TtaConfig::default()
+    };
+    let mut detector = TtaDetector::new(Box::new(BrightBox), config).unwrap();
+    assert_eq!(detector.transforms().len(), 6);
+
+    let results = detector.detect_batch(&[frame(3), frame(4)]);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[1].frame_id, 4);
+    let result = &results[0];
+    assert!(result.positive);
+    assert_eq!(result.boxes.len(), 1, "variants should fuse into one box");
+    let want = [0.1, 0.25, 0.35, 0.75];
+    for (got, want) in result.boxes[0].iter().zip(want) {
+        assert!((got - want).abs() < 0.05, "{:?}", result.boxes);
+    }
+    assert!((result.scores[0] - 0.9).abs() < 1e-4);
+}
+
+#[test]
+fn frames_without_pixels_run_once_unaugmented() {
+    let mut detector = TtaDetector::new(Box::new(BrightBox), TtaConfig::default()).unwrap();
+    let result = detector.detect(&Frame {
+        id: 9,
+        timestamp: 0.0,
+        rgba: None,
+        size: (4, 4),
+        path: None,
+    });
+    assert_eq!(result.frame_id, 9);
+    assert!(!result.positive && result.boxes.is_empty());
+}
+
+#[test]
+fn non_positive_scales_are_rejected() {
+    for scale in [0.0, -1.0] {
+        let config = TtaConfig {
+            scales: vec![1.0, scale],
+            ..TtaConfig::default()
+        };
+        let err = TtaDetector::new(Box::new(BrightBox), config).err().unwrap();
+        assert!(err.to_string().contains("finite and positive"), "{err}");
+    }
+}
@@ -0,0 +1,50 @@
+//! Objectness score calibration stored in the checkpoint sidecar.
+//!
+//! Multibox scores come out of a sigmoid but are rarely well calibrated: a 0.8 score is not a
+//! detection that is right 80% of the time. A `Calibration` remaps a score through its logit,
+//! either dividing by a temperature or applying a Platt scaling `a * logit + b`, and is fitted by
+//! `eval --sweep-out` on validation predictions. Both maps are monotonic, so rankings (and AP)
+//! are unchanged; only the meaning of score thresholds moves.
+
+use serde::{Deserialize, Serialize};
+
+/// Scores are clamped to `[EPS, 1 - EPS]` before taking the logit.
+const EPS: f32 = 1e-6;
+
+/// Fitted score remapping; `apply` turns a raw model score into a calibrated probability.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum Calibration {
+    /// `sigmoid(logit(score) / temperature)`.
+    Temperature { temperature: f32 },
+    /// `sigmoid(a * logit(score) + b)`.
+    Platt { a: f32, b: f32 },
+}
+
+impl Calibration {
+    /// Calibrated probability for a raw score in [0, 1].
+    pub fn apply(&self, score: f32) -> f32 {
+        let x = logit(score);
+        match *self {
+            Calibration::Temperature { temperature } => sigmoid(x / temperature),
+            Calibration::Platt { a, b } => sigmoid(a * x + b),
+        }
+    }
+
+    /// Calibrate every score in place.
+    pub fn apply_all(&self, scores: &mut [f32]) {
+        for score in scores {
+            *score = self.apply(*score);
+        }
+    }
+}
+
+/// Log-odds of `p`, clamped away from 0 and 1.
+pub fn logit(p: f32) -> f32 {
+    let p = p.clamp(EPS, 1.0 - EPS);
+    (p / (1.0 - p)).ln()
+}
+
+pub fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
@@ -0,0 +1,261 @@
+//! Self-describing checkpoint metadata.
+//!
+//! Burn `.bin` records only hold weights, so a checkpoint alone cannot tell a loader which
+//! architecture (or which hyperparameters) produced it. Training writes a versioned JSON sidecar
+//! next to every checkpoint (`<name>.meta.json`) carrying the model kind and config, the input
+//! feature spec, and the warehouse/code versions it was trained against, plus an optional score
+//! calibration fitted after training and the class names of multi-class models. Loaders read
+//! the sidecar, rebuild the matching architecture, and refuse files they cannot interpret.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::calibration::Calibration;
+use crate::input::{LINEAR_INPUT_DIM, MULTIBOX_INPUT_DIM, STATS_DIM};
+use crate::{LinearClassifierConfig, MultiboxModelConfig};
+
+/// Sidecar format version written by this crate. Bump on incompatible changes.
+pub const CHECKPOINT_FORMAT_VERSION: u32 = 1;
+
+/// Architecture family stored in a checkpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    LinearClassifier,
+    Multibox,
+}
+
+impl std::fmt::Display for ModelKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModelKind::LinearClassifier => write!(f, "linear_classifier"),
+            ModelKind::Multibox => write!(f, "multibox"),
+        }
+    }
+}
+
+/// Architecture config, tagged by model kind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "config", rename_all = "snake_case")]
+pub enum ModelConfig {
+    LinearClassifier(LinearClassifierConfig),
+    Multibox(MultiboxModelConfig),
+}
+
+impl ModelConfig {
+    pub fn kind(&self) -> ModelKind {
+        match self {
+            ModelConfig::LinearClassifier(_) => ModelKind::LinearClassifier,
+            ModelConfig::Multibox(_) => ModelKind::Multibox,
+        }
+    }
+
+    /// Config assumed for a checkpoint without a sidecar: the kind's default hyperparameters.
+    pub fn legacy(kind: ModelKind) -> Self {
+        match kind {
+            ModelKind::LinearClassifier => {
+                ModelConfig::LinearClassifier(LinearClassifierConfig::default())
+            }
+            ModelKind::Multibox => ModelConfig::Multibox(MultiboxModelConfig {
+                input_dim: Some(MULTIBOX_INPUT_DIM),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Input feature spec the architecture expects from `crate::input`.
+    pub fn input_spec(&self) -> InputSpec {
+        match self {
+            ModelConfig::LinearClassifier(_) => InputSpec {
+                name: "image_stats_v1".into(),
+                dim: LINEAR_INPUT_DIM,
+            },
+            ModelConfig::Multibox(cfg) if cfg.backbone.is_some() => InputSpec {
+                name: "image_chw_stats_v1".into(),
+                dim: STATS_DIM,
+            },
+            ModelConfig::Multibox(cfg) => InputSpec {
+                name: "image_grid_stats_v1".into(),
+                dim: cfg.input_dim.unwrap_or(MULTIBOX_INPUT_DIM),
+            },
+        }
+    }
+}
+
+/// Name and width of the vector input a model was trained on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputSpec {
+    pub name: String,
+    pub dim: usize,
+}
+
+/// Sidecar written next to a checkpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckpointMetadata {
+    pub format_version: u32,
+    pub model: ModelConfig,
+    pub input: InputSpec,
+    /// Warehouse manifest version used for training (`None` for capture-log training).
+    #[serde(default)]
+    pub warehouse_version: Option<String>,
+    pub code_version: String,
+    /// Score calibration applied by inference (`None` serves raw scores).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calibration: Option<Calibration>,
+    /// Names of the classes a multi-class model predicts, indexed by class id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class_names: Option<Vec<String>>,
+}
+
+#[derive(Debug, Error)]
+pub enum CheckpointError {
+    #[error(
+        "checkpoint metadata not found at {0}: re-train or re-export the checkpoint, or opt in to \
+         loading it with the model kind's default hyperparameters (`--legacy-checkpoint`)"
+    )]
+    Missing(PathBuf),
+    #[error("failed to access checkpoint metadata {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid checkpoint metadata {path}: {source}")]
+    Json {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("unsupported checkpoint format version {found} (this build reads up to {supported})")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("checkpoint holds a {found} model, expected {expected}")]
+    KindMismatch {
+        expected: ModelKind,
+        found: ModelKind,
+    },
+    #[error(
+        "checkpoint input spec {found_name}/{found_dim} does not match the {kind} architecture ({expected_name}/{expected_dim})"
+    )]
+    InputMismatch {
+        kind: ModelKind,
+        expected_name: String,
+        expected_dim: usize,
+        found_name: String,
+        found_dim: usize,
+    },
+    #[error("checkpoint names {names} classes but its model predicts {classes}")]
+    ClassNamesMismatch { names: usize, classes: usize },
+}
+
+/// Path of the metadata sidecar for a checkpoint (`model.bin` -> `model.meta.json`).
+pub fn sidecar_path(checkpoint: &Path) -> PathBuf {
+    checkpoint.with_extension("meta.json")
+}
+
+impl CheckpointMetadata {
+    /// Build metadata for `model` at the current format version.
+    pub fn new(
+        model: ModelConfig,
+        warehouse_version: Option<String>,
+        code_version: impl Into<String>,
+    ) -> Self {
+        let input = model.input_spec();
+        Self {
+            format_version: CHECKPOINT_FORMAT_VERSION,
+            model,
+            input,
+            warehouse_version,
+            code_version: code_version.into(),
+            calibration: None,
+            class_names: None,
+        }
+    }
+
+    pub fn kind(&self) -> ModelKind {
+        self.model.kind()
+    }
+
+    /// Check the format version and that the input spec matches the stored architecture.
+    pub fn validate(&self) -> Result<(), CheckpointError> {
+        if self.format_version == 0 || self.format_version > CHECKPOINT_FORMAT_VERSION {
+            return Err(CheckpointError::UnsupportedVersion {
+                found: self.format_version,
+                supported: CHECKPOINT_FORMAT_VERSION,
+            });
+        }
+        let expected = self.model.input_spec();
+        if expected != self.input {
+            return Err(CheckpointError::InputMismatch {
+                kind: self.kind(),
+                expected_name: expected.name,
+                expected_dim: expected.dim,
+                found_name: self.input.name.clone(),
+                found_dim: self.input.dim,
+            });
+        }
+        if let (Some(names), ModelConfig::Multibox(cfg)) = (&self.class_names, &self.model) {
+            if names.len() != cfg.num_classes.max(1) {
+                return Err(CheckpointError::ClassNamesMismatch {
+                    names: names.len(),
+                    classes: cfg.num_classes.max(1),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate and additionally require a specific model kind.
+    pub fn expect_kind(&self, expected: ModelKind) -> Result<(), CheckpointError> {
+        self.validate()?;
+        if self.kind() != expected {
+            return Err(CheckpointError::KindMismatch {
+                expected,
+                found: self.kind(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Write the sidecar for `checkpoint`.
+    pub fn save(&self, checkpoint: &Path) -> Result<(), CheckpointError> {
+        let path = sidecar_path(checkpoint);
+        let json = serde_json::to_vec_pretty(self).map_err(|source| CheckpointError::Json {
+            path: path.clone(),
+            source,
+        })?;
+        fs::write(&path, json).map_err(|source| CheckpointError::Io { path, source })
+    }
+
+    /// Read and validate the sidecar for `checkpoint`.
+    pub fn load(checkpoint: &Path) -> Result<Self, CheckpointError> {
+        let path = sidecar_path(checkpoint);
+        if !path.exists() {
+            return Err(CheckpointError::Missing(path));
+        }
+        let bytes = fs::read(&path).map_err(|source| CheckpointError::Io {
+            path: path.clone(),
+            source,
+        })?;
+        let meta: Self = serde_json::from_slice(&bytes)
+            .map_err(|source| CheckpointError::Json { path, source })?;
+        meta.validate()?;
+        Ok(meta)
+    }
+
+    /// Like [`CheckpointMetadata::load`], but a checkpoint written before sidecars existed loads
+    /// as `kind` with [`ModelConfig::legacy`] hyperparameters.
+    ///
+    /// Only for an explicit opt-in: nothing checks that the weights were trained on the inputs
+    /// those defaults assume.
+    pub fn load_or_legacy(checkpoint: &Path, kind: ModelKind) -> Result<Self, CheckpointError> {
+        match Self::load(checkpoint) {
+            Err(CheckpointError::Missing(_)) => {
+                Ok(Self::new(ModelConfig::legacy(kind), None, "legacy"))
+            }
+            result => result,
+        }
+    }
+}
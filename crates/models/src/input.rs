@@ -0,0 +1,53 @@
+//! Image-conditioned model inputs shared by training and inference.
+//!
+//! `training` and `inference` both build model inputs through these helpers, so the vector a
+//! model sees at train time is produced by the same code as the one it sees at serve time.
+//! Inputs are derived only from the image: the CHW image tensor and the per-image
+//! `ImageStats::feature_vector` row (mean RGB, std RGB, aspect, box count). The trailing
+//! box-count column is a label, not an image property, so it is never fed to a model.
+
+use burn::tensor::backend::Backend;
+use burn::tensor::module::adaptive_avg_pool2d;
+use burn::tensor::Tensor;
+
+/// Side length of the average-pooled image grid used by `multibox_input`.
+pub const INPUT_GRID: usize = 4;
+
+/// Number of `ImageStats` columns used as model input (mean RGB, std RGB, aspect).
+pub const STATS_DIM: usize = 7;
+
+/// Width of the `ImageStats::feature_vector` rows produced by collation.
+pub const FEATURE_DIM: usize = 8;
+
+/// Input width of `LinearClassifier` (mean RGB + aspect, the `ImageStats::tiny_input` layout).
+pub const LINEAR_INPUT_DIM: usize = 4;
+
+/// Input width of `MultiboxModel` when fed by `multibox_input`.
+pub const MULTIBOX_INPUT_DIM: usize = 3 * INPUT_GRID * INPUT_GRID + STATS_DIM;
+
+/// Build the `LinearClassifier` input from `[batch, FEATURE_DIM]` image stats.
+///
+/// Returns `[batch, LINEAR_INPUT_DIM]` laid out as mean R/G/B followed by aspect ratio.
+pub fn linear_input<B: Backend>(features: Tensor<B, 2>) -> Tensor<B, 2> {
+    let batch = features.dims()[0];
+    let mean = features.clone().slice([0..batch, 0..3]);
+    let aspect = features.slice([0..batch, 6..7]);
+    Tensor::cat(vec![mean, aspect], 1)
+}
+
+/// Select the `STATS_DIM` image-stat columns from `[batch, FEATURE_DIM]` features.
+pub fn stats_input<B: Backend>(features: Tensor<B, 2>) -> Tensor<B, 2> {
+    let batch = features.dims()[0];
+    features.slice([0..batch, 0..STATS_DIM])
+}
+
+/// Build the `MultiboxModel` input from `[batch, 3, H, W]` images and `[batch, FEATURE_DIM]` stats.
+///
+/// Returns `[batch, MULTIBOX_INPUT_DIM]`: the image average-pooled to an
+/// `INPUT_GRID x INPUT_GRID` RGB grid (channel-major), followed by the `STATS_DIM` image stats.
+pub fn multibox_input<B: Backend>(images: Tensor<B, 4>, features: Tensor<B, 2>) -> Tensor<B, 2> {
+    let batch = images.dims()[0];
+    let grid = adaptive_avg_pool2d(images, [INPUT_GRID, INPUT_GRID])
+        .reshape([batch, 3 * INPUT_GRID * INPUT_GRID]);
+    Tensor::cat(vec![grid, stats_input(features)], 1)
+}
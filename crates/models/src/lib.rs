@@ -3,6 +3,10 @@
 //! This crate defines the neural network architectures used for detection:
 //! - `LinearClassifier`: Simple feedforward network for binary classification.
 //! - `MultiboxModel`: Multi-box detection model with spatial output heads.
+//! - `ConvBackbone`: Strided Conv2d/BatchNorm/ReLU image encoder that can feed `MultiboxModel`.
+//! - `input`: Image-conditioned input builders shared by training and inference.
+//! - `checkpoint`: Versioned metadata sidecar that makes checkpoints self-describing.
+//! - `calibration`: Objectness score calibration (temperature/Platt) stored in that sidecar.
 //!
 //! These are pure Burn Modules with no awareness of the Detector trait. The `inference`
 //! crate wraps them into Detector implementations for runtime use.
@@ -16,13 +20,25 @@
 //! Model architectures (`LinearClassifier`, `MultiboxModel`) and their config types are **stable**.
 //! The forward pass signatures and checkpoint format will not change in a backwards-incompatible
 //! way without a major version bump.
+//!
+//! 0.7.0 changed the `MultiboxModel` record layout (optional conv backbone and class head), so
+//! 0.6.0 multibox `.bin` checkpoints no longer deserialize and must be retrained. Linear
+//! classifier records are unchanged.
+
+pub mod calibration;
+pub mod checkpoint;
+pub mod input;
 
 use burn::module::Module;
 use burn::nn;
+use burn::nn::conv::{Conv2d, Conv2dConfig};
+use burn::nn::{BatchNorm, BatchNormConfig, PaddingConfig2d};
 use burn::tensor::activation::{relu, sigmoid};
+use burn::tensor::module::adaptive_avg_pool2d;
 use burn::tensor::Tensor;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LinearClassifierConfig {
     pub hidden: usize,
 }
@@ -53,12 +69,115 @@ impl<B: burn::tensor::backend::Backend> LinearClassifier<B> {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Convolutional image encoder config.
+///
+/// Stage `i` is a stride-2 3x3 conv with `width * 2^i` output channels, followed by BatchNorm
+/// and ReLU, so each stage halves the spatial resolution. The final feature map is globally
+/// average-pooled into a `[batch, out_channels]` embedding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConvBackboneConfig {
+    pub in_channels: usize,
+    pub width: usize,
+    pub depth: usize,
+}
+
+impl Default for ConvBackboneConfig {
+    fn default() -> Self {
+        Self {
+            in_channels: 3,
+            width: 16,
+            depth: 4,
+        }
+    }
+}
+
+impl ConvBackboneConfig {
+    /// Channel count of the pooled embedding produced by `ConvBackbone::forward`.
+    pub fn out_channels(&self) -> usize {
+        if self.depth == 0 {
+            self.in_channels
+        } else {
+            self.width.max(1) << (self.depth - 1)
+        }
+    }
+}
+
+#[derive(Debug, Module)]
+struct ConvStage<B: burn::tensor::backend::Backend> {
+    conv: Conv2d<B>,
+    norm: BatchNorm<B>,
+}
+
+impl<B: burn::tensor::backend::Backend> ConvStage<B> {
+    fn new(in_channels: usize, out_channels: usize, device: &B::Device) -> Self {
+        let conv = Conv2dConfig::new([in_channels, out_channels], [3, 3])
+            .with_stride([2, 2])
+            .with_padding(PaddingConfig2d::Explicit(1, 1))
+            .with_bias(false)
+            .init(device);
+        let norm = BatchNormConfig::new(out_channels).init(device);
+        Self { conv, norm }
+    }
+
+    fn forward(&self, input: Tensor<B, 4>) -> Tensor<B, 4> {
+        relu(self.norm.forward(self.conv.forward(input)))
+    }
+}
+
+#[derive(Debug, Module)]
+pub struct ConvBackbone<B: burn::tensor::backend::Backend> {
+    stages: Vec<ConvStage<B>>,
+    out_channels: usize,
+}
+
+impl<B: burn::tensor::backend::Backend> ConvBackbone<B> {
+    pub fn new(cfg: ConvBackboneConfig, device: &B::Device) -> Self {
+        let width = cfg.width.max(1);
+        let mut stages = Vec::with_capacity(cfg.depth);
+        let mut channels = cfg.in_channels;
+        for i in 0..cfg.depth {
+            let out = width << i;
+            stages.push(ConvStage::new(channels, out, device));
+            channels = out;
+        }
+        Self {
+            stages,
+            out_channels: cfg.out_channels(),
+        }
+    }
+
+    pub fn out_channels(&self) -> usize {
+        self.out_channels
+    }
+
+    /// Encode `[B, C, H, W]` images into a globally pooled `[B, out_channels]` embedding.
+    pub fn forward(&self, images: Tensor<B, 4>) -> Tensor<B, 2> {
+        let mut x = images;
+        for stage in &self.stages {
+            x = stage.forward(x);
+        }
+        let [batch, channels, _, _] = x.dims();
+        adaptive_avg_pool2d(x, [1, 1]).reshape([batch, channels])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MultiboxModelConfig {
     pub hidden: usize,
     pub depth: usize,
     pub max_boxes: usize,
+    /// Width of the vector input to `forward_multibox`; ignored when `backbone` is set.
     pub input_dim: Option<usize>,
+    /// Optional conv encoder; when set the stem consumes its embedding plus image stats.
+    #[serde(default)]
+    pub backbone: Option<ConvBackboneConfig>,
+    /// Object classes; above 1 a class head predicts per-slot class logits.
+    #[serde(default = "default_num_classes")]
+    pub num_classes: usize,
+}
+
+fn default_num_classes() -> usize {
+    1
 }
 
 impl Default for MultiboxModelConfig {
@@ -68,23 +187,32 @@ impl Default for MultiboxModelConfig {
             depth: 2,
             max_boxes: 64,
             input_dim: None,
+            backbone: None,
+            num_classes: default_num_classes(),
         }
     }
 }
 
 #[derive(Debug, Module)]
 pub struct MultiboxModel<B: burn::tensor::backend::Backend> {
+    backbone: Option<ConvBackbone<B>>,
     stem: nn::Linear<B>,
     blocks: Vec<nn::Linear<B>>,
     box_head: nn::Linear<B>,
     score_head: nn::Linear<B>,
+    class_head: Option<nn::Linear<B>>,
     max_boxes: usize,
+    num_classes: usize,
     input_dim: usize,
 }
 
 impl<B: burn::tensor::backend::Backend> MultiboxModel<B> {
     pub fn new(cfg: MultiboxModelConfig, device: &B::Device) -> Self {
-        let input_dim = cfg.input_dim.unwrap_or(4);
+        let backbone = cfg.backbone.clone().map(|bb| ConvBackbone::new(bb, device));
+        let input_dim = match &backbone {
+            Some(bb) => bb.out_channels() + input::STATS_DIM,
+            None => cfg.input_dim.unwrap_or(4),
+        };
         let stem = nn::LinearConfig::new(input_dim, cfg.hidden).init(device);
         let mut blocks = Vec::new();
         for _ in 0..cfg.depth {
@@ -92,31 +220,99 @@ impl<B: burn::tensor::backend::Backend> MultiboxModel<B> {
         }
         let box_head = nn::LinearConfig::new(cfg.hidden, cfg.max_boxes.max(1) * 4).init(device);
         let score_head = nn::LinearConfig::new(cfg.hidden, cfg.max_boxes.max(1)).init(device);
+        let num_classes = cfg.num_classes.max(1);
+        let class_head = (num_classes > 1).then(|| {
+            nn::LinearConfig::new(cfg.hidden, cfg.max_boxes.max(1) * num_classes).init(device)
+        });
         Self {
+            backbone,
             stem,
             blocks,
             box_head,
             score_head,
+            class_head,
             max_boxes: cfg.max_boxes.max(1),
+            num_classes,
             input_dim,
         }
     }
 
-    pub fn forward(&self, input: Tensor<B, 2>) -> Tensor<B, 2> {
+    /// Number of object classes the model predicts (1 without a class head).
+    pub fn num_classes(&self) -> usize {
+        self.num_classes
+    }
+
+    /// Whether this model encodes pixels with a `ConvBackbone`.
+    pub fn has_backbone(&self) -> bool {
+        self.backbone.is_some()
+    }
+
+    fn trunk(&self, input: Tensor<B, 2>) -> Tensor<B, 2> {
         let mut x = relu(self.stem.forward(input));
         for block in &self.blocks {
             x = relu(block.forward(x));
         }
-        self.score_head.forward(x)
+        x
+    }
+
+    /// Vector input for the stem from `[B, 3, H, W]` images and `[B, FEATURE_DIM]` image stats.
+    ///
+    /// With a backbone this is the conv embedding plus image stats; otherwise it is
+    /// `input::multibox_input` (pooled RGB grid plus image stats).
+    fn image_input(&self, images: Tensor<B, 4>, features: Tensor<B, 2>) -> Tensor<B, 2> {
+        match &self.backbone {
+            Some(backbone) => {
+                let embedding = backbone.forward(images);
+                Tensor::cat(vec![embedding, input::stats_input(features)], 1)
+            }
+            None => input::multibox_input(images, features),
+        }
+    }
+
+    pub fn forward(&self, input: Tensor<B, 2>) -> Tensor<B, 2> {
+        self.score_head.forward(self.trunk(input))
     }
 
     /// Multibox forward: returns (boxes, scores) with shape [B, max_boxes, 4] and [B, max_boxes].
     /// Boxes/scores are passed through sigmoid to keep them in a stable range.
     pub fn forward_multibox(&self, input: Tensor<B, 2>) -> (Tensor<B, 3>, Tensor<B, 2>) {
-        let mut x = relu(self.stem.forward(input));
-        for block in &self.blocks {
-            x = relu(block.forward(x));
-        }
+        self.heads(self.trunk(input))
+    }
+
+    /// Image multibox forward used by training and inference.
+    ///
+    /// Takes `[B, 3, H, W]` images and `[B, FEATURE_DIM]` image stats (as produced by `collate`
+    /// or the warehouse batches) and returns the same outputs as `forward_multibox`.
+    pub fn forward_multibox_images(
+        &self,
+        images: Tensor<B, 4>,
+        features: Tensor<B, 2>,
+    ) -> (Tensor<B, 3>, Tensor<B, 2>) {
+        self.forward_multibox(self.image_input(images, features))
+    }
+
+    /// `forward_multibox_images` plus raw class logits `[B, max_boxes, num_classes]`.
+    ///
+    /// Single-class models have no class head and return zero logits, so every slot's class is 0.
+    pub fn forward_multibox_images_with_classes(
+        &self,
+        images: Tensor<B, 4>,
+        features: Tensor<B, 2>,
+    ) -> (Tensor<B, 3>, Tensor<B, 2>, Tensor<B, 3>) {
+        let x = self.trunk(self.image_input(images, features));
+        let batch = x.dims()[0];
+        let class_logits = match &self.class_head {
+            Some(head) => {
+                head.forward(x.clone())
+                    .reshape([batch, self.max_boxes, self.num_classes])
+            }
+            None => Tensor::zeros([batch, self.max_boxes, 1], &x.device()),
+        };
+        let (boxes, scores) = self.heads(x);
+        (boxes, scores, class_logits)
+    }
+
+    fn heads(&self, x: Tensor<B, 2>) -> (Tensor<B, 3>, Tensor<B, 2>) {
         let boxes_flat = sigmoid(self.box_head.forward(x.clone()));
         let scores = sigmoid(self.score_head.forward(x));
         let batch = boxes_flat.dims()[0];
@@ -149,5 +345,8 @@ impl<B: burn::tensor::backend::Backend> MultiboxModel<B> {
 }
 
 pub mod prelude {
-    pub use super::{LinearClassifier, LinearClassifierConfig, MultiboxModel, MultiboxModelConfig};
+    pub use super::{
+        ConvBackbone, ConvBackboneConfig, LinearClassifier, LinearClassifierConfig, MultiboxModel,
+        MultiboxModelConfig,
+    };
 }
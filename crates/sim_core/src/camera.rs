@@ -101,11 +101,13 @@ pub fn camera_controller(
     }
 }
 
+type FreeCameraFilter = (With<Flycam>, Without<InstrumentPovCamera>);
+
 pub fn pov_toggle_system(
     mut commands: Commands,
     keys: Res<ButtonInput<KeyCode>>,
     mut state: ResMut<ActiveCameraMode>,
-    mut free_cams: Query<(Entity, &mut Camera), (With<Flycam>, Without<InstrumentPovCamera>)>,
+    mut free_cams: Query<(Entity, &mut Camera), FreeCameraFilter>,
     mut instrument_cams: Query<(Entity, &mut Camera), With<InstrumentPovCamera>>,
 ) {
     if !keys.just_pressed(KeyCode::KeyC) {
@@ -0,0 +1,219 @@
+//! Per-frame prediction records and error analysis for `eval`.
+//!
+//! `match_frame` labels every prediction at or above the score threshold as a true or false
+//! positive and every ground-truth box as found or missed, using the same greedy score-ordered
+//! matching as `metrics::DetectionAccumulator`, so per-frame counts add up to the aggregate
+//! precision/recall. `eval --predictions-out` writes one `FramePredictions` per line;
+//! `eval --report-dir` ranks frames with `worst_frames` and renders them with `render_overlay`.
+
+use burn::tensor::backend::Backend;
+use burn::tensor::Tensor;
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+use vision_core::overlay::{draw_rect, normalize_box};
+
+use crate::metrics::iou_xyxy;
+
+/// Overlay color for ground-truth boxes.
+pub const GT_COLOR: Rgba<u8> = Rgba([0, 220, 0, 255]);
+/// Overlay color for predicted boxes.
+pub const PRED_COLOR: Rgba<u8> = Rgba([255, 48, 48, 255]);
+
+/// Outcome of one box at the evaluation operating point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchStatus {
+    /// A prediction matched to ground truth, or ground truth that was found.
+    Tp,
+    /// A prediction with no ground truth to claim.
+    Fp,
+    /// Ground truth no prediction claimed.
+    Fn,
+}
+
+/// A predicted box and how it matched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PredictedBox {
+    pub bbox: [f32; 4],
+    pub score: f32,
+    pub status: MatchStatus,
+    /// IoU with the matched ground truth, or the best IoU with any ground truth for an FP.
+    pub iou: f32,
+    /// Index of the matched ground-truth box.
+    pub gt: Option<usize>,
+}
+
+/// A ground-truth box and how it matched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroundTruthBox {
+    pub bbox: [f32; 4],
+    /// `Tp` when a prediction claimed it, `Fn` otherwise.
+    pub status: MatchStatus,
+    /// IoU with the claiming prediction, or the best IoU with any prediction for an FN.
+    pub iou: f32,
+    /// Index of the claiming prediction.
+    pub pred: Option<usize>,
+}
+
+/// One line of the `--predictions-out` JSONL file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FramePredictions {
+    /// Checkpoint (or `fresh`) that produced the predictions.
+    pub checkpoint: String,
+    /// Position of the frame in evaluation order.
+    pub frame: usize,
+    /// Source image path, when the input has one (capture logs).
+    pub image: Option<String>,
+    pub tp: usize,
+    pub fp: usize,
+    #[serde(rename = "fn")]
+    pub fn_: usize,
+    /// Predictions at or above the score threshold, in descending score order.
+    pub predictions: Vec<PredictedBox>,
+    pub ground_truth: Vec<GroundTruthBox>,
+}
+
+impl FramePredictions {
+    /// A record for matched boxes from `match_frame`; TP/FP/FN counts are taken from them.
+    pub fn new(
+        checkpoint: impl Into<String>,
+        frame: usize,
+        image: Option<String>,
+        predictions: Vec<PredictedBox>,
+        ground_truth: Vec<GroundTruthBox>,
+    ) -> Self {
+        let count_gt = |status| ground_truth.iter().filter(|g| g.status == status).count();
+        Self {
+            checkpoint: checkpoint.into(),
+            frame,
+            image,
+            tp: count_gt(MatchStatus::Tp),
+            fp: predictions
+                .iter()
+                .filter(|p| p.status == MatchStatus::Fp)
+                .count(),
+            fn_: count_gt(MatchStatus::Fn),
+            predictions,
+            ground_truth,
+        }
+    }
+
+    /// Number of errors (missed plus spurious boxes).
+    pub fn errors(&self) -> usize {
+        self.fn_ + self.fp
+    }
+}
+
+/// Match one frame's predictions (any order) against its ground truth.
+pub fn match_frame(
+    boxes: &[[f32; 4]],
+    scores: &[f32],
+    gts: &[[f32; 4]],
+    iou_threshold: f32,
+    score_threshold: f32,
+) -> (Vec<PredictedBox>, Vec<GroundTruthBox>) {
+    let mut order: Vec<usize> = (0..boxes.len().min(scores.len()))
+        .filter(|&p| scores[p] >= score_threshold)
+        .collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    let mut ground_truth: Vec<GroundTruthBox> = gts
+        .iter()
+        .map(|&bbox| GroundTruthBox {
+            bbox,
+            status: MatchStatus::Fn,
+            iou: 0.0,
+            pred: None,
+        })
+        .collect();
+    let mut predictions = Vec::with_capacity(order.len());
+    for p in order {
+        let best_unclaimed = gts
+            .iter()
+            .enumerate()
+            .filter(|(g, _)| ground_truth[*g].pred.is_none())
+            .map(|(g, gt)| (g, iou_xyxy(boxes[p], *gt)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        let idx = predictions.len();
+        let predicted = match best_unclaimed {
+            Some((g, iou)) if iou >= iou_threshold => {
+                ground_truth[g] = GroundTruthBox {
+                    bbox: gts[g],
+                    status: MatchStatus::Tp,
+                    iou,
+                    pred: Some(idx),
+                };
+                PredictedBox {
+                    bbox: boxes[p],
+                    score: scores[p],
+                    status: MatchStatus::Tp,
+                    iou,
+                    gt: Some(g),
+                }
+            }
+            _ => PredictedBox {
+                bbox: boxes[p],
+                score: scores[p],
+                status: MatchStatus::Fp,
+                iou: best_iou(boxes[p], gts),
+                gt: None,
+            },
+        };
+        predictions.push(predicted);
+    }
+    let kept: Vec<[f32; 4]> = predictions.iter().map(|p| p.bbox).collect();
+    for gt in ground_truth.iter_mut().filter(|gt| gt.pred.is_none()) {
+        gt.iou = best_iou(gt.bbox, &kept);
+    }
+    (predictions, ground_truth)
+}
+
+fn best_iou(bbox: [f32; 4], others: &[[f32; 4]]) -> f32 {
+    others
+        .iter()
+        .map(|other| iou_xyxy(bbox, *other))
+        .fold(0.0, f32::max)
+}
+
+/// The `n` frames with the most errors, ordered by FN + FP, then FN, then frame index.
+pub fn worst_frames(frames: &[FramePredictions], n: usize) -> Vec<&FramePredictions> {
+    let mut ranked: Vec<&FramePredictions> = frames.iter().filter(|f| f.errors() > 0).collect();
+    ranked.sort_by(|a, b| {
+        b.errors()
+            .cmp(&a.errors())
+            .then(b.fn_.cmp(&a.fn_))
+            .then(a.frame.cmp(&b.frame))
+    });
+    ranked.truncate(n);
+    ranked
+}
+
+/// Convert one `[3, H, W]` image with values in [0, 1] to RGBA.
+pub fn image_from_tensor<B: Backend>(image: Tensor<B, 3>) -> RgbaImage {
+    let [_, height, width] = image.dims();
+    let pixels = image.into_data().to_vec::<f32>().unwrap_or_default();
+    let plane = width * height;
+    RgbaImage::from_fn(width as u32, height as u32, |x, y| {
+        let i = y as usize * width + x as usize;
+        let channel = |c: usize| {
+            let v = pixels.get(c * plane + i).copied().unwrap_or(0.0);
+            (v.clamp(0.0, 1.0) * 255.0).round() as u8
+        };
+        Rgba([channel(0), channel(1), channel(2), 255])
+    })
+}
+
+/// Draw ground truth (`GT_COLOR`) and predictions (`PRED_COLOR`) on `image`.
+pub fn render_overlay(image: &mut RgbaImage, frame: &FramePredictions) {
+    let dims = image.dimensions();
+    for gt in &frame.ground_truth {
+        if let Some(px) = normalize_box(gt.bbox, dims) {
+            draw_rect(image, px, GT_COLOR, 2);
+        }
+    }
+    for pred in &frame.predictions {
+        if let Some(px) = normalize_box(pred.bbox, dims) {
+            draw_rect(image, px, PRED_COLOR, 1);
+        }
+    }
+}
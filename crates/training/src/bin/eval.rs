@@ -1,257 +1,1081 @@
-use clap::Parser;
-use training::dataset::{collate, DatasetPathConfig};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use burn::tensor::{Tensor, TensorData};
+use burn_dataset::aug::{tta_transforms, TtaTransform};
+use burn_dataset::{WarehouseLoaders, WarehouseManifest};
+use clap::{Parser, ValueEnum};
+use models::checkpoint::{self, CheckpointMetadata};
+use models::input::{linear_input, MULTIBOX_INPUT_DIM};
+use serde::Serialize;
+use training::analysis::{
+    image_from_tensor, match_frame, render_overlay, worst_frames, FramePredictions,
+};
+use training::calibration::{sweep, CalibrationMethod, SweepReport};
+use training::dataset::DatasetPathConfig;
+use training::metrics::{nms, CocoEvaluator, CocoMetrics, DetectionAccumulator};
+use training::trainer::{BatchSource, SampleSource, WarehouseSource, WarehouseSplit};
 use training::util::{
-    load_linear_classifier_from_checkpoint, load_multibox_model_from_checkpoint, BackendKind,
-    ModelKind,
+    linear_classifier_from_metadata, multibox_model_from_metadata, BackendKind, ModelKind,
+    TrainingInputSource,
 };
 use training::{
-    LinearClassifier, LinearClassifierConfig, MultiboxModel, MultiboxModelConfig, TrainBackend,
+    CollatedBatch, LinearClassifier, LinearClassifierConfig, MultiboxModel, MultiboxModelConfig,
+    TrainBackend,
 };
+use vision_core::ensemble::{fuse_results, FusionConfig, FusionMethod};
+use vision_core::interfaces::DetectionResult;
 
 #[derive(Parser, Debug)]
 #[command(
     name = "eval",
-    about = "Evaluate LinearClassifier/MultiboxModel checkpoint on a dataset (precision/recall by IoU)"
+    about = "Evaluate LinearClassifier/MultiboxModel checkpoints on a dataset (precision/recall, COCO AP/AR)"
 )]
 struct Args {
-    /// Model to evaluate.
-    #[arg(long, value_enum, default_value_t = ModelKind::Tiny)]
-    model: ModelKind,
+    /// Model to evaluate when no checkpoint is given (default tiny), and the kind assumed for
+    /// checkpoints without a metadata sidecar under `--legacy-checkpoint`.
+    #[arg(long, value_enum)]
+    model: Option<ModelKind>,
+    /// Load checkpoints without a metadata sidecar as `--model` with its default
+    /// hyperparameters (otherwise they are rejected). Only for weights trained that way.
+    #[arg(long, requires = "model")]
+    legacy_checkpoint: bool,
     /// Backend to use (ndarray or wgpu if enabled).
     #[arg(long, value_enum, default_value_t = BackendKind::NdArray)]
     backend: BackendKind,
-    /// Dataset root containing labels/ and images/ (uses data_contracts schemas).
+    /// Evaluation input source (capture logs by default; `warehouse` reads shards like `train`).
+    #[arg(long, value_enum, default_value_t = TrainingInputSource::CaptureLogs)]
+    input_source: TrainingInputSource,
+    /// Warehouse manifest path (used with --input-source warehouse).
+    #[arg(long, default_value = "assets/warehouse/manifest.json")]
+    warehouse_manifest: String,
+    /// Capture-log dataset root containing labels/ and images/ (uses data_contracts schemas).
     #[arg(long, default_value = "assets/datasets/captures_filtered")]
     dataset_root: String,
-    /// Labels subdirectory relative to dataset root.
+    /// Labels subdirectory relative to dataset root (capture-logs only).
     #[arg(long, default_value = "labels")]
     labels_subdir: String,
-    /// Images subdirectory relative to dataset root.
+    /// Images subdirectory relative to dataset root (capture-logs only).
     #[arg(long, default_value = ".")]
     images_subdir: String,
-    /// Maximum boxes per image (pads/truncates to this for eval batch collation).
+    /// Which part of the dataset to evaluate; `train`/`val` need the run's `--val-ratio`/`--seed`.
+    #[arg(long, value_enum, default_value_t = EvalSplit::All)]
+    split: EvalSplit,
+    /// Validation fraction used by the training run (for `--split train|val`).
+    #[arg(long, default_value_t = 0.0)]
+    val_ratio: f32,
+    /// Split seed used by the training run (for `--split train|val`).
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Maximum boxes per image (capture-log collation; warehouse shards carry their own).
     #[arg(long, default_value_t = 64)]
     max_boxes: usize,
-    /// Checkpoint path to load.
+    /// Checkpoint path(s) to load; repeat to compare several models (any mix of architectures).
     #[arg(long)]
-    checkpoint: Option<String>,
-    /// IoU threshold for true positive.
+    checkpoint: Vec<String>,
+    /// IoU threshold for true positive (precision/recall operating point).
+    #[arg(long, default_value_t = 0.5)]
+    iou_threshold: f32,
+    /// Score threshold for precision/recall (AP/AR use every prediction).
+    #[arg(long, default_value_t = 0.5)]
+    score_threshold: f32,
+    /// IoU threshold for NMS over multibox predictions (1.0 keeps every slot).
     #[arg(long, default_value_t = 0.5)]
+    nms_iou: f32,
+    /// Frames per forward pass.
+    #[arg(long, default_value_t = 8)]
+    batch_size: usize,
+    /// Write the metrics for every checkpoint (including PR curves) as JSON to this path.
+    #[arg(long)]
+    json_out: Option<String>,
+    /// Write one JSON line per frame (boxes, scores, TP/FP/FN status, IoU) to this path.
+    #[arg(long)]
+    predictions_out: Option<String>,
+    /// Write a worst-frames report with GT/prediction overlays into this directory.
+    #[arg(long)]
+    report_dir: Option<String>,
+    /// Frames listed (and rendered) in the `--report-dir` report.
+    #[arg(long, default_value_t = 20)]
+    report_top: usize,
+    /// Write a score-threshold sweep (P/R/F1 per threshold, fitted calibration, reliability
+    /// diagrams) for every checkpoint as JSON to this path.
+    #[arg(long)]
+    sweep_out: Option<String>,
+    /// Calibration fitted by the sweep (multibox only; `none` to skip).
+    #[arg(long, value_enum, default_value_t = CalibrationMethod::Platt)]
+    calibration: CalibrationMethod,
+    /// Recall the sweep picks the highest-precision threshold for.
+    #[arg(long, default_value_t = 0.9)]
+    target_recall: f32,
+    /// Confidence bins in the sweep's reliability diagrams.
+    #[arg(long, default_value_t = 10)]
+    reliability_bins: usize,
+    /// Store the fitted calibration in each checkpoint's metadata, where inference applies it.
+    /// Multibox only, and not allowed with `--tta`: the fit would be on fused TTA scores, not
+    /// the model's own.
+    #[arg(long, requires = "sweep_out", conflicts_with = "tta")]
+    write_calibration: bool,
+    /// Also evaluate the fused ensemble of every multibox `--checkpoint` (reported as `ensemble`).
+    #[arg(long)]
+    ensemble: bool,
+    /// Fusion weight of each `--checkpoint` in the ensemble, in order (default 1 each).
+    #[arg(long, requires = "ensemble")]
+    ensemble_weight: Vec<f32>,
+    /// How ensemble members' (and `--tta` variants') boxes are fused: nms, soft-nms, or wbf.
+    #[arg(long, default_value_t = FusionMethod::Wbf)]
+    fusion: FusionMethod,
+    /// IoU above which fused boxes are suppressed (nms) or merged (wbf).
+    #[arg(long, default_value_t = 0.55)]
+    fusion_iou: f32,
+    /// Test-time augmentation for multibox models: also run mirrored (and `--tta-scales`) copies
+    /// of each batch and fuse the boxes per class.
+    #[arg(long)]
+    tta: bool,
+    /// Scales for `--tta` (1.0 is the original image).
+    #[arg(long, requires = "tta", value_delimiter = ',', default_value = "1.0")]
+    tta_scales: Vec<f32>,
+}
+
+/// Frames to evaluate, with their image paths when the source has them.
+struct EvalInput<'a> {
+    source: &'a dyn BatchSource<TrainBackend>,
+    images: Option<Vec<String>>,
+    /// `(width, height)` the warehouse resized frames to; the source image sizes are not kept.
+    resized_to: Option<(u32, u32)>,
+}
+
+/// Dataset split to evaluate, reproduced the same way `train` splits its input.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum EvalSplit {
+    /// Every sample.
+    All,
+    /// The training side of the `--val-ratio`/`--seed` split.
+    Train,
+    /// The held-out side of the `--val-ratio`/`--seed` split.
+    Val,
+}
+
+/// Metrics for one evaluated model.
+#[derive(Debug, Serialize)]
+struct EvalReport {
+    checkpoint: String,
+    frames: usize,
+    iou_threshold: f32,
+    score_threshold: f32,
+    precision: f32,
+    recall: f32,
+    tp: usize,
+    fp: usize,
+    #[serde(rename = "fn")]
+    fn_: usize,
+    /// COCO metrics; `None` for the linear classifier, which has no box outputs.
+    coco: Option<CocoMetrics>,
+    /// Set when COCO's small/medium/large areas are in pixels of frames resized to this
+    /// `[width, height]` (warehouse shards with a target size), not of the source images.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    areas_resized_to: Option<[u32; 2]>,
+    /// Per-class AP (multi-class models only).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    per_class: Vec<ClassMetrics>,
+}
+
+/// AP of one class, scoring each prediction as its highest-logit class.
+#[derive(Debug, Serialize)]
+struct ClassMetrics {
+    class_id: usize,
+    /// From the checkpoint's `class_names`, when it has them.
+    name: Option<String>,
+    num_gt: usize,
+    map: Option<f32>,
+    map50: Option<f32>,
+}
+
+/// Threshold sweep for one evaluated model.
+#[derive(Debug, Serialize)]
+struct SweepEntry {
+    checkpoint: String,
     iou_threshold: f32,
+    #[serde(flatten)]
+    sweep: SweepReport,
+}
+
+/// Everything `evaluate` collects for one model.
+struct Evaluation {
+    report: EvalReport,
+    /// Per-frame records (multibox only).
+    frames: Vec<FramePredictions>,
+    /// `(score, is_true_positive)` for every prediction, regardless of the score threshold.
+    scored: Vec<(f32, bool)>,
+    positives: usize,
+}
+
+/// A model under evaluation; the variant is picked at runtime from the checkpoint metadata.
+#[allow(clippy::large_enum_variant)]
+enum EvalModel {
+    Linear(LinearClassifier<TrainBackend>),
+    Multibox(MultiboxModel<TrainBackend>),
+    /// Multibox checkpoints with their fusion weights, scored on their fused boxes.
+    Ensemble(Vec<(MultiboxModel<TrainBackend>, f32)>),
+}
+
+type Device = <TrainBackend as burn::tensor::backend::Backend>::Device;
+
+fn fresh_model(kind: ModelKind, max_boxes: usize, device: &Device) -> EvalModel {
+    match kind {
+        ModelKind::Tiny => EvalModel::Linear(LinearClassifier::<TrainBackend>::new(
+            LinearClassifierConfig::default(),
+            device,
+        )),
+        ModelKind::Big => EvalModel::Multibox(MultiboxModel::<TrainBackend>::new(
+            MultiboxModelConfig {
+                input_dim: Some(MULTIBOX_INPUT_DIM),
+                max_boxes,
+                ..Default::default()
+            },
+            device,
+        )),
+    }
+}
+
+/// Load a checkpoint from its sidecar, or, with `legacy` set, as that kind when it has none.
+fn load_eval_model(
+    path: &str,
+    legacy: Option<ModelKind>,
+    device: &Device,
+) -> anyhow::Result<EvalModel> {
+    let path = Path::new(path);
+    let meta = match legacy {
+        Some(ModelKind::Tiny) => {
+            CheckpointMetadata::load_or_legacy(path, checkpoint::ModelKind::LinearClassifier)?
+        }
+        Some(ModelKind::Big) => {
+            CheckpointMetadata::load_or_legacy(path, checkpoint::ModelKind::Multibox)?
+        }
+        None => CheckpointMetadata::load(path)?,
+    };
+    Ok(match meta.kind() {
+        checkpoint::ModelKind::LinearClassifier => {
+            EvalModel::Linear(linear_classifier_from_metadata(path, meta, device)?)
+        }
+        checkpoint::ModelKind::Multibox => {
+            EvalModel::Multibox(multibox_model_from_metadata(path, meta, device)?)
+        }
+    })
 }
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
     training::util::validate_backend_choice(args.backend)?;
 
-    let cfg = DatasetPathConfig {
-        root: args.dataset_root.into(),
-        labels_subdir: args.labels_subdir,
-        images_subdir: args.images_subdir,
-    };
-    let samples = cfg.load()?;
-    if samples.is_empty() {
-        println!("No samples found under {}", cfg.root.display());
-        return Ok(());
+    if args.batch_size == 0 {
+        anyhow::bail!("--batch-size must be at least 1");
+    }
+    if args.tta {
+        tta_transforms(true, &args.tta_scales)?;
     }
 
     let device = <TrainBackend as burn::tensor::backend::Backend>::Device::default();
-    let ckpt = args.checkpoint.clone();
-
-    // Build collate batches
-    let batch_size = 8usize;
-    let mut total_tp = 0f32;
-    let mut total_fp = 0f32;
-    let mut total_fn = 0f32;
-
-    match args.model {
-        ModelKind::Tiny => {
-            let model = match ckpt {
-                Some(ref p) => {
-                    load_linear_classifier_from_checkpoint(p, &device).unwrap_or_else(|e| {
-                        println!("Failed to load checkpoint {p}; using fresh model ({e})");
-                        LinearClassifier::<TrainBackend>::new(
-                            LinearClassifierConfig::default(),
-                            &device,
-                        )
-                    })
-                }
-                None => {
-                    println!("No checkpoint provided; using fresh LinearClassifier");
-                    LinearClassifier::<TrainBackend>::new(
-                        LinearClassifierConfig::default(),
-                        &device,
-                    )
+    let mut models = Vec::new();
+    if args.checkpoint.is_empty() {
+        let model = args.model.unwrap_or(ModelKind::Tiny);
+        println!("No checkpoint provided; using fresh {model:?} model");
+        models.push((
+            "fresh".to_string(),
+            fresh_model(model, args.max_boxes, &device),
+        ));
+    }
+    for p in &args.checkpoint {
+        let legacy = args.model.filter(|_| args.legacy_checkpoint);
+        let model = load_eval_model(p, legacy, &device)
+            .map_err(|e| e.context(format!("failed to load checkpoint {p}")))?;
+        models.push((p.clone(), model));
+    }
+
+    if args.write_calibration {
+        if let Some((label, _)) = models
+            .iter()
+            .find(|(_, model)| matches!(model, EvalModel::Linear(_)))
+        {
+            anyhow::bail!(
+                "--write-calibration only applies to multibox checkpoints: {label} is a linear \
+                 classifier, whose scores are not probabilities and are never calibrated"
+            );
+        }
+    }
+
+    if args.ensemble {
+        let ensemble = ensemble_model(&models, &args)?;
+        models.push(("ensemble".to_string(), ensemble));
+    }
+
+    if !(0.0..=1.0).contains(&args.target_recall) {
+        anyhow::bail!("--target-recall must be in [0, 1]");
+    }
+
+    let (reports, sweeps) = match args.input_source {
+        TrainingInputSource::Warehouse => {
+            let manifest_path = Path::new(&args.warehouse_manifest);
+            let manifest = WarehouseManifest::load(manifest_path).map_err(|e| {
+                anyhow::anyhow!(
+                    "failed to load warehouse manifest at {}: {e}",
+                    manifest_path.display()
+                )
+            })?;
+            for p in &args.checkpoint {
+                let trained_on = CheckpointMetadata::load(Path::new(p))
+                    .ok()
+                    .and_then(|meta| meta.warehouse_version);
+                if let Some(trained_on) = trained_on.filter(|v| *v != manifest.version) {
+                    println!(
+                        "warning: {p} was trained on warehouse version {trained_on}, evaluating on {}",
+                        manifest.version
+                    );
                 }
+            }
+            let val_ratio = match args.split {
+                EvalSplit::All => 0.0,
+                EvalSplit::Train | EvalSplit::Val => args.val_ratio,
+            };
+            let loaders =
+                WarehouseLoaders::from_manifest_path(manifest_path, val_ratio, args.seed, false)
+                    .map_err(|e| {
+                        anyhow::anyhow!(
+                            "failed to load warehouse manifest at {}: {e}",
+                            manifest_path.display()
+                        )
+                    })?;
+            let split = match args.split {
+                EvalSplit::All | EvalSplit::Train => WarehouseSplit::Train,
+                EvalSplit::Val => WarehouseSplit::Val,
+            };
+            // Shards are already padded to the warehouse's box count.
+            let source = WarehouseSource::new(
+                &loaders,
+                split,
+                args.batch_size,
+                manifest.transform.max_boxes,
+            );
+            let input = EvalInput {
+                source: &source,
+                images: None,
+                resized_to: manifest.transform.target_size,
+            };
+            evaluate_all(&models, &input, &args, &device)?
+        }
+        TrainingInputSource::CaptureLogs => {
+            let cfg = DatasetPathConfig {
+                root: args.dataset_root.clone().into(),
+                labels_subdir: args.labels_subdir.clone(),
+                images_subdir: args.images_subdir.clone(),
+            };
+            let samples = cfg.load()?;
+            let samples = match args.split {
+                EvalSplit::All => samples,
+                EvalSplit::Train => training::split_samples(samples, args.val_ratio, args.seed).0,
+                EvalSplit::Val => training::split_samples(samples, args.val_ratio, args.seed).1,
+            };
+            let source = SampleSource::new(&samples, args.batch_size, args.max_boxes);
+            let input = EvalInput {
+                source: &source,
+                images: Some(
+                    samples
+                        .iter()
+                        .map(|s| s.image.display().to_string())
+                        .collect(),
+                ),
+                resized_to: None,
             };
-            for chunk in samples.chunks(batch_size) {
-                let batch = collate::<TrainBackend>(chunk, args.max_boxes)?;
-                let boxes = batch.boxes.clone();
-                let first_box = boxes
+            evaluate_all(&models, &input, &args, &device)?
+        }
+    };
+    if let Some(path) = &args.sweep_out {
+        let path = Path::new(path);
+        create_parent(path)?;
+        fs::write(path, serde_json::to_vec_pretty(&sweeps)?)
+            .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+        println!("Wrote threshold sweep to {}", path.display());
+    }
+    if let Some(path) = &args.json_out {
+        let path = Path::new(path);
+        create_parent(path)?;
+        fs::write(path, serde_json::to_vec_pretty(&reports)?)
+            .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+        println!("Wrote metrics to {}", path.display());
+    }
+
+    Ok(())
+}
+
+fn create_parent(path: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(())
+}
+
+/// Evaluate every model on `input`, printing each report and writing the per-frame outputs and
+/// threshold sweeps.
+fn evaluate_all(
+    models: &[(String, EvalModel)],
+    input: &EvalInput,
+    args: &Args,
+    device: &Device,
+) -> anyhow::Result<(Vec<EvalReport>, Vec<SweepEntry>)> {
+    if input.source.is_empty() {
+        anyhow::bail!(
+            "no samples to evaluate in the {:?} split (check --val-ratio/--seed)",
+            args.split
+        );
+    }
+    let mut predictions_out = match &args.predictions_out {
+        Some(path) => {
+            let path = Path::new(path);
+            create_parent(path)?;
+            let file = fs::File::create(path)
+                .map_err(|e| anyhow::anyhow!("failed to create {}: {e}", path.display()))?;
+            Some(BufWriter::new(file))
+        }
+        None => None,
+    };
+    let mut reports = Vec::new();
+    let mut sweeps = Vec::new();
+    for (i, (label, model)) in models.iter().enumerate() {
+        let Evaluation {
+            report,
+            frames,
+            scored,
+            positives,
+        } = evaluate(label, model, input, args, device)?;
+        if models.len() > 1 {
+            print!("[{label}] ");
+        }
+        print_report(&report);
+        reports.push(report);
+        if args.sweep_out.is_some() {
+            let entry = sweep_model(label, model, &scored, positives, args)?;
+            print_sweep(&entry.sweep);
+            if args.write_calibration && !matches!(model, EvalModel::Ensemble(_)) {
+                write_calibration(label, &entry.sweep)?;
+            }
+            sweeps.push(entry);
+        }
+        if matches!(model, EvalModel::Linear(_)) {
+            if args.predictions_out.is_some() || args.report_dir.is_some() {
+                println!(
+                    "  (no per-frame boxes for the linear classifier; skipping predictions/report)"
+                );
+            }
+            continue;
+        }
+        if let Some(out) = predictions_out.as_mut() {
+            for frame in &frames {
+                serde_json::to_writer(&mut *out, frame)?;
+                out.write_all(b"\n")?;
+            }
+        }
+        if let Some(dir) = &args.report_dir {
+            let mut dir = PathBuf::from(dir);
+            if models.len() > 1 {
+                dir = dir.join(format!("model_{i}"));
+            }
+            write_report(&dir, label, &frames, input, args, device)?;
+        }
+    }
+    if let (Some(out), Some(path)) = (predictions_out.as_mut(), &args.predictions_out) {
+        out.flush()?;
+        println!("Wrote per-frame predictions to {path}");
+    }
+    Ok((reports, sweeps))
+}
+
+/// Sweep thresholds for one model, fitting `--calibration` when the model outputs probabilities.
+fn sweep_model(
+    label: &str,
+    model: &EvalModel,
+    scored: &[(f32, bool)],
+    positives: usize,
+    args: &Args,
+) -> anyhow::Result<SweepEntry> {
+    // Linear scores are regressed, not sigmoid outputs, so there is no logit to rescale.
+    let method = match model {
+        EvalModel::Linear(_) => CalibrationMethod::None,
+        EvalModel::Multibox(_) | EvalModel::Ensemble(_) => args.calibration,
+    };
+    let run = |method| {
+        sweep(
+            scored,
+            positives,
+            method,
+            args.target_recall,
+            args.reliability_bins,
+        )
+    };
+    let sweep = match run(method) {
+        Ok(sweep) => sweep,
+        Err(e) => {
+            println!("  calibration skipped: {e:#}");
+            run(CalibrationMethod::None)?
+        }
+    };
+    Ok(SweepEntry {
+        checkpoint: label.to_string(),
+        iou_threshold: args.iou_threshold,
+        sweep,
+    })
+}
+
+fn print_sweep(sweep: &SweepReport) {
+    let calibrated = |threshold: Option<f32>| {
+        threshold.map_or_else(String::new, |t| format!(" (calibrated {t:.3})"))
+    };
+    match &sweep.best_f1 {
+        Some(p) => println!(
+            "  best F1={:.3} at score>={:.3}{} (precision={:.3}, recall={:.3})",
+            p.f1,
+            p.threshold,
+            calibrated(p.calibrated_threshold),
+            p.precision,
+            p.recall
+        ),
+        None => println!("  no predictions to sweep"),
+    }
+    match &sweep.precision_at_recall {
+        Some(p) => println!(
+            "  recall>={:.2}: precision={:.3} at score>={:.3}{}",
+            sweep.target_recall,
+            p.precision,
+            p.threshold,
+            calibrated(p.calibrated_threshold)
+        ),
+        None => println!("  recall {:.2} is not reachable", sweep.target_recall),
+    }
+    match &sweep.calibrated_reliability {
+        Some(after) => println!(
+            "  ECE={:.3} -> {:.3} with {:?}",
+            sweep.reliability.ece,
+            after.ece,
+            sweep
+                .calibration
+                .expect("calibrated reliability implies a calibration")
+        ),
+        None => println!("  ECE={:.3}", sweep.reliability.ece),
+    }
+}
+
+/// Store the sweep's calibration in the metadata sidecar of checkpoint `label`.
+fn write_calibration(label: &str, sweep: &SweepReport) -> anyhow::Result<()> {
+    let Some(calibration) = sweep.calibration else {
+        return Ok(());
+    };
+    let path = Path::new(label);
+    let Ok(mut meta) = CheckpointMetadata::load(path) else {
+        println!("  no checkpoint metadata for {label}; not writing calibration");
+        return Ok(());
+    };
+    meta.calibration = Some(calibration);
+    meta.save(path)?;
+    println!(
+        "  Wrote calibration to {}",
+        checkpoint::sidecar_path(path).display()
+    );
+    Ok(())
+}
+
+/// Write `report.md` listing the worst frames and render their overlays into `overlays/`.
+fn write_report(
+    dir: &Path,
+    label: &str,
+    frames: &[FramePredictions],
+    input: &EvalInput,
+    args: &Args,
+    device: &Device,
+) -> anyhow::Result<()> {
+    let worst = worst_frames(frames, args.report_top);
+    let overlay_dir = dir.join("overlays");
+    fs::create_dir_all(&overlay_dir)?;
+    let overlay_name = |frame: usize| format!("overlays/frame_{frame:05}.png");
+
+    let mut pending: std::collections::BTreeMap<usize, &FramePredictions> =
+        worst.iter().map(|f| (f.frame, *f)).collect();
+    let mut offset = 0;
+    for batch in input.source.batches(device) {
+        if pending.is_empty() {
+            break;
+        }
+        let images = batch?.images;
+        let [n, c, h, w] = images.dims();
+        for b in 0..n {
+            if let Some(frame) = pending.remove(&(offset + b)) {
+                let image = images
                     .clone()
-                    .slice([0..boxes.dims()[0], 0..1, 0..4])
-                    .reshape([boxes.dims()[0], 4]);
+                    .slice([b..b + 1, 0..c, 0..h, 0..w])
+                    .reshape([c, h, w]);
+                let mut overlay = image_from_tensor(image);
+                render_overlay(&mut overlay, frame);
+                let path = dir.join(overlay_name(frame.frame));
+                overlay
+                    .save(&path)
+                    .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+            }
+        }
+        offset += n;
+    }
+
+    let mut md = format!(
+        "# Worst frames: {label}\n\n\
+         {} of {} frames have errors at iou_thresh={} score_thresh={}. \
+         Ground truth is drawn in green, predictions in red.\n\n\
+         | rank | frame | image | fn | fp | tp | overlay |\n\
+         |---|---|---|---|---|---|---|\n",
+        frames.iter().filter(|f| f.errors() > 0).count(),
+        frames.len(),
+        args.iou_threshold,
+        args.score_threshold
+    );
+    for (rank, frame) in worst.iter().enumerate() {
+        let name = overlay_name(frame.frame);
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} | [{name}]({name}) |\n",
+            rank + 1,
+            frame.frame,
+            frame.image.as_deref().unwrap_or("-"),
+            frame.fn_,
+            frame.fp,
+            frame.tp,
+        ));
+    }
+    let path = dir.join("report.md");
+    fs::write(&path, md).map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+    println!(
+        "Wrote worst-frames report ({} frames) to {}",
+        worst.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn print_report(report: &EvalReport) {
+    println!(
+        "Eval complete: precision={:.3}, recall={:.3} (tp={}, fp={}, fn={}, iou_thresh={}, score_thresh={})",
+        report.precision,
+        report.recall,
+        report.tp,
+        report.fp,
+        report.fn_,
+        report.iou_threshold,
+        report.score_threshold
+    );
+    let Some(coco) = &report.coco else {
+        return;
+    };
+    let fmt = |v: Option<f32>| v.map_or_else(|| "n/a".to_string(), |v| format!("{v:.3}"));
+    println!(
+        "  AP@[.50:.95]={} AP@.50={} AP@.75={}",
+        fmt(coco.map),
+        fmt(coco.map50),
+        fmt(coco.map75)
+    );
+    let areas = report.areas_resized_to.map_or_else(String::new, |[w, h]| {
+        format!(" (areas in {w}x{h} resized pixels)")
+    });
+    println!(
+        "  AP small={} medium={} large={}{areas}",
+        fmt(coco.map_small),
+        fmt(coco.map_medium),
+        fmt(coco.map_large)
+    );
+    println!(
+        "  AR@1={} AR@10={} AR@100={}",
+        fmt(coco.ar1),
+        fmt(coco.ar10),
+        fmt(coco.ar100)
+    );
+    for class in &report.per_class {
+        let name = class
+            .name
+            .as_ref()
+            .map_or_else(String::new, |name| format!(" ({name})"));
+        println!(
+            "  class {}{name}: AP@[.50:.95]={} AP@.50={} (gt={})",
+            class.class_id,
+            fmt(class.map),
+            fmt(class.map50),
+            class.num_gt
+        );
+    }
+}
+
+/// Aggregate metrics, per-frame records (multibox only), and scored predictions for sweeps.
+fn evaluate(
+    label: &str,
+    model: &EvalModel,
+    input: &EvalInput,
+    args: &Args,
+    device: &Device,
+) -> anyhow::Result<Evaluation> {
+    let source = input.source;
+    let mut records = Vec::new();
+    let mut acc = DetectionAccumulator::new(args.iou_threshold, args.score_threshold);
+    let mut coco = CocoEvaluator::new();
+    let mut frames = 0;
+    let (mut tp, mut fp, mut fn_) = (0, 0, 0);
+    let mut scored = Vec::new();
+    let mut positives = 0;
+    let mut per_class = Vec::new();
+
+    match model {
+        EvalModel::Linear(model) => {
+            for batch in source.batches(device) {
+                let batch = batch?;
+                let input = linear_input(batch.features.clone());
 
                 let mask = batch.box_mask.clone();
                 let has_box = mask.clone().sum_dim(1).reshape([mask.dims()[0], 1]);
 
-                let preds = model.forward(first_box);
-                // Treat preds > 0.5 as positive.
+                let preds = model.forward(input);
                 let preds_vec: Vec<f32> = preds.into_data().to_vec::<f32>().unwrap_or_default();
                 let has_box_vec: Vec<f32> = has_box.into_data().to_vec::<f32>().unwrap_or_default();
-                for (p, t) in preds_vec.into_iter().zip(has_box_vec.into_iter()) {
-                    let pred_pos = p > 0.5;
+                for (p, t) in preds_vec.into_iter().zip(has_box_vec) {
+                    frames += 1;
+                    let pred_pos = p >= args.score_threshold;
                     let gt_pos = t > 0.5;
+                    scored.push((p, gt_pos));
+                    positives += usize::from(gt_pos);
                     match (pred_pos, gt_pos) {
-                        (true, true) => total_tp += 1.0,
-                        (true, false) => total_fp += 1.0,
-                        (false, true) => total_fn += 1.0,
+                        (true, true) => tp += 1,
+                        (true, false) => fp += 1,
+                        (false, true) => fn_ += 1,
                         (false, false) => {}
                     }
                 }
             }
         }
-        ModelKind::Big => {
-            let model = match ckpt {
-                Some(ref p) => load_multibox_model_from_checkpoint(p, &device, args.max_boxes)
-                    .unwrap_or_else(|e| {
-                        println!("Failed to load checkpoint {p}; using fresh model ({e})");
-                        MultiboxModel::<TrainBackend>::new(
-                            MultiboxModelConfig {
-                                input_dim: Some(4 + 8),
-                                max_boxes: args.max_boxes,
-                                ..Default::default()
-                            },
-                            &device,
-                        )
-                    }),
-                None => {
-                    println!("No checkpoint provided; using fresh MultiboxModel");
-                    MultiboxModel::<TrainBackend>::new(
-                        MultiboxModelConfig {
-                            input_dim: Some(4 + 8),
-                            max_boxes: args.max_boxes,
-                            ..Default::default()
-                        },
-                        &device,
-                    )
-                }
+        EvalModel::Multibox(_) | EvalModel::Ensemble(_) => {
+            let num_classes = match model {
+                EvalModel::Multibox(model) => model.num_classes(),
+                EvalModel::Ensemble(members) => members[0].0.num_classes(),
+                _ => 1,
             };
-            for chunk in samples.chunks(batch_size) {
-                let batch = collate::<TrainBackend>(chunk, args.max_boxes)?;
-                let boxes = batch.boxes.clone();
-                let first_box = boxes
-                    .clone()
-                    .slice([0..boxes.dims()[0], 0..1, 0..4])
-                    .reshape([boxes.dims()[0], 4]);
-                let features = batch.features.clone();
-                let input = burn::tensor::Tensor::cat(vec![first_box, features], 1);
-
-                let (pred_boxes, pred_scores) = model.forward_multibox(input);
-                let gt_boxes = batch.boxes.clone();
-                let gt_mask = batch.box_mask.clone();
-
-                // Simple IoU TP/FP: for each pred with score>0.5, see if it matches a GT.
-                let pb = pred_boxes.into_data().to_vec::<f32>().unwrap_or_default();
-                let ps = pred_scores.into_data().to_vec::<f32>().unwrap_or_default();
-                let gb = gt_boxes.into_data().to_vec::<f32>().unwrap_or_default();
-                let gm = gt_mask.into_data().to_vec::<f32>().unwrap_or_default();
-
-                let bsz = chunk.len();
-                for b in 0..bsz {
-                    // Collect GT boxes for this sample.
-                    let mut gt_list = Vec::new();
-                    for g in 0..args.max_boxes {
-                        if gm[b * args.max_boxes + g] > 0.5 {
-                            gt_list.push([
-                                gb[(b * args.max_boxes + g) * 4],
-                                gb[(b * args.max_boxes + g) * 4 + 1],
-                                gb[(b * args.max_boxes + g) * 4 + 2],
-                                gb[(b * args.max_boxes + g) * 4 + 3],
-                            ]);
-                        }
-                    }
-                    let mut gt_matched = vec![false; gt_list.len()];
-                    for p in 0..args.max_boxes {
-                        let score = ps[b * args.max_boxes + p];
-                        if score <= 0.5 {
-                            continue;
-                        }
-                        let pb_box = [
-                            pb[(b * args.max_boxes + p) * 4],
-                            pb[(b * args.max_boxes + p) * 4 + 1],
-                            pb[(b * args.max_boxes + p) * 4 + 2],
-                            pb[(b * args.max_boxes + p) * 4 + 3],
-                        ];
-                        let mut matched = false;
-                        for (i, gb_box) in gt_list.iter().enumerate() {
-                            let iou = iou_xyxy(pb_box, *gb_box);
-                            if iou >= args.iou_threshold {
-                                matched = true;
-                                gt_matched[i] = true;
-                                break;
-                            }
-                        }
-                        if matched {
-                            total_tp += 1.0;
-                        } else {
-                            total_fp += 1.0;
-                        }
-                    }
-                    for matched in gt_matched {
-                        if !matched {
-                            total_fn += 1.0;
-                        }
+            let mut class_coco: Vec<CocoEvaluator> = if num_classes > 1 {
+                (0..num_classes).map(|_| CocoEvaluator::new()).collect()
+            } else {
+                Vec::new()
+            };
+            for batch in source.batches(device) {
+                let batch = batch?;
+                let [_, _, height, width] = batch.images.dims();
+                let preds = match model {
+                    EvalModel::Multibox(model) => multibox_predictions(model, &batch, args),
+                    EvalModel::Ensemble(members) => ensemble_predictions(members, &batch, args),
+                    EvalModel::Linear(_) => unreachable!("linear models are scored per frame"),
+                };
+                let max_gt = batch.boxes.dims()[1];
+                let gb = batch.boxes.into_data().to_vec::<f32>().unwrap_or_default();
+                let gm = batch
+                    .box_mask
+                    .into_data()
+                    .to_vec::<f32>()
+                    .unwrap_or_default();
+                let gc = batch
+                    .classes
+                    .into_data()
+                    .to_vec::<f32>()
+                    .unwrap_or_default();
+                let corners =
+                    |v: &[f32], i: usize| [v[i * 4], v[i * 4 + 1], v[i * 4 + 2], v[i * 4 + 3]];
+
+                for (b, pred) in preds.iter().enumerate() {
+                    let gt_slots: Vec<usize> = (0..max_gt)
+                        .filter(|g| gm[b * max_gt + g] > 0.5)
+                        .map(|g| b * max_gt + g)
+                        .collect();
+                    let gts: Vec<[f32; 4]> = gt_slots.iter().map(|&g| corners(&gb, g)).collect();
+                    acc.add_frame(&pred.boxes, &pred.scores, &gts);
+                    for (class, evaluator) in class_coco.iter_mut().enumerate() {
+                        let (boxes, scores): (Vec<[f32; 4]>, Vec<f32>) = (0..pred.boxes.len())
+                            .filter(|&i| pred.classes[i] as usize == class)
+                            .map(|i| (pred.boxes[i], pred.scores[i]))
+                            .unzip();
+                        let class_gts: Vec<[f32; 4]> = gt_slots
+                            .iter()
+                            .filter(|&&g| gt_class(gc[g], num_classes) == class)
+                            .map(|&g| corners(&gb, g))
+                            .collect();
+                        evaluator.add_frame(
+                            &boxes,
+                            &scores,
+                            &class_gts,
+                            (width as u32, height as u32),
+                        );
                     }
+                    let (predictions, ground_truth) = match_frame(
+                        &pred.boxes,
+                        &pred.scores,
+                        &gts,
+                        args.iou_threshold,
+                        args.score_threshold,
+                    );
+                    let frame = records.len();
+                    let image = input
+                        .images
+                        .as_ref()
+                        .and_then(|images| images.get(frame).cloned());
+                    records.push(FramePredictions::new(
+                        label,
+                        frame,
+                        image,
+                        predictions,
+                        ground_truth,
+                    ));
+                    coco.add_frame(
+                        &pred.boxes,
+                        &pred.scores,
+                        &gts,
+                        (width as u32, height as u32),
+                    );
                 }
             }
+            let names = checkpoint_class_names(label);
+            per_class = class_coco
+                .iter()
+                .enumerate()
+                .map(|(class_id, evaluator)| {
+                    let metrics = evaluator.evaluate();
+                    ClassMetrics {
+                        class_id,
+                        name: names.as_ref().and_then(|n| n.get(class_id).cloned()),
+                        num_gt: metrics.num_gt,
+                        map: metrics.map,
+                        map50: metrics.map50,
+                    }
+                })
+                .collect();
+            frames = acc.frames();
+            (tp, fp, fn_) = acc.counts();
+            scored = acc.ranked().to_vec();
+            positives = acc.num_gt();
         }
     }
 
-    let precision = if total_tp + total_fp > 0.0 {
-        total_tp / (total_tp + total_fp)
-    } else {
-        0.0
+    let ratio = |num: usize, den: usize| {
+        if den == 0 {
+            0.0
+        } else {
+            num as f32 / den as f32
+        }
     };
-    let recall = if total_tp + total_fn > 0.0 {
-        total_tp / (total_tp + total_fn)
-    } else {
-        0.0
+    let report = EvalReport {
+        checkpoint: label.to_string(),
+        frames,
+        iou_threshold: args.iou_threshold,
+        score_threshold: args.score_threshold,
+        precision: ratio(tp, tp + fp),
+        recall: ratio(tp, tp + fn_),
+        tp,
+        fp,
+        fn_,
+        coco: (!matches!(model, EvalModel::Linear(_))).then(|| coco.evaluate()),
+        areas_resized_to: input
+            .resized_to
+            .filter(|_| !matches!(model, EvalModel::Linear(_)))
+            .map(|(w, h)| [w, h]),
+        per_class,
     };
+    Ok(Evaluation {
+        report,
+        frames: records,
+        scored,
+        positives,
+    })
+}
 
-    println!(
-        "Eval complete: precision={:.3}, recall={:.3} (tp={}, fp={}, fn={}, iou_thresh={})",
-        precision, recall, total_tp, total_fp, total_fn, args.iou_threshold
-    );
+/// Index of the largest logit.
+fn argmax(logits: &[f32]) -> usize {
+    logits
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map_or(0, |(i, _)| i)
+}
 
-    Ok(())
+/// A GT class id stored as a float, clamped into the model's classes.
+fn gt_class(value: f32, num_classes: usize) -> usize {
+    (value.round().max(0.0) as usize).min(num_classes.saturating_sub(1))
+}
+
+/// Class names from a checkpoint's metadata (`None` for fresh models or unnamed classes).
+fn checkpoint_class_names(path: &str) -> Option<Vec<String>> {
+    CheckpointMetadata::load(Path::new(path))
+        .ok()
+        .and_then(|meta| meta.class_names)
 }
 
-fn iou_xyxy(a: [f32; 4], b: [f32; 4]) -> f32 {
-    let ax0 = a[0].min(a[2]);
-    let ay0 = a[1].min(a[3]);
-    let ax1 = a[0].max(a[2]);
-    let ay1 = a[1].max(a[3]);
-    let bx0 = b[0].min(b[2]);
-    let by0 = b[1].min(b[3]);
-    let bx1 = b[0].max(b[2]);
-    let by1 = b[1].max(b[3]);
-
-    let inter_x0 = ax0.max(bx0);
-    let inter_y0 = ay0.max(by0);
-    let inter_x1 = ax1.min(bx1);
-    let inter_y1 = ay1.min(by1);
-
-    let inter_w = (inter_x1 - inter_x0).max(0.0);
-    let inter_h = (inter_y1 - inter_y0).max(0.0);
-    let inter_area = inter_w * inter_h;
-
-    let area_a = (ax1 - ax0).max(0.0) * (ay1 - ay0).max(0.0);
-    let area_b = (bx1 - bx0).max(0.0) * (by1 - by0).max(0.0);
-    let denom = area_a + area_b - inter_area;
-    if denom <= 0.0 {
-        0.0
-    } else {
-        inter_area / denom
+/// Run a multibox model on a batch (every `--tta` variant of it, fused like
+/// `inference::tta::TtaDetector`, when set).
+fn multibox_predictions(
+    model: &MultiboxModel<TrainBackend>,
+    batch: &CollatedBatch<TrainBackend>,
+    args: &Args,
+) -> Vec<DetectionResult> {
+    if !args.tta {
+        return forward_predictions(model, batch.images.clone(), batch, args.nms_iou);
+    }
+    let [_, _, height, width] = batch.images.dims();
+    let variants: Vec<(Vec<DetectionResult>, f32)> = tta_transforms(true, &args.tta_scales)
+        .expect("--tta-scales are checked in main")
+        .into_iter()
+        .map(|transform| {
+            let images = tta_images(&batch.images, transform);
+            let mut preds = forward_predictions(model, images, batch, args.nms_iou);
+            for pred in &mut preds {
+                transform.invert_boxes(&mut pred.boxes, (width as u32, height as u32));
+            }
+            (preds, 1.0)
+        })
+        .collect();
+    fuse_frames(&variants, &fusion_config(args))
+}
+
+/// `images` (`batch.images` or a transformed copy) through a multibox model, NMS'd per frame.
+/// Each result's classes are the argmax class of its boxes (0 when class-agnostic).
+fn forward_predictions(
+    model: &MultiboxModel<TrainBackend>,
+    images: Tensor<TrainBackend, 4>,
+    batch: &CollatedBatch<TrainBackend>,
+    nms_iou: f32,
+) -> Vec<DetectionResult> {
+    // Global features stay those of the original frame.
+    let (pred_boxes, pred_scores, class_logits) =
+        model.forward_multibox_images_with_classes(images, batch.features.clone());
+    // Slot count comes from the checkpoint's config, not the collation padding.
+    let [frames, max_pred] = pred_scores.dims();
+    let num_classes = class_logits.dims()[2];
+    let pb = pred_boxes.into_data().to_vec::<f32>().unwrap_or_default();
+    let ps = pred_scores.into_data().to_vec::<f32>().unwrap_or_default();
+    let pc = class_logits.into_data().to_vec::<f32>().unwrap_or_default();
+    (0..frames)
+        .map(|b| {
+            let slot = |p: usize| b * max_pred + p;
+            let frame_boxes: Vec<[f32; 4]> = (0..max_pred)
+                .map(|p| {
+                    let i = slot(p) * 4;
+                    [pb[i], pb[i + 1], pb[i + 2], pb[i + 3]]
+                })
+                .collect();
+            let frame_scores = &ps[slot(0)..slot(max_pred)];
+            let keep = nms(&frame_boxes, frame_scores, nms_iou);
+            let scores: Vec<f32> = keep.iter().map(|&p| frame_scores[p]).collect();
+            DetectionResult {
+                frame_id: b as u64,
+                positive: !keep.is_empty(),
+                confidence: scores.iter().copied().fold(0.0, f32::max),
+                boxes: keep.iter().map(|&p| frame_boxes[p]).collect(),
+                scores,
+                classes: keep
+                    .iter()
+                    .map(|&p| argmax(&pc[slot(p) * num_classes..slot(p + 1) * num_classes]) as u32)
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Run every ensemble member on a batch and fuse their per-frame predictions, as
+/// [`vision_core::ensemble::EnsembleDetector`] does.
+fn ensemble_predictions(
+    members: &[(MultiboxModel<TrainBackend>, f32)],
+    batch: &CollatedBatch<TrainBackend>,
+    args: &Args,
+) -> Vec<DetectionResult> {
+    let outputs: Vec<(Vec<DetectionResult>, f32)> = members
+        .iter()
+        .map(|(model, weight)| (multibox_predictions(model, batch, args), *weight))
+        .collect();
+    fuse_frames(&outputs, &fusion_config(args))
+}
+
+/// Fuse weighted per-frame outputs of the same batch frame by frame with [`fuse_results`].
+fn fuse_frames(
+    outputs: &[(Vec<DetectionResult>, f32)],
+    fusion: &FusionConfig,
+) -> Vec<DetectionResult> {
+    let frames = outputs.first().map_or(0, |(preds, _)| preds.len());
+    (0..frames)
+        .map(|b| {
+            let results: Vec<(&DetectionResult, f32)> = outputs
+                .iter()
+                .map(|(preds, weight)| (&preds[b], *weight))
+                .collect();
+            fuse_results(b as u64, &results, fusion)
+        })
+        .collect()
+}
+
+/// Apply a TTA transform to every `[3, H, W]` image of a batch.
+fn tta_images(
+    images: &Tensor<TrainBackend, 4>,
+    transform: TtaTransform,
+) -> Tensor<TrainBackend, 4> {
+    if transform == TtaTransform::IDENTITY {
+        return images.clone();
+    }
+    let dims = images.dims();
+    let [_, channels, height, width] = dims;
+    let data = images
+        .clone()
+        .into_data()
+        .to_vec::<f32>()
+        .unwrap_or_default();
+    let out: Vec<f32> = data
+        .chunks(channels * height * width)
+        .flat_map(|chw| transform.apply_chw(chw, (width as u32, height as u32)))
+        .collect();
+    Tensor::from_data(TensorData::new(out, dims), &images.device())
+}
+
+fn fusion_config(args: &Args) -> FusionConfig {
+    FusionConfig {
+        method: args.fusion,
+        iou_threshold: args.fusion_iou,
+        ..FusionConfig::default()
+    }
+}
+
+/// The `--ensemble` entry: every multibox checkpoint with its `--ensemble-weight`.
+fn ensemble_model(models: &[(String, EvalModel)], args: &Args) -> anyhow::Result<EvalModel> {
+    let weights = match args.ensemble_weight.as_slice() {
+        [] => vec![1.0; args.checkpoint.len()],
+        weights if weights.len() == args.checkpoint.len() => weights.to_vec(),
+        weights => anyhow::bail!(
+            "got {} --ensemble-weight values for {} checkpoints",
+            weights.len(),
+            args.checkpoint.len()
+        ),
+    };
+    if let Some(weight) = weights.iter().find(|w| !w.is_finite() || **w <= 0.0) {
+        anyhow::bail!("--ensemble-weight must be positive (got {weight})");
+    }
+    let members: Vec<(MultiboxModel<TrainBackend>, f32)> = models
+        .iter()
+        .zip(weights)
+        .filter_map(|((label, model), weight)| match model {
+            EvalModel::Multibox(model) => Some((model.clone(), weight)),
+            _ => {
+                println!("  ensemble: skipping {label} (no boxes to fuse)");
+                None
+            }
+        })
+        .collect();
+    if members.len() < 2 {
+        anyhow::bail!("--ensemble needs at least two multibox checkpoints");
+    }
+    let num_classes = members[0].0.num_classes();
+    if members
+        .iter()
+        .any(|(model, _)| model.num_classes() != num_classes)
+    {
+        anyhow::bail!("--ensemble members must share the same number of classes");
     }
+    Ok(EvalModel::Ensemble(members))
 }
@@ -1,7 +1,7 @@
-use clap::Parser;
-use training::util::{run_train, TrainArgs};
+use training::config::parse_train_args;
+use training::util::run_train;
 
 fn main() -> anyhow::Result<()> {
-    let args = TrainArgs::parse();
+    let args = parse_train_args(std::env::args_os())?;
     run_train(args)
 }
@@ -0,0 +1,292 @@
+//! Score threshold sweeps and calibration fitting for `eval --sweep-out`.
+//!
+//! Inputs are scored predictions labelled correct or not (`(score, is_true_positive)`, as ranked
+//! by `metrics::DetectionAccumulator`) plus the number of ground-truth positives. `sweep`
+//! reports precision/recall/F1 at every distinct score, the F1-optimal threshold, and the
+//! threshold that reaches a target recall with the best precision. It also fits a
+//! `models::calibration::Calibration` (temperature or Platt scaling on the score logit, with
+//! Platt's smoothed targets) and bins predictions into reliability diagrams before and after
+//! calibration. Thresholds are reported on raw scores and, when a calibration is fitted, on the
+//! calibrated scale that inference applies once the calibration is stored in the checkpoint.
+
+use clap::ValueEnum;
+use models::calibration::{logit, sigmoid, Calibration};
+use serde::{Deserialize, Serialize};
+
+/// Which calibration to fit.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalibrationMethod {
+    /// Do not fit a calibration.
+    None,
+    /// One temperature dividing the score logit.
+    Temperature,
+    /// Scale and offset on the score logit.
+    #[default]
+    Platt,
+}
+
+/// Precision/recall when keeping every prediction with score at or above `threshold`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdPoint {
+    pub threshold: f32,
+    /// `threshold` after calibration (the value to use as an objectness threshold once the
+    /// calibration is stored with the checkpoint).
+    pub calibrated_threshold: Option<f32>,
+    pub precision: f32,
+    pub recall: f32,
+    pub f1: f32,
+}
+
+/// One confidence bin of a reliability diagram.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReliabilityBin {
+    pub lower: f32,
+    pub upper: f32,
+    pub count: usize,
+    /// Mean score of the predictions in the bin (0 when empty).
+    pub mean_confidence: f32,
+    /// Fraction of the bin's predictions that are true positives (0 when empty).
+    pub accuracy: f32,
+}
+
+/// Confidence-vs-accuracy bins plus the expected calibration error (count-weighted gap).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReliabilityDiagram {
+    pub bins: Vec<ReliabilityBin>,
+    pub ece: f32,
+}
+
+/// Result of `sweep`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepReport {
+    pub predictions: usize,
+    pub positives: usize,
+    /// Threshold with the highest F1.
+    pub best_f1: Option<ThresholdPoint>,
+    pub target_recall: f32,
+    /// Highest-precision threshold whose recall reaches `target_recall`.
+    pub precision_at_recall: Option<ThresholdPoint>,
+    pub calibration: Option<Calibration>,
+    pub reliability: ReliabilityDiagram,
+    pub calibrated_reliability: Option<ReliabilityDiagram>,
+    /// Every distinct threshold, in descending score order.
+    pub curve: Vec<ThresholdPoint>,
+}
+
+/// Sweep thresholds over `scored` predictions and fit `method`.
+pub fn sweep(
+    scored: &[(f32, bool)],
+    positives: usize,
+    method: CalibrationMethod,
+    target_recall: f32,
+    bins: usize,
+) -> anyhow::Result<SweepReport> {
+    let calibration = fit_calibration(scored, method)?;
+    let mut curve = threshold_curve(scored, positives);
+    if let Some(calibration) = &calibration {
+        for point in &mut curve {
+            point.calibrated_threshold = Some(calibration.apply(point.threshold));
+        }
+    }
+    let calibrated_reliability = calibration.map(|calibration| {
+        let calibrated: Vec<(f32, bool)> = scored
+            .iter()
+            .map(|&(score, tp)| (calibration.apply(score), tp))
+            .collect();
+        reliability_diagram(&calibrated, bins)
+    });
+    Ok(SweepReport {
+        predictions: scored.len(),
+        positives,
+        best_f1: best_f1(&curve),
+        target_recall,
+        precision_at_recall: precision_at_recall(&curve, target_recall),
+        calibration,
+        reliability: reliability_diagram(scored, bins),
+        calibrated_reliability,
+        curve,
+    })
+}
+
+/// Precision/recall/F1 at every distinct score, highest first.
+pub fn threshold_curve(scored: &[(f32, bool)], positives: usize) -> Vec<ThresholdPoint> {
+    let mut ranked = scored.to_vec();
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let mut curve = Vec::new();
+    let mut tp = 0usize;
+    for (i, &(score, is_tp)) in ranked.iter().enumerate() {
+        tp += usize::from(is_tp);
+        // Emit once per distinct score, after all its ties are counted.
+        if ranked.get(i + 1).is_some_and(|next| next.0 == score) {
+            continue;
+        }
+        let precision = tp as f32 / (i + 1) as f32;
+        let recall = if positives == 0 {
+            0.0
+        } else {
+            tp as f32 / positives as f32
+        };
+        let f1 = if precision + recall > 0.0 {
+            2.0 * precision * recall / (precision + recall)
+        } else {
+            0.0
+        };
+        curve.push(ThresholdPoint {
+            threshold: score,
+            calibrated_threshold: None,
+            precision,
+            recall,
+            f1,
+        });
+    }
+    curve
+}
+
+/// The point with the highest F1 (the highest threshold among ties).
+pub fn best_f1(curve: &[ThresholdPoint]) -> Option<ThresholdPoint> {
+    curve
+        .iter()
+        .copied()
+        .reduce(|best, p| if p.f1 > best.f1 { p } else { best })
+}
+
+/// The highest-precision point with recall at least `target` (the highest threshold among ties).
+pub fn precision_at_recall(curve: &[ThresholdPoint], target: f32) -> Option<ThresholdPoint> {
+    curve
+        .iter()
+        .copied()
+        .filter(|p| p.recall >= target)
+        .reduce(|best, p| {
+            if p.precision > best.precision {
+                p
+            } else {
+                best
+            }
+        })
+}
+
+/// Bin predictions by score into `bins` equal-width bins over [0, 1].
+pub fn reliability_diagram(scored: &[(f32, bool)], bins: usize) -> ReliabilityDiagram {
+    let bins = bins.max(1);
+    let mut sums = vec![(0usize, 0.0f64, 0usize); bins];
+    for &(score, is_tp) in scored {
+        let idx = ((score.clamp(0.0, 1.0) * bins as f32) as usize).min(bins - 1);
+        sums[idx].0 += 1;
+        sums[idx].1 += score as f64;
+        sums[idx].2 += usize::from(is_tp);
+    }
+    let total = scored.len().max(1) as f32;
+    let mut ece = 0.0;
+    let bins = sums
+        .iter()
+        .enumerate()
+        .map(|(i, &(count, score_sum, tps))| {
+            let (mean_confidence, accuracy) = if count == 0 {
+                (0.0, 0.0)
+            } else {
+                ((score_sum / count as f64) as f32, tps as f32 / count as f32)
+            };
+            ece += count as f32 / total * (accuracy - mean_confidence).abs();
+            ReliabilityBin {
+                lower: i as f32 / bins as f32,
+                upper: (i + 1) as f32 / bins as f32,
+                count,
+                mean_confidence,
+                accuracy,
+            }
+        })
+        .collect();
+    ReliabilityDiagram { bins, ece }
+}
+
+/// Fit `method` to the scored predictions by maximum likelihood.
+pub fn fit_calibration(
+    scored: &[(f32, bool)],
+    method: CalibrationMethod,
+) -> anyhow::Result<Option<Calibration>> {
+    if method == CalibrationMethod::None {
+        return Ok(None);
+    }
+    let positives = scored.iter().filter(|(_, tp)| *tp).count();
+    let negatives = scored.len() - positives;
+    if positives == 0 || negatives == 0 {
+        anyhow::bail!(
+            "calibration needs both true and false positives (got {positives} TP, {negatives} FP)"
+        );
+    }
+    // Platt's smoothed targets keep the fit finite when the classes separate perfectly.
+    let hi = (positives as f64 + 1.0) / (positives as f64 + 2.0);
+    let lo = 1.0 / (negatives as f64 + 2.0);
+    let samples: Vec<(f64, f64)> = scored
+        .iter()
+        .map(|&(score, tp)| (logit(score) as f64, if tp { hi } else { lo }))
+        .collect();
+    let (a, b) = fit_logistic(&samples, method == CalibrationMethod::Platt);
+    if !(a > 0.0 && a.is_finite() && b.is_finite()) {
+        anyhow::bail!("scores do not separate true from false positives (fitted slope {a:.4})");
+    }
+    Ok(Some(match method {
+        CalibrationMethod::Temperature => Calibration::Temperature {
+            temperature: (1.0 / a) as f32,
+        },
+        _ => Calibration::Platt {
+            a: a as f32,
+            b: b as f32,
+        },
+    }))
+}
+
+/// Logistic regression `sigmoid(a * x + b)` on `(x, target)` pairs via damped Newton steps;
+/// `b` stays 0 unless `with_bias`.
+fn fit_logistic(samples: &[(f64, f64)], with_bias: bool) -> (f64, f64) {
+    let nll = |a: f64, b: f64| -> f64 {
+        samples
+            .iter()
+            .map(|&(x, t)| {
+                let z = a * x + b;
+                // log(1 + e^z) - t * z, computed stably.
+                z.max(0.0) + (-z.abs()).exp().ln_1p() - t * z
+            })
+            .sum()
+    };
+    let (mut a, mut b) = (1.0f64, 0.0f64);
+    let mut loss = nll(a, b);
+    for _ in 0..100 {
+        let (mut ga, mut gb, mut haa, mut hab, mut hbb) = (0.0, 0.0, 1e-9, 0.0, 1e-9);
+        for &(x, t) in samples {
+            let p = sigmoid((a * x + b) as f32) as f64;
+            let w = p * (1.0 - p);
+            ga += (p - t) * x;
+            gb += p - t;
+            haa += w * x * x;
+            hab += w * x;
+            hbb += w;
+        }
+        let (da, db) = if with_bias {
+            let det = haa * hbb - hab * hab;
+            if det.abs() < 1e-12 {
+                break;
+            }
+            ((hbb * ga - hab * gb) / det, (haa * gb - hab * ga) / det)
+        } else {
+            (ga / haa, 0.0)
+        };
+        let mut step = 1.0;
+        let mut improved = false;
+        while step > 1e-6 {
+            let (na, nb) = (a - step * da, b - step * db);
+            let next = nll(na, nb);
+            if next <= loss {
+                improved = loss - next > 1e-10;
+                (a, b, loss) = (na, nb, next);
+                break;
+            }
+            step *= 0.5;
+        }
+        if !improved {
+            break;
+        }
+    }
+    (a, b)
+}
@@ -0,0 +1,321 @@
+//! Declarative experiment files for `train --config`.
+//!
+//! A config is a TOML (or, by `.json` extension, JSON) document whose keys are `TrainArgs`
+//! field names grouped into sections:
+//!
+//! ```toml
+//! seed = 42
+//!
+//! [model]
+//! model = "big"
+//! max_boxes = 16
+//!
+//! [optimizer]
+//! lr = 3e-4
+//! grad_accum = 4
+//!
+//! [schedule]
+//! epochs = 20
+//! scheduler = "cosine"
+//! ```
+//!
+//! Values are applied as if they were passed as flags before the command line, so explicit CLI
+//! flags override the file; `--input-root` also replaces the file's `input_source` and
+//! `dataset_root`. Every run writes the fully resolved settings (defaults included) to
+//! `<checkpoint-dir>/resolved.toml`, plus a `[provenance]` section with the warehouse manifest
+//! version and code version; `train --config <run>/resolved.toml` replays the run and refuses to
+//! start if the warehouse manifest has changed since.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+
+use clap::{Parser, ValueEnum};
+use serde::Serializer;
+use toml::{Table, Value};
+
+use crate::util::TrainArgs;
+
+/// File name of the resolved config written next to the run's checkpoints.
+pub const RESOLVED_CONFIG_FILE: &str = "resolved.toml";
+
+/// Section holding run provenance; not mapped to flags except the warehouse version pin.
+const PROVENANCE: &str = "provenance";
+
+/// Config sections and the `TrainArgs` fields they group. Keys not listed here are top-level.
+const SECTIONS: &[(&str, &[&str])] = &[
+    (
+        "model",
+        &[
+            "model",
+            "backend",
+            "max_boxes",
+            "backbone_depth",
+            "backbone_width",
+            "num_classes",
+        ],
+    ),
+    (
+        "loss",
+        &[
+            "lambda_box",
+            "lambda_obj",
+            "lambda_cls",
+            "box_loss",
+            "obj_loss",
+            "class_loss",
+            "focal_alpha",
+            "focal_gamma",
+            "matcher",
+            "match_cost_l1",
+            "match_cost_giou",
+            "match_cost_obj",
+        ],
+    ),
+    (
+        "optimizer",
+        &[
+            "lr",
+            "grad_clip",
+            "grad_accum",
+            "ema",
+            "ema_decay",
+            "ema_warmup_steps",
+            "weights",
+        ],
+    ),
+    (
+        "schedule",
+        &[
+            "epochs",
+            "scheduler",
+            "lr_end",
+            "warmup_steps",
+            "lr_step_epochs",
+            "lr_gamma",
+            "one_cycle_pct",
+            "early_stop_patience",
+            "monitor",
+            "mode",
+            "early_stop_min_delta",
+        ],
+    ),
+    (
+        "data",
+        &[
+            "input_source",
+            "warehouse_manifest",
+            "dataset_root",
+            "input_root",
+            "labels_subdir",
+            "images_subdir",
+            "batch_size",
+            "drop_last",
+            "val_ratio",
+            "real_val_dir",
+        ],
+    ),
+    (
+        "augment",
+        &["flip_prob", "color_jitter_prob", "color_jitter_strength"],
+    ),
+    ("eval", &["infer_obj_thresh", "infer_iou_thresh"]),
+    (
+        "output",
+        &[
+            "checkpoint_out",
+            "metrics_out",
+            "status_file",
+            "checkpoint_dir",
+            "save_every",
+            "keep_last",
+            "keep_best",
+        ],
+    ),
+];
+
+/// Parse `train` arguments, expanding `--config <file>` into flags that the rest of the
+/// command line can override.
+pub fn parse_train_args<I, T>(argv: I) -> anyhow::Result<TrainArgs>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+    let Some(path) = config_path(&argv) else {
+        return Ok(TrainArgs::try_parse_from(argv)?);
+    };
+    let mut flags = load_config_flags(Path::new(&path))?;
+    if has_flag(&argv, "--input-root") {
+        // `--input-root` picks the data source itself; the file's choice must not conflict.
+        flags = without_flags(flags, &["--input-source", "--dataset-root"]);
+    }
+    let mut expanded = argv[..1.min(argv.len())].to_vec();
+    expanded.extend(flags);
+    expanded.extend(argv.into_iter().skip(1));
+    Ok(TrainArgs::try_parse_from(expanded)?)
+}
+
+/// The value of `--config` in `argv`, if present.
+fn config_path(argv: &[OsString]) -> Option<OsString> {
+    let mut iter = argv.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        let arg_str = arg.to_string_lossy();
+        if arg_str == "--config" {
+            return iter.next().cloned();
+        }
+        if let Some(path) = arg_str.strip_prefix("--config=") {
+            return Some(path.into());
+        }
+    }
+    None
+}
+
+/// Whether `argv` passes `flag`, as `--flag value` or `--flag=value`.
+fn has_flag(argv: &[OsString], flag: &str) -> bool {
+    argv.iter().skip(1).any(|arg| {
+        let arg = arg.to_string_lossy();
+        arg == flag
+            || arg
+                .strip_prefix(flag)
+                .is_some_and(|rest| rest.starts_with('='))
+    })
+}
+
+/// `flags` without the given string-valued `--flag value` pairs.
+fn without_flags(flags: Vec<OsString>, drop: &[&str]) -> Vec<OsString> {
+    let mut kept = Vec::with_capacity(flags.len());
+    let mut iter = flags.into_iter();
+    while let Some(flag) = iter.next() {
+        if drop.iter().any(|name| flag == *name) {
+            iter.next();
+        } else {
+            kept.push(flag);
+        }
+    }
+    kept
+}
+
+/// Read a config file and turn it into `--flag value` arguments.
+pub fn load_config_flags(path: &Path) -> anyhow::Result<Vec<OsString>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("failed to read config {}: {e}", path.display()))?;
+    let parse_err = |e: &dyn std::fmt::Display| {
+        anyhow::anyhow!("failed to parse config {}: {e}", path.display())
+    };
+    let table: Table = if path.extension().is_some_and(|ext| ext == "json") {
+        serde_json::from_str(&text).map_err(|e| parse_err(&e))?
+    } else {
+        toml::from_str(&text).map_err(|e| parse_err(&e))?
+    };
+    config_flags(&table).map_err(|e| anyhow::anyhow!("invalid config {}: {e}", path.display()))
+}
+
+/// Flags for the keys of a parsed config.
+pub fn config_flags(table: &Table) -> anyhow::Result<Vec<OsString>> {
+    let mut flags = Vec::new();
+    for (key, value) in table {
+        match value {
+            Value::Table(section) if key == PROVENANCE => {
+                if let Some(version) = section.get("warehouse_version") {
+                    push_flag(&mut flags, "expect_warehouse_version", version)?;
+                }
+            }
+            Value::Table(section) => {
+                if !SECTIONS.iter().any(|(name, _)| name == key) {
+                    anyhow::bail!("unknown section [{key}]");
+                }
+                for (field, value) in section {
+                    push_flag(&mut flags, field, value)?;
+                }
+            }
+            _ => push_flag(&mut flags, key, value)?,
+        }
+    }
+    Ok(flags)
+}
+
+fn push_flag(flags: &mut Vec<OsString>, key: &str, value: &Value) -> anyhow::Result<()> {
+    let flag = format!("--{}", key.replace('_', "-"));
+    match value {
+        Value::Boolean(b) => flags.push(format!("{flag}={b}").into()),
+        Value::String(s) => flags.extend([flag.into(), s.into()]),
+        Value::Integer(i) => flags.extend([flag.into(), i.to_string().into()]),
+        Value::Float(f) => flags.extend([flag.into(), f.to_string().into()]),
+        other => anyhow::bail!("`{key}` must be a string, number, or boolean (got {other})"),
+    }
+    Ok(())
+}
+
+/// The fully resolved settings of `args` as a sectioned TOML document.
+pub fn resolved_config(
+    args: &TrainArgs,
+    warehouse_version: Option<&str>,
+) -> anyhow::Result<String> {
+    let Value::Table(flat) = Value::try_from(args)? else {
+        anyhow::bail!("train arguments did not serialize to a table");
+    };
+    let mut doc = Table::new();
+    for (key, value) in flat {
+        match SECTIONS
+            .iter()
+            .find(|(_, keys)| keys.contains(&key.as_str()))
+        {
+            Some((section, _)) => {
+                doc.entry(section.to_string())
+                    .or_insert_with(|| Value::Table(Table::new()))
+                    .as_table_mut()
+                    .expect("sections are tables")
+                    .insert(key, value);
+            }
+            None => {
+                doc.insert(key, value);
+            }
+        }
+    }
+    let mut provenance = Table::new();
+    if let Some(version) = warehouse_version {
+        provenance.insert("warehouse_version".into(), version.into());
+    }
+    provenance.insert(
+        "code_version".into(),
+        burn_dataset::WarehouseManifest::resolve_code_version().into(),
+    );
+    doc.insert(PROVENANCE.into(), Value::Table(provenance));
+    Ok(toml::to_string_pretty(&doc)?)
+}
+
+/// Write `resolved.toml` for `args` into `dir`.
+pub fn write_resolved_config(
+    dir: &Path,
+    args: &TrainArgs,
+    warehouse_version: Option<&str>,
+) -> anyhow::Result<()> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(RESOLVED_CONFIG_FILE);
+    fs::write(&path, resolved_config(args, warehouse_version)?)
+        .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))
+}
+
+/// Serialize a clap `ValueEnum` as the name its flag accepts.
+pub(crate) fn value_enum<S: Serializer, T: ValueEnum>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let name = value
+        .to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default();
+    serializer.serialize_str(&name)
+}
+
+/// `value_enum` for optional flags.
+pub(crate) fn opt_value_enum<S: Serializer, T: ValueEnum>(
+    value: &Option<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => value_enum(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
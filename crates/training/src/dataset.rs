@@ -1,8 +1,10 @@
 use burn::tensor::TensorData;
 use burn::tensor::{backend::Backend, Tensor};
-use burn_dataset::BurnBatch;
+use burn_dataset::{BurnBatch, TransformPipeline};
 use data_contracts::capture::CaptureMetadata;
 use data_contracts::preprocess::{stats_from_chw_f32, stats_from_rgb_u8};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use serde::Deserialize;
 use std::fs;
 use std::path::PathBuf;
@@ -27,6 +29,8 @@ pub struct CollatedBatch<B: Backend> {
     pub boxes: Tensor<B, 3>,
     /// Mask indicating which box slots are populated (shape: [batch, max_boxes]).
     pub box_mask: Tensor<B, 2>,
+    /// Class id of each box slot as a float, 0 in padding (shape: [batch, max_boxes]).
+    pub classes: Tensor<B, 2>,
     /// Global/image features per sample (mean/std RGB, aspect ratio, box count) shape [batch, F].
     pub features: Tensor<B, 2>,
 }
@@ -54,9 +58,48 @@ impl DatasetPathConfig {
     }
 }
 
+/// Split capture-log samples into `(train, val)` the way warehouse loaders split shards.
+///
+/// Samples are ordered by image path, shuffled when `seed` is set, and the first
+/// `round(val_ratio * n)` go to validation.
+pub fn split_samples(
+    mut samples: Vec<RunSample>,
+    val_ratio: f32,
+    seed: Option<u64>,
+) -> (Vec<RunSample>, Vec<RunSample>) {
+    samples.sort_by(|a, b| a.image.cmp(&b.image));
+    if let Some(s) = seed {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(s);
+        samples.shuffle(&mut rng);
+    }
+    let val_count =
+        ((val_ratio.clamp(0.0, 1.0) * samples.len() as f32).round() as usize).min(samples.len());
+    let train = samples.split_off(val_count);
+    (train, samples)
+}
+
 pub fn collate<B: Backend>(
     samples: &[RunSample],
     max_boxes: usize,
+) -> anyhow::Result<CollatedBatch<B>> {
+    collate_with(samples, max_boxes, None)
+}
+
+/// `collate` with each sample augmented by `pipeline`'s per-sample knobs (flip, color jitter,
+/// ...) before its features are computed.
+pub fn collate_augmented<B: Backend>(
+    samples: &[RunSample],
+    max_boxes: usize,
+    pipeline: &TransformPipeline,
+    rng: &mut dyn rand::RngCore,
+) -> anyhow::Result<CollatedBatch<B>> {
+    collate_with(samples, max_boxes, Some((pipeline, rng)))
+}
+
+fn collate_with<B: Backend>(
+    samples: &[RunSample],
+    max_boxes: usize,
+    mut augment: Option<(&TransformPipeline, &mut dyn rand::RngCore)>,
 ) -> anyhow::Result<CollatedBatch<B>> {
     if samples.is_empty() {
         anyhow::bail!("cannot collate empty batch");
@@ -76,9 +119,10 @@ pub fn collate<B: Backend>(
 
     // Gather normalized boxes, truncated to max_boxes.
     let mut all_boxes: Vec<Vec<[f32; 4]>> = Vec::with_capacity(batch);
+    let mut all_classes: Vec<Vec<u32>> = Vec::with_capacity(batch);
 
     for (idx, sample) in samples.iter().enumerate() {
-        let img = if idx == 0 {
+        let mut img = if idx == 0 {
             first.clone()
         } else {
             let img = image::open(&sample.image)
@@ -98,21 +142,8 @@ pub fn collate<B: Backend>(
             rgb
         };
 
-        let stats = stats_from_rgb_u8(width, height, img.as_raw())
-            .map_err(|e| anyhow::anyhow!("failed to compute image stats: {e}"))?;
-
-        // Push normalized pixel data in CHW order.
-        for c in 0..3 {
-            for y in 0..height {
-                for x in 0..width {
-                    let p = img.get_pixel(x, y);
-                    let v = p[c] as f32 / 255.0;
-                    image_buf.push(v);
-                }
-            }
-        }
-
         let mut boxes = Vec::new();
+        let mut classes = Vec::new();
         for label in &sample.metadata.labels {
             let bbox = if let Some(norm) = label.bbox_norm {
                 norm
@@ -127,22 +158,44 @@ pub fn collate<B: Backend>(
                 continue;
             };
             boxes.push(bbox);
+            classes.push(label.class_id);
             if boxes.len() >= max_boxes {
                 break;
             }
         }
+        if let Some((pipeline, rng)) = augment.as_mut() {
+            pipeline.augment(&mut img, &mut boxes, &mut **rng);
+        }
+
+        let stats = stats_from_rgb_u8(width, height, img.as_raw())
+            .map_err(|e| anyhow::anyhow!("failed to compute image stats: {e}"))?;
+
+        // Push normalized pixel data in CHW order.
+        for c in 0..3 {
+            for y in 0..height {
+                for x in 0..width {
+                    let p = img.get_pixel(x, y);
+                    let v = p[c] as f32 / 255.0;
+                    image_buf.push(v);
+                }
+            }
+        }
+
         let box_count = boxes.len() as f32;
         features.extend_from_slice(&stats.feature_vector(box_count));
         all_boxes.push(boxes);
+        all_classes.push(classes);
     }
 
     let mut boxes_buf = vec![0.0f32; batch * max_boxes * 4];
     let mut mask_buf = vec![0.0f32; batch * max_boxes];
-    for (b, boxes) in all_boxes.iter().enumerate() {
-        for (i, bbox) in boxes.iter().enumerate() {
+    let mut classes_buf = vec![0.0f32; batch * max_boxes];
+    for (b, (boxes, classes)) in all_boxes.iter().zip(&all_classes).enumerate() {
+        for (i, (bbox, class_id)) in boxes.iter().zip(classes).enumerate() {
             let base = (b * max_boxes + i) * 4;
             boxes_buf[base..base + 4].copy_from_slice(bbox);
             mask_buf[b * max_boxes + i] = 1.0;
+            classes_buf[b * max_boxes + i] = *class_id as f32;
         }
     }
 
@@ -154,6 +207,8 @@ pub fn collate<B: Backend>(
     let boxes =
         Tensor::<B, 3>::from_data(TensorData::new(boxes_buf, [batch, max_boxes, 4]), device);
     let box_mask = Tensor::<B, 2>::from_data(TensorData::new(mask_buf, [batch, max_boxes]), device);
+    let classes =
+        Tensor::<B, 2>::from_data(TensorData::new(classes_buf, [batch, max_boxes]), device);
 
     let features = Tensor::<B, 2>::from_data(TensorData::new(features, [batch, 8]), device);
 
@@ -161,6 +216,7 @@ pub fn collate<B: Backend>(
         images,
         boxes,
         box_mask,
+        classes,
         features,
     })
 }
@@ -230,6 +286,7 @@ pub fn collate_from_burn_batch<B: Backend>(
         images: batch.images,
         boxes: batch.boxes,
         box_mask: batch.box_mask,
+        classes: batch.classes,
         features,
     })
 }
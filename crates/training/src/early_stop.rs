@@ -0,0 +1,247 @@
+//! Early stopping and best/last checkpoint selection for `run_train`.
+//!
+//! `EarlyStopping` watches one validation metric (`--monitor`) on the first validation split.
+//! It rewrites `best.bin` when the metric improves by more than `--early-stop-min-delta` in the
+//! `--mode` direction, stops the run once the metric has not improved for
+//! `--early-stop-patience` epochs, and writes `last.bin` on the run's final epoch. Both files get
+//! the usual metadata sidecar, so either can be loaded like `--checkpoint-out`.
+//! `best_score.json` records which epoch `best.bin` came from, letting a `--resume`d run carry on
+//! with the same best and patience count; a fresh run discards the one an earlier run left.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use burn::module::{AutodiffModule, Module};
+use burn::record::{BinFileRecorder, FullPrecisionSettings};
+use burn::tensor::backend::AutodiffBackend;
+use clap::ValueEnum;
+use models::checkpoint::CheckpointMetadata;
+use serde::{Deserialize, Serialize};
+
+use crate::ema::{select_weights, ModelEma, WeightsKind};
+use crate::metrics::MonitorMetric;
+use crate::trainer::{Control, EpochInfo, TrainCallback};
+
+pub const BEST_FILE: &str = "best.bin";
+pub const LAST_FILE: &str = "last.bin";
+const BEST_SCORE_FILE: &str = "best_score.json";
+
+/// Direction in which the monitored metric improves.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorMode {
+    Min,
+    Max,
+}
+
+impl MonitorMode {
+    /// Natural direction of `metric` (max for mAP, min for loss).
+    pub fn for_metric(metric: MonitorMetric) -> Self {
+        if metric.higher_is_better() {
+            MonitorMode::Max
+        } else {
+            MonitorMode::Min
+        }
+    }
+}
+
+/// Best value of a monitored metric and how many epochs it has gone without improving.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plateau {
+    mode: MonitorMode,
+    min_delta: f32,
+    best: Option<BestScore>,
+    stale_epochs: usize,
+}
+
+/// The epoch `best.bin` was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BestScore {
+    pub epoch: usize,
+    pub monitor: MonitorMetric,
+    pub value: f32,
+}
+
+impl Plateau {
+    pub fn new(mode: MonitorMode, min_delta: f32) -> Self {
+        Self {
+            mode,
+            min_delta: min_delta.max(0.0),
+            best: None,
+            stale_epochs: 0,
+        }
+    }
+
+    /// Continue from a previous best observed before `next_epoch`.
+    pub fn resume(mut self, best: BestScore, next_epoch: usize) -> Self {
+        self.stale_epochs = next_epoch.saturating_sub(best.epoch + 1);
+        self.best = Some(best);
+        self
+    }
+
+    pub fn best(&self) -> Option<BestScore> {
+        self.best
+    }
+
+    /// Epochs since the last improvement.
+    pub fn stale_epochs(&self) -> usize {
+        self.stale_epochs
+    }
+
+    /// Record `value` for `epoch`; returns whether it is a new best.
+    pub fn observe(&mut self, monitor: MonitorMetric, epoch: usize, value: f32) -> bool {
+        let improved = match self.best {
+            None => value.is_finite(),
+            Some(best) => match self.mode {
+                MonitorMode::Min => value < best.value - self.min_delta,
+                MonitorMode::Max => value > best.value + self.min_delta,
+            },
+        };
+        if improved {
+            self.best = Some(BestScore {
+                epoch,
+                monitor,
+                value,
+            });
+            self.stale_epochs = 0;
+        } else {
+            self.stale_epochs += 1;
+        }
+        improved
+    }
+}
+
+/// Training callback that saves `best.bin`/`last.bin` and stops on a plateau.
+pub struct EarlyStopping {
+    dir: PathBuf,
+    metadata: CheckpointMetadata,
+    weights: WeightsKind,
+    monitor: MonitorMetric,
+    plateau: Plateau,
+    /// Stop after this many epochs without improvement (`None` never stops early).
+    patience: Option<usize>,
+}
+
+impl EarlyStopping {
+    pub fn new(
+        dir: PathBuf,
+        metadata: CheckpointMetadata,
+        weights: WeightsKind,
+        monitor: MonitorMetric,
+        plateau: Plateau,
+        patience: Option<usize>,
+    ) -> Self {
+        Self {
+            dir,
+            metadata,
+            weights,
+            monitor,
+            plateau,
+            patience,
+        }
+    }
+
+    /// Pick up the best score written by an earlier run into `dir`, if it tracked the same metric
+    /// before `next_epoch`; any other one is discarded.
+    pub fn resume(&mut self, next_epoch: usize) -> anyhow::Result<()> {
+        match load_best_score(&self.dir)? {
+            Some(best) if best.monitor == self.monitor && best.epoch < next_epoch => {
+                self.plateau = self.plateau.resume(best, next_epoch);
+                Ok(())
+            }
+            Some(_) => self.discard_best_score(),
+            None => Ok(()),
+        }
+    }
+
+    /// Remove a `best_score.json` left in `dir` by an earlier run, so retention and `--resume`
+    /// only ever see this run's best.
+    pub fn discard_best_score(&self) -> anyhow::Result<()> {
+        let path = self.dir.join(BEST_SCORE_FILE);
+        if path.is_file() {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    pub fn best(&self) -> Option<BestScore> {
+        self.plateau.best()
+    }
+
+    pub fn best_path(&self) -> PathBuf {
+        self.dir.join(BEST_FILE)
+    }
+
+    fn save<B, M>(&self, file: &str, model: &M, ema: Option<&ModelEma<B, M>>) -> anyhow::Result<()>
+    where
+        B: AutodiffBackend,
+        M: AutodiffModule<B>,
+    {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(file);
+        let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+        select_weights(model, ema, self.weights)
+            .save_file(&path, &recorder)
+            .map_err(|e| anyhow::anyhow!("failed to save {}: {e}", path.display()))?;
+        self.metadata
+            .save(&path)
+            .map_err(|e| anyhow::anyhow!("failed to write checkpoint metadata: {e}"))
+    }
+}
+
+impl<B, M, O> TrainCallback<B, M, O> for EarlyStopping
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+{
+    fn on_epoch_end(
+        &mut self,
+        info: &EpochInfo,
+        model: &M,
+        _optim: &O,
+        ema: Option<&ModelEma<B, M>>,
+    ) -> anyhow::Result<Control> {
+        let Some(value) = info.val.first().and_then(|val| self.monitor.value(val)) else {
+            if info.is_final {
+                self.save(LAST_FILE, model, ema)?;
+            }
+            return Ok(Control::Continue);
+        };
+        if self.plateau.observe(self.monitor, info.epoch, value) {
+            self.save(BEST_FILE, model, ema)?;
+            if let Some(best) = self.plateau.best() {
+                fs::write(
+                    self.dir.join(BEST_SCORE_FILE),
+                    serde_json::to_vec_pretty(&best)?,
+                )?;
+            }
+        }
+        let control = match self.patience {
+            Some(patience) if self.plateau.stale_epochs() >= patience => {
+                println!(
+                    "early stopping at epoch {}: {} has not improved for {patience} epochs",
+                    info.epoch,
+                    self.monitor.as_str()
+                );
+                Control::Stop
+            }
+            _ => Control::Continue,
+        };
+        if info.is_final || control == Control::Stop {
+            self.save(LAST_FILE, model, ema)?;
+        }
+        Ok(control)
+    }
+}
+
+/// The `best_score.json` in `dir`, if a run has written one.
+pub fn load_best_score(dir: &Path) -> anyhow::Result<Option<BestScore>> {
+    let path = dir.join(BEST_SCORE_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let data = fs::read(&path)?;
+    Ok(Some(serde_json::from_slice(&data).map_err(|e| {
+        anyhow::anyhow!("failed to parse {}: {e}", path.display())
+    })?))
+}
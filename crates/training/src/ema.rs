@@ -0,0 +1,151 @@
+//! Exponential moving average (EMA) of model weights.
+//!
+//! `ModelEma` keeps a shadow copy of every float parameter on the inner (non-autodiff) backend
+//! and blends the live weights into it after each optimizer step:
+//! `shadow = d * shadow + (1 - d) * weights`. The effective decay `d` ramps up from 0 towards
+//! `decay` over roughly `warmup_steps` updates, so the shadow tracks the fast-moving early weights
+//! instead of staying anchored to the random initialization.
+
+use burn::module::{AutodiffModule, Module, ModuleMapper, ModuleVisitor, Param};
+use burn::tensor::backend::{AutodiffBackend, Backend};
+use burn::tensor::{Tensor, TensorPrimitive};
+use clap::ValueEnum;
+
+/// Which weights `run_train` validates and saves.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeightsKind {
+    /// The optimizer's live weights.
+    Raw,
+    /// The EMA shadow weights (falls back to raw when EMA is off).
+    #[default]
+    Ema,
+}
+
+/// EMA decay and warmup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmaConfig {
+    pub decay: f32,
+    /// Time constant (in updates) of the decay ramp; 0 uses `decay` from the first update.
+    pub warmup_steps: u64,
+}
+
+impl Default for EmaConfig {
+    fn default() -> Self {
+        Self {
+            decay: 0.999,
+            warmup_steps: 100,
+        }
+    }
+}
+
+impl EmaConfig {
+    /// Effective decay for the update after `updates` previous ones.
+    pub fn decay_at(&self, updates: u64) -> f32 {
+        if self.warmup_steps == 0 {
+            return self.decay;
+        }
+        let ramp = 1.0 - (-((updates + 1) as f64) / self.warmup_steps as f64).exp();
+        self.decay * ramp as f32
+    }
+}
+
+/// Shadow weights of an autodiff module `M`, kept on the inner backend.
+pub struct ModelEma<B: AutodiffBackend, M: AutodiffModule<B>> {
+    shadow: M::InnerModule,
+    config: EmaConfig,
+    updates: u64,
+}
+
+impl<B: AutodiffBackend, M: AutodiffModule<B>> ModelEma<B, M> {
+    /// Start the shadow from the current weights of `model`.
+    pub fn new(model: &M, config: EmaConfig) -> Self {
+        Self::from_shadow(model.valid(), config, 0)
+    }
+
+    /// Continue from saved shadow weights after `updates` updates (e.g. on resume).
+    pub fn from_shadow(shadow: M::InnerModule, config: EmaConfig, updates: u64) -> Self {
+        Self {
+            shadow,
+            config,
+            updates,
+        }
+    }
+
+    pub fn config(&self) -> EmaConfig {
+        self.config
+    }
+
+    /// Number of updates applied so far.
+    pub fn updates(&self) -> u64 {
+        self.updates
+    }
+
+    pub fn shadow(&self) -> &M::InnerModule {
+        &self.shadow
+    }
+
+    pub fn into_shadow(self) -> M::InnerModule {
+        self.shadow
+    }
+
+    /// Blend the current weights of `model` into the shadow.
+    pub fn update(&mut self, model: &M) {
+        let mut collect = CollectParams::<B::InnerBackend> { params: Vec::new() };
+        model.visit(&mut collect);
+        let decay = self.config.decay_at(self.updates);
+        let mut blend = Blend::<B::InnerBackend> {
+            params: collect.params.into_iter(),
+            decay,
+        };
+        self.shadow = self.shadow.clone().map(&mut blend);
+        self.updates += 1;
+    }
+}
+
+/// The weights picked by `kind` on the inner backend (raw when there is no EMA).
+pub fn select_weights<B, M>(
+    model: &M,
+    ema: Option<&ModelEma<B, M>>,
+    kind: WeightsKind,
+) -> M::InnerModule
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+{
+    match (kind, ema) {
+        (WeightsKind::Ema, Some(ema)) => ema.shadow().clone(),
+        _ => model.valid(),
+    }
+}
+
+/// Float parameter values (detached, inner backend) in module traversal order.
+struct CollectParams<B: Backend> {
+    params: Vec<TensorPrimitive<B>>,
+}
+
+impl<B: AutodiffBackend> ModuleVisitor<B> for CollectParams<B::InnerBackend> {
+    fn visit_float<const D: usize>(&mut self, param: &Param<Tensor<B, D>>) {
+        self.params.push(param.val().inner().into_primitive());
+    }
+}
+
+/// Blends collected parameters into a module with the same structure.
+struct Blend<B: Backend> {
+    params: std::vec::IntoIter<TensorPrimitive<B>>,
+    decay: f32,
+}
+
+impl<B: Backend> ModuleMapper<B> for Blend<B> {
+    fn map_float<const D: usize>(&mut self, param: Param<Tensor<B, D>>) -> Param<Tensor<B, D>> {
+        let Some(current) = self.params.next() else {
+            return param;
+        };
+        let current = Tensor::<B, D>::from_primitive(current);
+        let decay = self.decay;
+        param.map(|shadow| {
+            shadow
+                .mul_scalar(decay)
+                .add(current.mul_scalar(1.0 - decay))
+        })
+    }
+}
@@ -1,8 +1,21 @@
 //! Burn-based training and evaluation for CortenForge detection models.
 //!
 //! This crate provides:
-//! - Dataset loading and collation (`collate`, `collate_from_burn_batch`).
-//! - Training loop utilities (`run_train`, `TrainArgs`).
+//! - Dataset loading and collation (`collate`, `collate_from_burn_batch`), with per-sample
+//!   augmentations from `burn_dataset::TransformPipeline` (`collate_augmented`).
+//! - TOML/JSON experiment configs and the per-run `resolved.toml` snapshot (`config`).
+//! - Training loop utilities (`run_train`, `TrainArgs`) on top of the generic `trainer::Trainer`
+//!   (pluggable batch sources, models, losses, validators, and callbacks).
+//! - GT-to-prediction matchers (`matcher`: greedy IoU or Hungarian).
+//! - Multibox losses (`loss`: L1/GIoU/DIoU/CIoU boxes, BCE/focal objectness).
+//! - Validation metrics and the per-epoch JSONL log (`metrics`).
+//! - Score threshold sweeps, calibration fitting, and reliability diagrams (`calibration`).
+//! - Per-frame prediction records, worst-frame ranking, and overlays for `eval` (`analysis`).
+//! - Exponential moving average of weights (`ema`).
+//! - LR schedules (warmup, cosine/step/one-cycle) and gradient clipping/accumulation (`optim`).
+//! - Live `--status-file` progress reporting (`status`).
+//! - Periodic resumable checkpoints and `--resume` (`resume`).
+//! - Early stopping and `best.bin`/`last.bin` selection (`early_stop`).
 //! - Model checkpoint loading/saving helpers.
 //!
 //! Supports both `LinearClassifier` and `MultiboxModel` from the `models` crate.
@@ -19,11 +32,29 @@
 
 #![recursion_limit = "256"]
 
+pub mod analysis;
+pub mod calibration;
+pub mod config;
 pub mod dataset;
+pub mod early_stop;
+pub mod ema;
+pub mod loss;
+pub mod matcher;
+pub mod metrics;
+pub mod optim;
+pub mod resume;
+pub mod status;
+pub mod trainer;
 pub mod util;
 
-pub use dataset::{collate, collate_from_burn_batch, CollatedBatch, DatasetPathConfig, RunSample};
-pub use models::{LinearClassifier, LinearClassifierConfig, MultiboxModel, MultiboxModelConfig};
+pub use dataset::{
+    collate, collate_augmented, collate_from_burn_batch, split_samples, CollatedBatch,
+    DatasetPathConfig, RunSample,
+};
+pub use models::{
+    ConvBackbone, ConvBackboneConfig, LinearClassifier, LinearClassifierConfig, MultiboxModel,
+    MultiboxModelConfig,
+};
 pub use util::{run_train, TrainArgs};
 /// Backend alias for training/eval (NdArray by default; WGPU if enabled).
 #[cfg(feature = "backend-wgpu")]
@@ -0,0 +1,263 @@
+//! Differentiable multibox losses: IoU-family box regression and objectness.
+//!
+//! Box losses take `[B, P, 4]` predicted/target corners plus the `[B, P, 4]` weights produced by
+//! the matcher (1 on matched slots) and average over matched slots. Objectness losses take
+//! `[B, P]` sigmoid scores and 0/1 targets. Everything stays on Burn tensors so gradients flow.
+//!
+//! Most of the `max_boxes` slots in an image are unmatched, so plain BCE is dominated by easy
+//! negatives and drives every score toward zero; the focal loss down-weights those easy
+//! negatives and is normalized by the number of positives instead of the number of slots.
+//!
+//! Multi-class models add a classification loss over `[B, P, C]` class logits and one-hot
+//! targets, averaged over matched slots only (objectness already covers the background).
+
+use burn::tensor::activation::log_softmax;
+use burn::tensor::backend::Backend;
+use burn::tensor::Tensor;
+use clap::ValueEnum;
+
+const EPS: f32 = 1e-6;
+
+/// Box regression loss applied to matched slots.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoxLossKind {
+    /// Sum of absolute corner errors.
+    #[default]
+    L1,
+    /// 1 - GIoU (penalizes the empty area of the enclosing box).
+    Giou,
+    /// 1 - IoU + normalized center distance.
+    Diou,
+    /// DIoU plus an aspect-ratio consistency term.
+    Ciou,
+}
+
+/// Objectness loss applied to every slot.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ObjLossKind {
+    /// Binary cross-entropy averaged over all slots.
+    #[default]
+    Bce,
+    /// Sigmoid focal loss normalized by the number of positive slots.
+    Focal,
+}
+
+/// Classification loss applied to matched slots of multi-class models.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClassLossKind {
+    /// Softmax cross-entropy.
+    #[default]
+    Softmax,
+    /// Softmax focal loss (cross-entropy scaled by `(1 - p_t)^gamma`, with `--focal-gamma`).
+    Focal,
+}
+
+/// Loss selection and focal-loss hyperparameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LossConfig {
+    pub box_loss: BoxLossKind,
+    pub obj_loss: ObjLossKind,
+    pub class_loss: ClassLossKind,
+    /// Weight of positive slots in the focal loss (negatives get `1 - alpha`).
+    pub focal_alpha: f32,
+    /// Focusing exponent of the focal loss (0 reduces to alpha-weighted BCE).
+    pub focal_gamma: f32,
+}
+
+impl Default for LossConfig {
+    fn default() -> Self {
+        Self {
+            box_loss: BoxLossKind::L1,
+            obj_loss: ObjLossKind::Bce,
+            class_loss: ClassLossKind::Softmax,
+            focal_alpha: 0.25,
+            focal_gamma: 2.0,
+        }
+    }
+}
+
+impl LossConfig {
+    /// Box loss averaged over matched slots (zero when nothing is matched).
+    pub fn box_loss<B: Backend>(
+        &self,
+        pred: Tensor<B, 3>,
+        target: Tensor<B, 3>,
+        weights: Tensor<B, 3>,
+    ) -> Tensor<B, 1> {
+        box_loss(self.box_loss, pred, target, weights)
+    }
+
+    /// Objectness loss over all slots.
+    pub fn objectness_loss<B: Backend>(
+        &self,
+        scores: Tensor<B, 2>,
+        targets: Tensor<B, 2>,
+    ) -> Tensor<B, 1> {
+        match self.obj_loss {
+            ObjLossKind::Bce => bce_objectness(scores, targets),
+            ObjLossKind::Focal => {
+                focal_objectness(scores, targets, self.focal_alpha, self.focal_gamma)
+            }
+        }
+    }
+
+    /// Classification loss over matched slots (`targets` is one-hot, all zero when unmatched).
+    pub fn classification_loss<B: Backend>(
+        &self,
+        logits: Tensor<B, 3>,
+        targets: Tensor<B, 3>,
+    ) -> Tensor<B, 1> {
+        let gamma = match self.class_loss {
+            ClassLossKind::Softmax => 0.0,
+            ClassLossKind::Focal => self.focal_gamma,
+        };
+        softmax_focal_classification(logits, targets, gamma)
+    }
+}
+
+/// Per-slot box loss `[B, P]` for the selected kind (not masked).
+pub fn box_loss_per_slot<B: Backend>(
+    kind: BoxLossKind,
+    pred: Tensor<B, 3>,
+    target: Tensor<B, 3>,
+) -> Tensor<B, 2> {
+    let [batch, slots, _] = pred.dims();
+    if kind == BoxLossKind::L1 {
+        return (pred - target).abs().sum_dim(2).reshape([batch, slots]);
+    }
+
+    let (px0, py0, px1, py1) = corners(pred);
+    let (tx0, ty0, tx1, ty1) = corners(target);
+    let pw = (px1.clone() - px0.clone()).clamp_min(0.0);
+    let ph = (py1.clone() - py0.clone()).clamp_min(0.0);
+    let tw = (tx1.clone() - tx0.clone()).clamp_min(0.0);
+    let th = (ty1.clone() - ty0.clone()).clamp_min(0.0);
+
+    let inter_w =
+        (px1.clone().min_pair(tx1.clone()) - px0.clone().max_pair(tx0.clone())).clamp_min(0.0);
+    let inter_h =
+        (py1.clone().min_pair(ty1.clone()) - py0.clone().max_pair(ty0.clone())).clamp_min(0.0);
+    let inter = inter_w * inter_h;
+    let union = pw.clone() * ph.clone() + tw.clone() * th.clone() - inter.clone() + EPS;
+    let iou = inter / union.clone();
+
+    // Smallest enclosing box.
+    let hull_w = px1.clone().max_pair(tx1.clone()) - px0.clone().min_pair(tx0.clone());
+    let hull_h = py1.clone().max_pair(ty1.clone()) - py0.clone().min_pair(ty0.clone());
+
+    let loss = match kind {
+        BoxLossKind::L1 => unreachable!("handled above"),
+        BoxLossKind::Giou => {
+            let hull_area = hull_w * hull_h + EPS;
+            let giou = iou - (hull_area.clone() - union) / hull_area;
+            giou.neg().add_scalar(1.0)
+        }
+        BoxLossKind::Diou | BoxLossKind::Ciou => {
+            let diag_sq = hull_w.powf_scalar(2.0) + hull_h.powf_scalar(2.0) + EPS;
+            let dcx = (px0 + px1 - tx0 - tx1) * 0.5;
+            let dcy = (py0 + py1 - ty0 - ty1) * 0.5;
+            let center_sq = dcx.powf_scalar(2.0) + dcy.powf_scalar(2.0);
+            let diou_loss = iou.clone().neg().add_scalar(1.0) + center_sq / diag_sq;
+            if kind == BoxLossKind::Diou {
+                diou_loss
+            } else {
+                let angle_diff = atan_nonneg(tw / (th + EPS)) - atan_nonneg(pw / (ph + EPS));
+                let v = angle_diff.powf_scalar(2.0) * (4.0 / std::f32::consts::PI.powi(2));
+                // Trade-off weight is treated as a constant, as in the CIoU paper.
+                let alpha = (v.clone() / (iou.neg().add_scalar(1.0) + v.clone() + EPS)).detach();
+                diou_loss + alpha * v
+            }
+        }
+    };
+    loss.reshape([batch, slots])
+}
+
+/// Box loss averaged over matched slots; `weights` is the matcher's `[B, P, 4]` slot mask.
+pub fn box_loss<B: Backend>(
+    kind: BoxLossKind,
+    pred: Tensor<B, 3>,
+    target: Tensor<B, 3>,
+    weights: Tensor<B, 3>,
+) -> Tensor<B, 1> {
+    let [batch, slots, _] = weights.dims();
+    let slot_mask = weights.sum_dim(2).reshape([batch, slots]).div_scalar(4.0);
+    let matched = slot_mask.clone().sum().clamp_min(1.0);
+    (box_loss_per_slot(kind, pred, target) * slot_mask)
+        .sum()
+        .div(matched)
+}
+
+/// Binary cross-entropy averaged over all slots.
+pub fn bce_objectness<B: Backend>(scores: Tensor<B, 2>, targets: Tensor<B, 2>) -> Tensor<B, 1> {
+    let [batch, slots] = scores.dims();
+    let p = scores.clamp(EPS, 1.0 - EPS);
+    let neg_targets = targets.clone().neg().add_scalar(1.0);
+    let log_likelihood = targets * p.clone().log() + neg_targets * p.neg().add_scalar(1.0).log();
+    log_likelihood
+        .sum()
+        .neg()
+        .div_scalar((batch * slots).max(1) as f32)
+}
+
+/// Sigmoid focal loss `-alpha_t (1 - p_t)^gamma log(p_t)`, normalized by the positive count.
+pub fn focal_objectness<B: Backend>(
+    scores: Tensor<B, 2>,
+    targets: Tensor<B, 2>,
+    alpha: f32,
+    gamma: f32,
+) -> Tensor<B, 1> {
+    let p = scores.clamp(EPS, 1.0 - EPS);
+    let neg_targets = targets.clone().neg().add_scalar(1.0);
+    let p_t = p.clone() * targets.clone() + p.neg().add_scalar(1.0) * neg_targets.clone();
+    let alpha_t = targets.clone() * alpha + neg_targets * (1.0 - alpha);
+    let modulator = p_t.clone().neg().add_scalar(1.0).powf_scalar(gamma);
+    let positives = targets.sum().clamp_min(1.0);
+    (alpha_t * modulator * p_t.log()).sum().neg().div(positives)
+}
+
+/// Softmax focal loss `-(1 - p_t)^gamma log(p_t)` averaged over matched slots (rows of `targets`
+/// that are one-hot); `gamma = 0` is plain cross-entropy.
+pub fn softmax_focal_classification<B: Backend>(
+    logits: Tensor<B, 3>,
+    targets: Tensor<B, 3>,
+    gamma: f32,
+) -> Tensor<B, 1> {
+    let [batch, slots, _] = logits.dims();
+    let log_p_t = (log_softmax(logits, 2) * targets.clone())
+        .sum_dim(2)
+        .reshape([batch, slots]);
+    let matched = targets.sum_dim(2).reshape([batch, slots]);
+    let per_slot = if gamma == 0.0 {
+        log_p_t.neg()
+    } else {
+        let modulator = log_p_t
+            .clone()
+            .exp()
+            .neg()
+            .add_scalar(1.0)
+            .powf_scalar(gamma);
+        (modulator * log_p_t).neg()
+    };
+    let count = matched.clone().sum().clamp_min(1.0);
+    (per_slot * matched).sum().div(count)
+}
+
+fn corners<B: Backend>(
+    boxes: Tensor<B, 3>,
+) -> (Tensor<B, 3>, Tensor<B, 3>, Tensor<B, 3>, Tensor<B, 3>) {
+    let [batch, slots, _] = boxes.dims();
+    let col = |i: usize| boxes.clone().slice([0..batch, 0..slots, i..i + 1]);
+    (col(0), col(1), col(2), col(3))
+}
+
+/// `atan(x)` for `x >= 0`, built from differentiable primitives (Burn has no `atan`).
+///
+/// Uses `atan(x) = pi/4 + atan((x - 1) / (x + 1))` and a polynomial fit on `[-1, 1]`
+/// (max error about 1.5e-3 rad).
+fn atan_nonneg<B: Backend>(x: Tensor<B, 3>) -> Tensor<B, 3> {
+    let z = (x.clone().sub_scalar(1.0)) / (x.add_scalar(1.0));
+    let abs_z = z.clone().abs();
+    let fit = z.clone() * std::f32::consts::FRAC_PI_4
+        - z * (abs_z.clone().sub_scalar(1.0)) * (abs_z * 0.0663).add_scalar(0.2447);
+    fit.add_scalar(std::f32::consts::FRAC_PI_4)
+}
@@ -0,0 +1,383 @@
+//! Prediction-to-ground-truth matching for multibox training.
+//!
+//! `build_greedy_targets` (in `util`) picks the best-IoU slot for each GT independently, so two
+//! GTs can land on the same slot. `build_hungarian_targets` solves the bipartite assignment
+//! optimally instead: every GT gets a distinct slot (while slots last), minimizing a DETR-style
+//! cost of weighted L1 distance, negative GIoU, and negative predicted objectness.
+//!
+//! Both are built on a `SlotAssignment` (which GT each slot got), from which the box/objectness
+//! targets and, for multi-class models, the one-hot class targets are derived.
+
+use burn::tensor::backend::Backend;
+use burn::tensor::{Tensor, TensorData};
+use clap::ValueEnum;
+use vision_core::boxes::iou_xyxy;
+
+/// Strategy used to assign GT boxes to prediction slots.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatcherKind {
+    /// Best-IoU slot per GT; cheap, but GTs may share a slot.
+    #[default]
+    Greedy,
+    /// Optimal one-to-one assignment over the combined matching cost.
+    Hungarian,
+}
+
+/// Weights of the Hungarian matching cost terms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchCost {
+    /// Weight of the L1 distance between box corners.
+    pub l1: f32,
+    /// Weight of the negative GIoU.
+    pub giou: f32,
+    /// Weight of the negative predicted objectness.
+    pub obj: f32,
+}
+
+impl Default for MatchCost {
+    fn default() -> Self {
+        Self {
+            l1: 5.0,
+            giou: 2.0,
+            obj: 1.0,
+        }
+    }
+}
+
+/// Generalized IoU of two `[x0, y0, x1, y1]` boxes, in `[-1, 1]`.
+pub fn giou_xyxy(a: [f32; 4], b: [f32; 4]) -> f32 {
+    let ax0 = a[0].min(a[2]);
+    let ay0 = a[1].min(a[3]);
+    let ax1 = a[0].max(a[2]);
+    let ay1 = a[1].max(a[3]);
+    let bx0 = b[0].min(b[2]);
+    let by0 = b[1].min(b[3]);
+    let bx1 = b[0].max(b[2]);
+    let by1 = b[1].max(b[3]);
+
+    let inter_w = (ax1.min(bx1) - ax0.max(bx0)).max(0.0);
+    let inter_h = (ay1.min(by1) - ay0.max(by0)).max(0.0);
+    let inter_area = inter_w * inter_h;
+    let area_a = (ax1 - ax0) * (ay1 - ay0);
+    let area_b = (bx1 - bx0) * (by1 - by0);
+    let union = area_a + area_b - inter_area;
+    let hull = (ax1.max(bx1) - ax0.min(bx0)) * (ay1.max(by1) - ay0.min(by0));
+
+    let iou = if union > 0.0 { inter_area / union } else { 0.0 };
+    if hull > 0.0 {
+        iou - (hull - union) / hull
+    } else {
+        iou
+    }
+}
+
+/// Minimum-cost assignment for a row-major `rows x cols` cost matrix.
+///
+/// Returns, for each row, the column it is assigned to. Every column is used at most once; when
+/// there are more rows than columns the surplus rows are left unassigned (`None`).
+/// Non-finite costs (e.g. from a diverged prediction) are treated as worse than any finite one
+/// (`-inf` as better), so the solver always terminates.
+pub fn hungarian(cost: &[f32], rows: usize, cols: usize) -> Vec<Option<usize>> {
+    assert_eq!(cost.len(), rows * cols, "cost matrix must be rows x cols");
+    if rows == 0 || cols == 0 {
+        return vec![None; rows];
+    }
+    if rows > cols {
+        // Solve the transposed problem (each column picks a row) and invert it.
+        let transposed: Vec<f32> = (0..cols)
+            .flat_map(|c| (0..rows).map(move |r| cost[r * cols + c]))
+            .collect();
+        let mut out = vec![None; rows];
+        for (c, r) in hungarian(&transposed, cols, rows).into_iter().enumerate() {
+            if let Some(r) = r {
+                out[r] = Some(c);
+            }
+        }
+        return out;
+    }
+
+    // Large enough that no assignment of finite costs can add up to it.
+    let max_finite = cost
+        .iter()
+        .filter(|c| c.is_finite())
+        .fold(0.0f64, |m, &c| m.max(f64::from(c).abs()));
+    let sentinel = (max_finite + 1.0) * (rows as f64 + 1.0);
+
+    // Shortest augmenting path with potentials (rows <= cols); 1-based with a virtual column 0.
+    let cost_at = |r: usize, c: usize| {
+        let c = f64::from(cost[(r - 1) * cols + (c - 1)]);
+        if c.is_nan() || c == f64::INFINITY {
+            sentinel
+        } else if c == f64::NEG_INFINITY {
+            -sentinel
+        } else {
+            c
+        }
+    };
+    let mut u = vec![0.0f64; rows + 1];
+    let mut v = vec![0.0f64; cols + 1];
+    let mut col_owner = vec![0usize; cols + 1];
+    let mut way = vec![0usize; cols + 1];
+    for r in 1..=rows {
+        col_owner[0] = r;
+        let mut c0 = 0usize;
+        let mut min_v = vec![f64::INFINITY; cols + 1];
+        let mut used = vec![false; cols + 1];
+        loop {
+            used[c0] = true;
+            let r0 = col_owner[c0];
+            let mut delta = f64::INFINITY;
+            let mut c1 = 0usize;
+            for c in 1..=cols {
+                if used[c] {
+                    continue;
+                }
+                let reduced = cost_at(r0, c) - u[r0] - v[c];
+                if reduced < min_v[c] {
+                    min_v[c] = reduced;
+                    way[c] = c0;
+                }
+                if min_v[c] < delta {
+                    delta = min_v[c];
+                    c1 = c;
+                }
+            }
+            for c in 0..=cols {
+                if used[c] {
+                    u[col_owner[c]] += delta;
+                    v[c] -= delta;
+                } else {
+                    min_v[c] -= delta;
+                }
+            }
+            c0 = c1;
+            if col_owner[c0] == 0 {
+                break;
+            }
+        }
+        loop {
+            let c1 = way[c0];
+            col_owner[c0] = col_owner[c1];
+            c0 = c1;
+            if c0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut out = vec![None; rows];
+    for c in 1..=cols {
+        if col_owner[c] != 0 {
+            out[col_owner[c] - 1] = Some(c - 1);
+        }
+    }
+    out
+}
+
+/// GT assigned to each prediction slot, row-major over `[B, P]`: the GT's index along the padded
+/// `max_gt` axis, or `None` when the slot is unmatched.
+pub type SlotAssignment = Vec<Option<usize>>;
+
+/// Greedy best-IoU assignment: each GT takes its best slot independently, so a later GT can
+/// take over a slot an earlier one chose.
+pub fn assign_greedy<B: Backend>(
+    pred_boxes: Tensor<B, 3>,
+    gt_boxes: Tensor<B, 3>,
+    gt_mask: Tensor<B, 2>,
+) -> SlotAssignment {
+    let [batch, max_pred, _] = pred_boxes.dims();
+    let max_gt = gt_boxes.dims()[1];
+
+    let pred_boxes_vec = pred_boxes.into_data().to_vec::<f32>().unwrap_or_default();
+    let gt_boxes_vec = gt_boxes.into_data().to_vec::<f32>().unwrap_or_default();
+    let gt_mask_vec = gt_mask.into_data().to_vec::<f32>().unwrap_or_default();
+
+    let mut assignment = vec![None; batch * max_pred];
+    let corners = |v: &[f32], i: usize| [v[i * 4], v[i * 4 + 1], v[i * 4 + 2], v[i * 4 + 3]];
+    for b in 0..batch {
+        for g in 0..max_gt {
+            if gt_mask_vec.get(b * max_gt + g).copied().unwrap_or(0.0) < 0.5 {
+                continue;
+            }
+            let gb = corners(&gt_boxes_vec, b * max_gt + g);
+            let mut best_iou = -1.0f32;
+            let mut best_p = 0usize;
+            for p in 0..max_pred {
+                let iou = iou_xyxy(corners(&pred_boxes_vec, b * max_pred + p), gb);
+                if iou > best_iou {
+                    best_iou = iou;
+                    best_p = p;
+                }
+            }
+            assignment[b * max_pred + best_p] = Some(g);
+        }
+    }
+    assignment
+}
+
+/// Optimal one-to-one assignment minimizing `MatchCost`; GTs beyond the slot count stay
+/// unmatched.
+pub fn assign_hungarian<B: Backend>(
+    pred_boxes: Tensor<B, 3>,
+    pred_scores: Tensor<B, 2>,
+    gt_boxes: Tensor<B, 3>,
+    gt_mask: Tensor<B, 2>,
+    cost: &MatchCost,
+) -> SlotAssignment {
+    let [batch, max_pred, _] = pred_boxes.dims();
+    let max_gt = gt_boxes.dims()[1];
+
+    let pred_boxes_vec = pred_boxes.into_data().to_vec::<f32>().unwrap_or_default();
+    let pred_scores_vec = pred_scores.into_data().to_vec::<f32>().unwrap_or_default();
+    let gt_boxes_vec = gt_boxes.into_data().to_vec::<f32>().unwrap_or_default();
+    let gt_mask_vec = gt_mask.into_data().to_vec::<f32>().unwrap_or_default();
+
+    let mut assignment = vec![None; batch * max_pred];
+    let corners = |v: &[f32], i: usize| [v[i * 4], v[i * 4 + 1], v[i * 4 + 2], v[i * 4 + 3]];
+    for b in 0..batch {
+        let gts: Vec<usize> = (0..max_gt)
+            .filter(|g| gt_mask_vec.get(b * max_gt + g).copied().unwrap_or(0.0) >= 0.5)
+            .collect();
+        if gts.is_empty() {
+            continue;
+        }
+
+        let mut costs = Vec::with_capacity(gts.len() * max_pred);
+        for &g in &gts {
+            let gb = corners(&gt_boxes_vec, b * max_gt + g);
+            for p in 0..max_pred {
+                let pb = corners(&pred_boxes_vec, b * max_pred + p);
+                let l1: f32 = pb.iter().zip(&gb).map(|(x, y)| (x - y).abs()).sum();
+                let score = pred_scores_vec
+                    .get(b * max_pred + p)
+                    .copied()
+                    .unwrap_or(0.0);
+                costs.push(cost.l1 * l1 - cost.giou * giou_xyxy(pb, gb) - cost.obj * score);
+            }
+        }
+
+        for (row, slot) in hungarian(&costs, gts.len(), max_pred)
+            .into_iter()
+            .enumerate()
+        {
+            let Some(p) = slot else { continue };
+            assignment[b * max_pred + p] = Some(gts[row]);
+        }
+    }
+    assignment
+}
+
+/// Assign GTs to prediction slots with the selected matcher.
+pub fn assign_slots<B: Backend>(
+    matcher: MatcherKind,
+    cost: &MatchCost,
+    pred_boxes: Tensor<B, 3>,
+    pred_scores: Tensor<B, 2>,
+    gt_boxes: Tensor<B, 3>,
+    gt_mask: Tensor<B, 2>,
+) -> SlotAssignment {
+    match matcher {
+        MatcherKind::Greedy => assign_greedy(pred_boxes, gt_boxes, gt_mask),
+        MatcherKind::Hungarian => {
+            assign_hungarian(pred_boxes, pred_scores, gt_boxes, gt_mask, cost)
+        }
+    }
+}
+
+/// `(obj_targets, box_targets, box_weights)` for an assignment over `max_pred` slots: `[B, P]`
+/// objectness targets, `[B, P, 4]` box targets, and `[B, P, 4]` weights that are 1 on matched
+/// slots, all on `gt_boxes`' device.
+pub fn targets_from_assignment<B: Backend>(
+    assignment: &[Option<usize>],
+    max_pred: usize,
+    gt_boxes: Tensor<B, 3>,
+) -> (Tensor<B, 2>, Tensor<B, 3>, Tensor<B, 3>) {
+    let [batch, max_gt, _] = gt_boxes.dims();
+    let device = &gt_boxes.device();
+    let gt_boxes_vec = gt_boxes.into_data().to_vec::<f32>().unwrap_or_default();
+
+    let mut obj_targets = vec![0.0f32; batch * max_pred];
+    let mut box_targets = vec![0.0f32; batch * max_pred * 4];
+    let mut box_weights = vec![0.0f32; batch * max_pred * 4];
+    for (idx, g) in assignment.iter().enumerate() {
+        let Some(g) = g else { continue };
+        let gt = (idx / max_pred) * max_gt + g;
+        obj_targets[idx] = 1.0;
+        box_targets[idx * 4..idx * 4 + 4].copy_from_slice(&gt_boxes_vec[gt * 4..gt * 4 + 4]);
+        box_weights[idx * 4..idx * 4 + 4].copy_from_slice(&[1.0; 4]);
+    }
+
+    let obj_targets =
+        Tensor::<B, 2>::from_data(TensorData::new(obj_targets, [batch, max_pred]), device);
+    let box_targets =
+        Tensor::<B, 3>::from_data(TensorData::new(box_targets, [batch, max_pred, 4]), device);
+    let box_weights =
+        Tensor::<B, 3>::from_data(TensorData::new(box_weights, [batch, max_pred, 4]), device);
+
+    (obj_targets, box_targets, box_weights)
+}
+
+/// One-hot `[B, P, num_classes]` class targets of the matched slots (all zero when unmatched), on
+/// `gt_classes`' device.
+///
+/// `gt_classes` holds the `[B, max_gt]` float class ids from `CollatedBatch::classes`; ids at or
+/// above `num_classes` are clamped to the last class.
+pub fn class_targets<B: Backend>(
+    assignment: &[Option<usize>],
+    max_pred: usize,
+    gt_classes: Tensor<B, 2>,
+    num_classes: usize,
+) -> Tensor<B, 3> {
+    let [batch, max_gt] = gt_classes.dims();
+    let num_classes = num_classes.max(1);
+    let device = gt_classes.device();
+    let gt_classes_vec = gt_classes.into_data().to_vec::<f32>().unwrap_or_default();
+
+    let mut targets = vec![0.0f32; batch * max_pred * num_classes];
+    for (idx, g) in assignment.iter().enumerate() {
+        let Some(g) = g else { continue };
+        let class_id = gt_classes_vec[(idx / max_pred) * max_gt + g].max(0.0) as usize;
+        targets[idx * num_classes + class_id.min(num_classes - 1)] = 1.0;
+    }
+    Tensor::<B, 3>::from_data(
+        TensorData::new(targets, [batch, max_pred, num_classes]),
+        &device,
+    )
+}
+
+/// Build `(obj_targets, box_targets, box_weights)` with an optimal one-to-one GT/slot assignment.
+///
+/// Same shapes and semantics as `build_greedy_targets`: `[B, P]` objectness targets, `[B, P, 4]`
+/// box targets, and `[B, P, 4]` weights that are 1 on matched slots.
+pub fn build_hungarian_targets<B: Backend>(
+    pred_boxes: Tensor<B, 3>,
+    pred_scores: Tensor<B, 2>,
+    gt_boxes: Tensor<B, 3>,
+    gt_mask: Tensor<B, 2>,
+    cost: &MatchCost,
+) -> (Tensor<B, 2>, Tensor<B, 3>, Tensor<B, 3>) {
+    let max_pred = pred_boxes.dims()[1];
+    let assignment = assign_hungarian(pred_boxes, pred_scores, gt_boxes.clone(), gt_mask, cost);
+    targets_from_assignment(&assignment, max_pred, gt_boxes)
+}
+
+/// Build training targets with the selected matcher.
+pub fn build_targets<B: Backend>(
+    matcher: MatcherKind,
+    cost: &MatchCost,
+    pred_boxes: Tensor<B, 3>,
+    pred_scores: Tensor<B, 2>,
+    gt_boxes: Tensor<B, 3>,
+    gt_mask: Tensor<B, 2>,
+) -> (Tensor<B, 2>, Tensor<B, 3>, Tensor<B, 3>) {
+    let max_pred = pred_boxes.dims()[1];
+    let assignment = assign_slots(
+        matcher,
+        cost,
+        pred_boxes,
+        pred_scores,
+        gt_boxes.clone(),
+        gt_mask,
+    );
+    targets_from_assignment(&assignment, max_pred, gt_boxes)
+}
@@ -0,0 +1,629 @@
+//! Detection metrics and the per-epoch metrics log.
+//!
+//! `DetectionAccumulator` collects per-frame predictions against ground truth and reports
+//! precision/recall at a score threshold plus average precision at an IoU threshold.
+//! `CocoEvaluator` computes the COCO summary used by `eval`: AP at IoU 0.5, 0.75 and averaged
+//! over 0.50:0.05:0.95, AP by object size, AR@1/10/100, and score-sorted PR curves. Epoch
+//! summaries are appended as JSON lines (`{"epoch", "train_loss", "val_metrics": [...]}`, plus
+//! `domain_gap` when a synthetic and a real split are compared) to the file the tools TUI tails
+//! (`logs/metrics.jsonl` by default).
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use burn::module::AutodiffModule;
+use burn::tensor::backend::AutodiffBackend;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::ema::ModelEma;
+use crate::trainer::{Control, EpochInfo, TrainCallback};
+
+/// Box IoU and class-agnostic NMS, shared with inference.
+pub use vision_core::boxes::{iou_xyxy, nms};
+
+/// Accumulates TP/FP decisions across frames for one IoU threshold.
+#[derive(Debug, Clone)]
+pub struct DetectionAccumulator {
+    iou_threshold: f32,
+    score_threshold: f32,
+    /// `(score, is_true_positive)` for every prediction, used for AP.
+    ranked: Vec<(f32, bool)>,
+    num_gt: usize,
+    frames: usize,
+}
+
+impl DetectionAccumulator {
+    /// `iou_threshold` decides TP matches; `score_threshold` is the operating point for
+    /// precision/recall (AP uses every prediction regardless).
+    pub fn new(iou_threshold: f32, score_threshold: f32) -> Self {
+        Self {
+            iou_threshold,
+            score_threshold,
+            ranked: Vec::new(),
+            num_gt: 0,
+            frames: 0,
+        }
+    }
+
+    /// Add one frame: predictions (any order) and its ground-truth boxes.
+    ///
+    /// Predictions are matched in descending score order; each GT can be claimed once, so
+    /// duplicates count as false positives.
+    pub fn add_frame(&mut self, boxes: &[[f32; 4]], scores: &[f32], gts: &[[f32; 4]]) {
+        self.frames += 1;
+        self.num_gt += gts.len();
+        let mut order: Vec<usize> = (0..boxes.len().min(scores.len())).collect();
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        let mut claimed = vec![false; gts.len()];
+        for p in order {
+            let best = gts
+                .iter()
+                .enumerate()
+                .filter(|(g, _)| !claimed[*g])
+                .map(|(g, gt)| (g, iou_xyxy(boxes[p], *gt)))
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            let tp = match best {
+                Some((g, iou)) if iou >= self.iou_threshold => {
+                    claimed[g] = true;
+                    true
+                }
+                _ => false,
+            };
+            self.ranked.push((scores[p], tp));
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// `(score, is_true_positive)` for every prediction seen, in insertion order.
+    pub fn ranked(&self) -> &[(f32, bool)] {
+        &self.ranked
+    }
+
+    /// Total ground-truth boxes seen.
+    pub fn num_gt(&self) -> usize {
+        self.num_gt
+    }
+
+    /// `(tp, fp, fn)` counting predictions at or above the score threshold.
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let (mut tp, mut fp) = (0, 0);
+        for &(score, is_tp) in &self.ranked {
+            if score >= self.score_threshold {
+                if is_tp {
+                    tp += 1;
+                } else {
+                    fp += 1;
+                }
+            }
+        }
+        (tp, fp, self.num_gt.saturating_sub(tp))
+    }
+
+    pub fn precision(&self) -> f32 {
+        let (tp, fp, _) = self.counts();
+        if tp + fp == 0 {
+            0.0
+        } else {
+            tp as f32 / (tp + fp) as f32
+        }
+    }
+
+    pub fn recall(&self) -> f32 {
+        let (tp, _, _) = self.counts();
+        if self.num_gt == 0 {
+            0.0
+        } else {
+            tp as f32 / self.num_gt as f32
+        }
+    }
+
+    /// All-point interpolated average precision over every prediction.
+    pub fn average_precision(&self) -> f32 {
+        if self.num_gt == 0 {
+            return 0.0;
+        }
+        let mut ranked = self.ranked.clone();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let mut tp = 0usize;
+        let mut points = Vec::with_capacity(ranked.len());
+        for (i, &(_, is_tp)) in ranked.iter().enumerate() {
+            if is_tp {
+                tp += 1;
+            }
+            points.push((tp as f32 / self.num_gt as f32, tp as f32 / (i + 1) as f32));
+        }
+        // Make precision monotonically non-increasing in recall, then integrate.
+        for i in (0..points.len().saturating_sub(1)).rev() {
+            points[i].1 = points[i].1.max(points[i + 1].1);
+        }
+        let mut ap = 0.0;
+        let mut prev_recall = 0.0;
+        for (recall, precision) in points {
+            ap += (recall - prev_recall) * precision;
+            prev_recall = recall;
+        }
+        ap
+    }
+}
+
+/// COCO IoU thresholds 0.50:0.05:0.95.
+pub const COCO_IOU_THRESHOLDS: [f32; 10] = [0.5, 0.55, 0.6, 0.65, 0.7, 0.75, 0.8, 0.85, 0.9, 0.95];
+
+/// Per-frame detection limits for average recall (AR@1, AR@10, AR@100).
+pub const COCO_MAX_DETS: [usize; 3] = [1, 10, 100];
+
+/// Recall points used to interpolate the precision envelope (0.00, 0.01, ..., 1.00).
+const COCO_RECALL_POINTS: usize = 101;
+
+/// COCO object size buckets by box area in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AreaRange {
+    All,
+    /// Area below 32².
+    Small,
+    /// Area between 32² and 96².
+    Medium,
+    /// Area above 96².
+    Large,
+}
+
+impl AreaRange {
+    pub fn contains(self, area: f32) -> bool {
+        let (lo, hi) = match self {
+            AreaRange::All => (0.0, f32::INFINITY),
+            AreaRange::Small => (0.0, 32.0 * 32.0),
+            AreaRange::Medium => (32.0 * 32.0, 96.0 * 96.0),
+            AreaRange::Large => (96.0 * 96.0, f32::INFINITY),
+        };
+        (lo..=hi).contains(&area)
+    }
+}
+
+/// One point of a score-sorted precision/recall curve.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PrPoint {
+    /// Score of the prediction that adds this point; everything at or above it is kept.
+    pub score: f32,
+    pub precision: f32,
+    pub recall: f32,
+}
+
+/// Precision/recall curve at one IoU threshold, in descending score order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrCurve {
+    pub iou_threshold: f32,
+    pub points: Vec<PrPoint>,
+}
+
+/// COCO-style summary of a detection run.
+///
+/// AP values use 101-point interpolation and are `None` when no ground truth falls in the
+/// corresponding size bucket (COCO reports -1 there).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CocoMetrics {
+    pub frames: usize,
+    pub num_gt: usize,
+    /// AP averaged over IoU 0.50:0.05:0.95.
+    pub map: Option<f32>,
+    pub map50: Option<f32>,
+    pub map75: Option<f32>,
+    pub map_small: Option<f32>,
+    pub map_medium: Option<f32>,
+    pub map_large: Option<f32>,
+    /// Average recall over IoU 0.50:0.05:0.95 with at most 1/10/100 detections per frame.
+    pub ar1: Option<f32>,
+    pub ar10: Option<f32>,
+    pub ar100: Option<f32>,
+    /// PR curves at IoU 0.5 and 0.75.
+    pub pr_curves: Vec<PrCurve>,
+}
+
+/// Predictions and ground truth of one frame, boxes normalized to [0, 1].
+#[derive(Debug, Clone)]
+struct CocoFrame {
+    boxes: Vec<[f32; 4]>,
+    scores: Vec<f32>,
+    gts: Vec<[f32; 4]>,
+    /// `(width, height)` in pixels, used to bucket boxes by area.
+    image_size: (u32, u32),
+}
+
+impl CocoFrame {
+    fn area(&self, b: [f32; 4]) -> f32 {
+        let (w, h) = self.image_size;
+        (b[2] - b[0]).max(0.0) * w as f32 * (b[3] - b[1]).max(0.0) * h as f32
+    }
+}
+
+/// Matched predictions for one (IoU threshold, size bucket, detection limit) setting.
+struct Matches {
+    /// `(score, is_true_positive)` for every non-ignored prediction, in descending score order.
+    ranked: Vec<(f32, bool)>,
+    /// Ground-truth boxes inside the size bucket.
+    num_gt: usize,
+}
+
+impl Matches {
+    /// Precision/recall after each prediction.
+    fn curve(&self, iou_threshold: f32) -> PrCurve {
+        let mut tp = 0usize;
+        let points = self
+            .ranked
+            .iter()
+            .enumerate()
+            .map(|(i, &(score, is_tp))| {
+                tp += usize::from(is_tp);
+                PrPoint {
+                    score,
+                    precision: tp as f32 / (i + 1) as f32,
+                    recall: tp as f32 / self.num_gt.max(1) as f32,
+                }
+            })
+            .collect();
+        PrCurve {
+            iou_threshold,
+            points,
+        }
+    }
+
+    /// 101-point interpolated AP.
+    fn average_precision(&self) -> Option<f32> {
+        if self.num_gt == 0 {
+            return None;
+        }
+        let mut points = self.curve(0.0).points;
+        for i in (0..points.len().saturating_sub(1)).rev() {
+            points[i].precision = points[i].precision.max(points[i + 1].precision);
+        }
+        let mut sum = 0.0;
+        let mut idx = 0;
+        for r in 0..COCO_RECALL_POINTS {
+            let recall = r as f32 / (COCO_RECALL_POINTS - 1) as f32;
+            while idx < points.len() && points[idx].recall < recall - 1e-6 {
+                idx += 1;
+            }
+            match points.get(idx) {
+                Some(point) => sum += point.precision,
+                None => break,
+            }
+        }
+        Some(sum / COCO_RECALL_POINTS as f32)
+    }
+
+    fn recall(&self) -> Option<f32> {
+        if self.num_gt == 0 {
+            return None;
+        }
+        let tp = self.ranked.iter().filter(|(_, is_tp)| *is_tp).count();
+        Some(tp as f32 / self.num_gt as f32)
+    }
+}
+
+/// Collects frames and computes COCO-style AP/AR across IoU thresholds and box sizes.
+///
+/// Matching follows the COCO protocol: per frame, predictions are taken in descending score
+/// order and each claims the best-overlapping unclaimed ground truth. Ground truth outside the
+/// size bucket being scored is ignored, as are predictions matched to it and unmatched
+/// predictions outside the bucket.
+#[derive(Debug, Clone, Default)]
+pub struct CocoEvaluator {
+    frames: Vec<CocoFrame>,
+}
+
+impl CocoEvaluator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one frame: predictions (any order), ground truth, and the image `(width, height)`.
+    pub fn add_frame(
+        &mut self,
+        boxes: &[[f32; 4]],
+        scores: &[f32],
+        gts: &[[f32; 4]],
+        image_size: (u32, u32),
+    ) {
+        let n = boxes.len().min(scores.len());
+        self.frames.push(CocoFrame {
+            boxes: boxes[..n].to_vec(),
+            scores: scores[..n].to_vec(),
+            gts: gts.to_vec(),
+            image_size,
+        });
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Score-sorted PR curve at `iou_threshold` over all sizes (at most 100 detections per frame).
+    pub fn pr_curve(&self, iou_threshold: f32) -> PrCurve {
+        self.matches(iou_threshold, AreaRange::All, COCO_MAX_DETS[2])
+            .curve(iou_threshold)
+    }
+
+    /// AP at one IoU threshold and size bucket (at most 100 detections per frame).
+    pub fn average_precision(&self, iou_threshold: f32, area: AreaRange) -> Option<f32> {
+        self.matches(iou_threshold, area, COCO_MAX_DETS[2])
+            .average_precision()
+    }
+
+    /// Recall averaged over the COCO IoU thresholds, keeping the top `max_dets` per frame.
+    pub fn average_recall(&self, max_dets: usize) -> Option<f32> {
+        mean(
+            COCO_IOU_THRESHOLDS
+                .iter()
+                .map(|&t| self.matches(t, AreaRange::All, max_dets).recall()),
+        )
+    }
+
+    /// AP averaged over the COCO IoU thresholds for one size bucket.
+    pub fn map(&self, area: AreaRange) -> Option<f32> {
+        mean(
+            COCO_IOU_THRESHOLDS
+                .iter()
+                .map(|&t| self.average_precision(t, area)),
+        )
+    }
+
+    pub fn evaluate(&self) -> CocoMetrics {
+        CocoMetrics {
+            frames: self.frames.len(),
+            num_gt: self.frames.iter().map(|f| f.gts.len()).sum(),
+            map: self.map(AreaRange::All),
+            map50: self.average_precision(0.5, AreaRange::All),
+            map75: self.average_precision(0.75, AreaRange::All),
+            map_small: self.map(AreaRange::Small),
+            map_medium: self.map(AreaRange::Medium),
+            map_large: self.map(AreaRange::Large),
+            ar1: self.average_recall(COCO_MAX_DETS[0]),
+            ar10: self.average_recall(COCO_MAX_DETS[1]),
+            ar100: self.average_recall(COCO_MAX_DETS[2]),
+            pr_curves: vec![self.pr_curve(0.5), self.pr_curve(0.75)],
+        }
+    }
+
+    fn matches(&self, iou_threshold: f32, area: AreaRange, max_dets: usize) -> Matches {
+        let mut ranked = Vec::new();
+        let mut num_gt = 0;
+        for frame in &self.frames {
+            let gt_ignored: Vec<bool> = frame
+                .gts
+                .iter()
+                .map(|&gt| !area.contains(frame.area(gt)))
+                .collect();
+            num_gt += gt_ignored.iter().filter(|ignored| !**ignored).count();
+            // Prefer in-bucket ground truth; fall back to ignored ones.
+            let mut gt_order: Vec<usize> = (0..frame.gts.len()).collect();
+            gt_order.sort_by_key(|&g| gt_ignored[g]);
+
+            let mut order: Vec<usize> = (0..frame.boxes.len()).collect();
+            order.sort_by(|&a, &b| frame.scores[b].total_cmp(&frame.scores[a]));
+            order.truncate(max_dets);
+
+            let mut claimed = vec![false; frame.gts.len()];
+            for p in order {
+                let mut best: Option<(usize, f32)> = None;
+                for &g in &gt_order {
+                    if claimed[g] {
+                        continue;
+                    }
+                    // Once an in-bucket match is found, ignored ground truth cannot replace it.
+                    if best.is_some_and(|(b, _)| !gt_ignored[b]) && gt_ignored[g] {
+                        break;
+                    }
+                    let iou = iou_xyxy(frame.boxes[p], frame.gts[g]);
+                    if iou >= best.map_or(iou_threshold, |(_, best_iou)| best_iou) {
+                        best = Some((g, iou));
+                    }
+                }
+                let ignored = match best {
+                    Some((g, _)) => {
+                        claimed[g] = true;
+                        gt_ignored[g]
+                    }
+                    None => !area.contains(frame.area(frame.boxes[p])),
+                };
+                if !ignored {
+                    ranked.push((frame.scores[p], best.is_some()));
+                }
+            }
+        }
+        // Stable sort keeps per-frame order among equal scores.
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        Matches { ranked, num_gt }
+    }
+}
+
+/// Mean of the defined values, `None` if there are none.
+fn mean(values: impl Iterator<Item = Option<f32>>) -> Option<f32> {
+    let values: Vec<f32> = values.flatten().collect();
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f32>() / values.len() as f32)
+    }
+}
+
+/// Validation summary for one dataset split.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValMetrics {
+    /// Split name (e.g. `val`).
+    pub name: String,
+    pub frames: usize,
+    pub val_loss: f32,
+    pub precision: f32,
+    pub recall: f32,
+    /// AP at IoU 0.5; `None` for models without box outputs.
+    pub map50: Option<f32>,
+}
+
+/// Validation metric used to rank checkpoints.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorMetric {
+    /// mAP@0.5 on the val split (higher is better; multibox only).
+    #[value(name = "val_map50")]
+    ValMap50,
+    /// Validation loss (lower is better).
+    #[value(name = "val_loss")]
+    ValLoss,
+}
+
+impl MonitorMetric {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MonitorMetric::ValMap50 => "val_map50",
+            MonitorMetric::ValLoss => "val_loss",
+        }
+    }
+
+    pub fn value(self, metrics: &ValMetrics) -> Option<f32> {
+        match self {
+            MonitorMetric::ValMap50 => metrics.map50,
+            MonitorMetric::ValLoss => Some(metrics.val_loss),
+        }
+    }
+
+    pub fn higher_is_better(self) -> bool {
+        matches!(self, MonitorMetric::ValMap50)
+    }
+}
+
+/// Difference between two validation splits, oriented so positive means `target` is worse.
+///
+/// Used for sim-to-real transfer: `reference` is the synthetic `val` split and `target` the real
+/// captures from `--real-val-dir`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DomainGap {
+    pub reference: String,
+    pub target: String,
+    /// `target` loss minus `reference` loss.
+    pub val_loss: f32,
+    /// `reference` precision minus `target` precision.
+    pub precision: f32,
+    /// `reference` recall minus `target` recall.
+    pub recall: f32,
+    /// `reference` mAP@0.5 minus `target` mAP@0.5; `None` without box outputs.
+    pub map50: Option<f32>,
+}
+
+impl DomainGap {
+    pub fn between(reference: &ValMetrics, target: &ValMetrics) -> Self {
+        Self {
+            reference: reference.name.clone(),
+            target: target.name.clone(),
+            val_loss: target.val_loss - reference.val_loss,
+            precision: reference.precision - target.precision,
+            recall: reference.recall - target.recall,
+            map50: reference.map50.zip(target.map50).map(|(r, t)| r - t),
+        }
+    }
+
+    /// Gap between the splits named `reference` and `target`, when both produced metrics.
+    pub fn from_splits(val: &[ValMetrics], reference: &str, target: &str) -> Option<Self> {
+        let find = |name: &str| val.iter().find(|v| v.name == name);
+        Some(Self::between(find(reference)?, find(target)?))
+    }
+}
+
+/// One line of the metrics JSONL log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochMetrics {
+    pub epoch: usize,
+    pub train_loss: f32,
+    pub val_metrics: Vec<ValMetrics>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain_gap: Option<DomainGap>,
+}
+
+/// Append `row` as one JSON line, creating the file and its parent directory as needed.
+pub fn append_jsonl<T: Serialize>(path: &Path, row: &T) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let mut line = serde_json::to_vec(row)?;
+    line.push(b'\n');
+    file.write_all(&line)?;
+    Ok(())
+}
+
+/// Training callback that prints each epoch summary and appends it to the metrics log.
+#[derive(Debug, Clone)]
+pub struct MetricsLog {
+    path: PathBuf,
+    /// `(reference, target)` split names to report a `DomainGap` for.
+    domain_gap: Option<(String, String)>,
+}
+
+impl MetricsLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            domain_gap: None,
+        }
+    }
+
+    /// Also report the gap between the `reference` and `target` splits each epoch.
+    pub fn domain_gap(mut self, reference: impl Into<String>, target: impl Into<String>) -> Self {
+        self.domain_gap = Some((reference.into(), target.into()));
+        self
+    }
+}
+
+impl<B: AutodiffBackend, M: AutodiffModule<B>, O> TrainCallback<B, M, O> for MetricsLog {
+    fn on_epoch_end(
+        &mut self,
+        info: &EpochInfo,
+        _model: &M,
+        _optim: &O,
+        _ema: Option<&ModelEma<B, M>>,
+    ) -> anyhow::Result<Control> {
+        let epoch = info.epoch;
+        println!("epoch {epoch}: avg loss {:.4}", info.train_loss);
+        for val in &info.val {
+            let map50 = val
+                .map50
+                .map(|ap| format!(" mAP@0.5 {ap:.4}"))
+                .unwrap_or_default();
+            println!(
+                "epoch {epoch}: {} loss {:.4} precision {:.3} recall {:.3}{map50}",
+                val.name, val.val_loss, val.precision, val.recall
+            );
+        }
+        let domain_gap = self
+            .domain_gap
+            .as_ref()
+            .and_then(|(reference, target)| DomainGap::from_splits(&info.val, reference, target));
+        if let Some(gap) = &domain_gap {
+            let map50 = gap
+                .map50
+                .map(|ap| format!(" mAP@0.5 {ap:+.4}"))
+                .unwrap_or_default();
+            println!(
+                "epoch {epoch}: domain gap {}->{} loss {:+.4} precision {:+.3} recall {:+.3}{map50}",
+                gap.reference, gap.target, gap.val_loss, gap.precision, gap.recall
+            );
+        }
+        let row = EpochMetrics {
+            epoch,
+            train_loss: info.train_loss,
+            val_metrics: info.val.clone(),
+            domain_gap,
+        };
+        append_jsonl(&self.path, &row).map_err(|e| {
+            anyhow::anyhow!("failed to append metrics to {}: {e}", self.path.display())
+        })?;
+        Ok(Control::Continue)
+    }
+}
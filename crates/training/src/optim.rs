@@ -0,0 +1,164 @@
+//! Learning-rate schedules and gradient controls used by `trainer::Trainer`.
+//!
+//! Schedules are a pure function of the global optimizer step, so a resumed run picks up the
+//! same learning rate it would have had. An optional linear warmup ramps from zero to
+//! `lr_start` first; the chosen decay then runs over the remaining steps.
+//!
+//! Gradient clipping rescales all gradients by one factor so their global L2 norm stays under
+//! a bound. Gradient accumulation sums the gradients of several micro-batches and averages them
+//! before a single optimizer step, which emulates a larger batch when memory caps the real one.
+
+use std::f64::consts::PI;
+
+use burn::module::{AutodiffModule, ModuleVisitor, Param};
+use burn::optim::GradientsParams;
+use burn::tensor::backend::AutodiffBackend;
+use burn::tensor::{ElementConversion, Tensor};
+use clap::ValueEnum;
+
+/// Learning-rate decay applied after warmup.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchedulerKind {
+    /// `lr_start` for the whole run.
+    #[default]
+    Constant,
+    /// Cosine decay from `lr_start` to `lr_end`.
+    Cosine,
+    /// Multiply by `gamma` every `step_size` steps.
+    Step,
+    /// Cosine ramp from `lr_start / 25` up to `lr_start`, then cosine decay to `lr_end`.
+    OneCycle,
+}
+
+/// Divisor for the starting learning rate of a one-cycle schedule.
+const ONE_CYCLE_DIV: f64 = 25.0;
+
+/// Learning rate as a function of the global optimizer step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LrSchedule {
+    pub kind: SchedulerKind,
+    /// Base (constant/step) or peak (cosine/one-cycle) learning rate.
+    pub lr_start: f64,
+    /// Final learning rate for cosine and one-cycle.
+    pub lr_end: f64,
+    /// Linear warmup from 0 to `lr_start` over this many steps.
+    pub warmup_steps: u64,
+    /// Optimizer steps in the whole run (warmup included).
+    pub total_steps: u64,
+    /// Steps between decays for `Step`.
+    pub step_size: u64,
+    /// Decay factor for `Step`.
+    pub gamma: f64,
+    /// Fraction of the post-warmup steps spent ramping up in `OneCycle`.
+    pub pct_start: f64,
+}
+
+impl LrSchedule {
+    pub fn constant(lr: f64) -> Self {
+        Self {
+            kind: SchedulerKind::Constant,
+            lr_start: lr,
+            lr_end: lr,
+            warmup_steps: 0,
+            total_steps: 0,
+            step_size: 1,
+            gamma: 1.0,
+            pct_start: 0.3,
+        }
+    }
+
+    /// Learning rate for the optimizer step with zero-based index `step`.
+    pub fn lr_at(&self, step: u64) -> f64 {
+        if step < self.warmup_steps {
+            return self.lr_start * (step + 1) as f64 / self.warmup_steps as f64;
+        }
+        let t = (step - self.warmup_steps) as f64;
+        let span = self.total_steps.saturating_sub(self.warmup_steps).max(1) as f64;
+        let progress = (t / span).min(1.0);
+        match self.kind {
+            SchedulerKind::Constant => self.lr_start,
+            SchedulerKind::Cosine => cosine(self.lr_start, self.lr_end, progress),
+            SchedulerKind::Step => {
+                let decays = (step - self.warmup_steps) / self.step_size.max(1);
+                self.lr_start * self.gamma.powi(decays.min(i32::MAX as u64) as i32)
+            }
+            SchedulerKind::OneCycle => {
+                let pct = self.pct_start.clamp(0.0, 1.0);
+                if progress < pct {
+                    cosine(self.lr_start / ONE_CYCLE_DIV, self.lr_start, progress / pct)
+                } else if pct >= 1.0 {
+                    self.lr_start
+                } else {
+                    cosine(self.lr_start, self.lr_end, (progress - pct) / (1.0 - pct))
+                }
+            }
+        }
+    }
+}
+
+/// Cosine interpolation from `from` (progress 0) to `to` (progress 1).
+fn cosine(from: f64, to: f64, progress: f64) -> f64 {
+    to + (from - to) * 0.5 * (1.0 + (PI * progress).cos())
+}
+
+/// Global L2 norm over every gradient of `module` present in `grads`.
+pub fn grad_norm<B, M>(module: &M, grads: &GradientsParams) -> f32
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+{
+    let mut visitor = SquaredNorm { grads, sum: 0.0 };
+    module.visit(&mut visitor);
+    visitor.sum.sqrt() as f32
+}
+
+/// Multiply every gradient of `module` in `grads` by `factor`.
+pub fn scale_grads<B, M>(module: &M, grads: &mut GradientsParams, factor: f32)
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+{
+    let mut visitor = Scale { grads, factor };
+    module.visit(&mut visitor);
+}
+
+/// Rescale `grads` so their global norm is at most `max_norm`; returns the norm before clipping.
+pub fn clip_grad_norm<B, M>(module: &M, grads: &mut GradientsParams, max_norm: f32) -> f32
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+{
+    let norm = grad_norm::<B, M>(module, grads);
+    if norm.is_finite() && norm > max_norm {
+        scale_grads::<B, M>(module, grads, max_norm / (norm + 1e-6));
+    }
+    norm
+}
+
+struct SquaredNorm<'a> {
+    grads: &'a GradientsParams,
+    sum: f64,
+}
+
+impl<B: AutodiffBackend> ModuleVisitor<B> for SquaredNorm<'_> {
+    fn visit_float<const D: usize>(&mut self, param: &Param<Tensor<B, D>>) {
+        if let Some(grad) = self.grads.get::<B::InnerBackend, D>(param.id) {
+            let squared = grad.powi_scalar(2).sum().into_scalar();
+            self.sum += squared.elem::<f64>();
+        }
+    }
+}
+
+struct Scale<'a> {
+    grads: &'a mut GradientsParams,
+    factor: f32,
+}
+
+impl<B: AutodiffBackend> ModuleVisitor<B> for Scale<'_> {
+    fn visit_float<const D: usize>(&mut self, param: &Param<Tensor<B, D>>) {
+        if let Some(grad) = self.grads.remove::<B::InnerBackend, D>(param.id) {
+            self.grads
+                .register::<B::InnerBackend, D>(param.id, grad.mul_scalar(self.factor));
+        }
+    }
+}
@@ -0,0 +1,329 @@
+//! Periodic resumable checkpoints for `run_train`.
+//!
+//! Every `--save-every` epochs the run writes `<checkpoint-dir>/epoch_NNNN/` containing the model
+//! record (`model.bin` plus its metadata sidecar), the optimizer record (`optim.bin`), the EMA
+//! shadow weights when `--ema` is on (`ema.bin`), and a `state.json` cursor (next epoch, global
+//! step, split seed, epoch metrics). `--resume <dir>` accepts either one of those epoch
+//! directories or the checkpoint dir itself (latest epoch wins) and continues from the next
+//! epoch. Old epoch directories are pruned down to the newest `--keep-last` plus, with
+//! `--keep-best`, the one `best.bin` was taken from (see `early_stop`).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use burn::module::{AutodiffModule, Module};
+use burn::optim::Optimizer;
+use burn::record::{BinFileRecorder, FullPrecisionSettings, Recorder};
+use burn::tensor::backend::AutodiffBackend;
+use models::checkpoint::CheckpointMetadata;
+use serde::{Deserialize, Serialize};
+
+use crate::early_stop::load_best_score;
+use crate::ema::{EmaConfig, ModelEma};
+use crate::metrics::ValMetrics;
+use crate::trainer::{Control, EpochInfo, TrainCallback};
+
+/// Version of the `state.json` layout.
+pub const RESUME_FORMAT_VERSION: u32 = 1;
+
+const STATE_FILE: &str = "state.json";
+const MODEL_FILE: &str = "model.bin";
+const OPTIM_FILE: &str = "optim.bin";
+const EMA_FILE: &str = "ema.bin";
+
+/// Training cursor stored next to each periodic checkpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResumeState {
+    pub format_version: u32,
+    /// Number of completed epochs; training resumes at this epoch index.
+    pub next_epoch: usize,
+    /// Optimizer steps taken so far.
+    pub step: u64,
+    /// Seed the train/val split was drawn with; a resumed run must use the same one.
+    pub seed: Option<u64>,
+    pub train_loss: f32,
+    pub val: Option<ValMetrics>,
+}
+
+impl ResumeState {
+    pub fn load(dir: &Path) -> anyhow::Result<Self> {
+        let path = dir.join(STATE_FILE);
+        let data = fs::read(&path)
+            .map_err(|e| anyhow::anyhow!("failed to read {}: {e}", path.display()))?;
+        let state: Self = serde_json::from_slice(&data)?;
+        if state.format_version != RESUME_FORMAT_VERSION {
+            anyhow::bail!(
+                "unsupported resume state version {} in {}",
+                state.format_version,
+                path.display()
+            );
+        }
+        Ok(state)
+    }
+}
+
+/// Resolve `--resume <dir>` to a concrete epoch checkpoint directory.
+///
+/// `dir` may be an epoch directory (has `state.json`) or a checkpoint dir holding several.
+pub fn resolve_resume_dir(dir: &Path) -> anyhow::Result<PathBuf> {
+    if dir.join(STATE_FILE).is_file() {
+        return Ok(dir.to_path_buf());
+    }
+    epoch_dirs(dir)?
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("no resumable checkpoints found under {}", dir.display()))
+}
+
+/// A checkpoint restored by `--resume`.
+pub struct Resumed<M, O> {
+    pub model: M,
+    pub optim: O,
+    pub state: ResumeState,
+}
+
+/// Load model and optimizer records from an epoch checkpoint directory into `model`/`optim`.
+///
+/// The stored architecture must match `expected`, and the stored split seed must match `seed`.
+pub fn restore<B, M, O>(
+    dir: &Path,
+    model: M,
+    optim: O,
+    expected: &CheckpointMetadata,
+    seed: Option<u64>,
+    device: &B::Device,
+) -> anyhow::Result<Resumed<M, O>>
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+    O: Optimizer<M, B>,
+{
+    let state = ResumeState::load(dir)?;
+    if state.seed != seed {
+        anyhow::bail!(
+            "checkpoint {} was trained with --seed {:?}, but this run uses {:?}; the train/val split would differ",
+            dir.display(),
+            state.seed,
+            seed
+        );
+    }
+    let model_path = dir.join(MODEL_FILE);
+    let meta = CheckpointMetadata::load(&model_path)?;
+    if meta.model != expected.model {
+        anyhow::bail!(
+            "checkpoint {} holds a different model configuration than the current flags",
+            dir.display()
+        );
+    }
+
+    let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+    let model = model
+        .load_file(&model_path, &recorder, device)
+        .map_err(|e| anyhow::anyhow!("failed to load {}: {e}", model_path.display()))?;
+    let optim_path = dir.join(OPTIM_FILE);
+    let record: O::Record = Recorder::<B>::load(&recorder, optim_path.clone(), device)
+        .map_err(|e| anyhow::anyhow!("failed to load {}: {e}", optim_path.display()))?;
+    Ok(Resumed {
+        model,
+        optim: optim.load_record(record),
+        state,
+    })
+}
+
+/// Restore the EMA for a resumed run from `dir/ema.bin`, taken after `state.step` updates.
+///
+/// Checkpoints written without `--ema` restart the average from the resumed `model`.
+pub fn restore_ema<B, M>(
+    dir: &Path,
+    model: &M,
+    config: EmaConfig,
+    state: &ResumeState,
+    device: &B::Device,
+) -> anyhow::Result<ModelEma<B, M>>
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+{
+    let path = dir.join(EMA_FILE);
+    if !path.is_file() {
+        return Ok(ModelEma::new(model, config));
+    }
+    let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+    let shadow = model
+        .valid()
+        .load_file(&path, &recorder, device)
+        .map_err(|e| anyhow::anyhow!("failed to load {}: {e}", path.display()))?;
+    Ok(ModelEma::from_shadow(shadow, config, state.step))
+}
+
+/// When to write periodic checkpoints and which ones to keep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointPolicy {
+    /// Save every N epochs (0 disables; the final epoch, early stop included, is always saved
+    /// otherwise).
+    pub save_every: usize,
+    /// Number of newest epoch directories to keep.
+    pub keep_last: usize,
+    /// Also keep the epoch recorded in `best_score.json`, i.e. the one `best.bin` came from.
+    pub keep_best: bool,
+}
+
+impl Default for CheckpointPolicy {
+    fn default() -> Self {
+        Self {
+            save_every: 1,
+            keep_last: 2,
+            keep_best: false,
+        }
+    }
+}
+
+/// Writes periodic checkpoints and applies the retention policy.
+///
+/// As a `TrainCallback` it saves at the end of each scheduled epoch and the final one, recording
+/// the first validation split's metrics in the cursor. Register it after callbacks that may stop
+/// the run, so the epoch they stop on counts as final.
+#[derive(Debug, Clone)]
+pub struct RunCheckpointer {
+    dir: PathBuf,
+    metadata: CheckpointMetadata,
+    policy: CheckpointPolicy,
+    seed: Option<u64>,
+    epochs: usize,
+}
+
+impl RunCheckpointer {
+    pub fn new(
+        dir: PathBuf,
+        metadata: CheckpointMetadata,
+        policy: CheckpointPolicy,
+        seed: Option<u64>,
+        epochs: usize,
+    ) -> Self {
+        Self {
+            dir,
+            metadata,
+            policy,
+            seed,
+            epochs,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Save a checkpoint after `state.next_epoch` epochs if the schedule says so.
+    ///
+    /// The final epoch (`is_final`, or the scheduled last one) is always saved. Returns the epoch
+    /// directory when one was written.
+    pub fn save<B, M, O>(
+        &self,
+        state: &ResumeState,
+        is_final: bool,
+        model: &M,
+        optim: &O,
+        ema: Option<&ModelEma<B, M>>,
+    ) -> anyhow::Result<Option<PathBuf>>
+    where
+        B: AutodiffBackend,
+        M: AutodiffModule<B>,
+        O: Optimizer<M, B>,
+    {
+        let save_every = self.policy.save_every;
+        let is_final = is_final || state.next_epoch == self.epochs;
+        if save_every == 0 || (state.next_epoch % save_every != 0 && !is_final) {
+            return Ok(None);
+        }
+        let dir = self.dir.join(format!("epoch_{:04}", state.next_epoch));
+        fs::create_dir_all(&dir)?;
+
+        let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
+        let model_path = dir.join(MODEL_FILE);
+        model
+            .clone()
+            .save_file(&model_path, &recorder)
+            .map_err(|e| anyhow::anyhow!("failed to save {}: {e}", model_path.display()))?;
+        self.metadata
+            .save(&model_path)
+            .map_err(|e| anyhow::anyhow!("failed to write checkpoint metadata: {e}"))?;
+        Recorder::<B>::record(&recorder, optim.to_record(), dir.join(OPTIM_FILE))
+            .map_err(|e| anyhow::anyhow!("failed to save optimizer state: {e}"))?;
+        if let Some(ema) = ema {
+            let ema_path = dir.join(EMA_FILE);
+            ema.shadow()
+                .clone()
+                .save_file(&ema_path, &recorder)
+                .map_err(|e| anyhow::anyhow!("failed to save {}: {e}", ema_path.display()))?;
+        }
+        // Written last: a directory without state.json is an interrupted save and is ignored.
+        fs::write(dir.join(STATE_FILE), serde_json::to_vec_pretty(state)?)?;
+
+        self.prune()?;
+        Ok(Some(dir))
+    }
+
+    /// Drop epoch directories outside the newest `keep_last` and the best one.
+    ///
+    /// A `best_score.json` outside this run's epochs is left over from another run and ignored.
+    fn prune(&self) -> anyhow::Result<()> {
+        let dirs = epoch_dirs(&self.dir)?;
+        let best = match self.policy.keep_best {
+            true => load_best_score(&self.dir)?
+                .filter(|best| best.epoch < self.epochs)
+                .map(|best| self.dir.join(format!("epoch_{:04}", best.epoch + 1))),
+            false => None,
+        };
+        let keep_from = dirs.len().saturating_sub(self.policy.keep_last.max(1));
+        for (idx, dir) in dirs.iter().enumerate() {
+            if idx >= keep_from || Some(dir) == best.as_ref() {
+                continue;
+            }
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+}
+
+impl<B, M, O> TrainCallback<B, M, O> for RunCheckpointer
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+    O: Optimizer<M, B>,
+{
+    fn on_epoch_end(
+        &mut self,
+        info: &EpochInfo,
+        model: &M,
+        optim: &O,
+        ema: Option<&ModelEma<B, M>>,
+    ) -> anyhow::Result<Control> {
+        let state = ResumeState {
+            format_version: RESUME_FORMAT_VERSION,
+            next_epoch: info.epoch + 1,
+            step: info.step,
+            seed: self.seed,
+            train_loss: info.train_loss,
+            val: info.val.first().cloned(),
+        };
+        self.save(&state, info.is_final, model, optim, ema)?;
+        Ok(Control::Continue)
+    }
+}
+
+/// Completed `epoch_NNNN` directories under `root`, oldest first.
+fn epoch_dirs(root: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut dirs: Vec<PathBuf> = fs::read_dir(root)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("epoch_"))
+                && path.join(STATE_FILE).is_file()
+        })
+        .collect();
+    dirs.sort();
+    Ok(dirs)
+}
@@ -0,0 +1,105 @@
+//! Live progress reporting for `train --status-file`.
+//!
+//! `StatusReporter` keeps a `data_contracts::TrainStatus` up to date and rewrites it atomically
+//! after every optimizer step, so the tools TUI can poll it mid-run. Without a status path it
+//! only tracks progress in memory.
+
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use burn::module::AutodiffModule;
+use burn::tensor::backend::AutodiffBackend;
+use data_contracts::{TrainState, TrainStatus};
+
+use crate::trainer::{StepInfo, TrainCallback};
+
+pub struct StatusReporter {
+    path: Option<PathBuf>,
+    status: TrainStatus,
+    started: Instant,
+    /// Step count when this process started (non-zero after `--resume`).
+    start_step: u64,
+}
+
+impl StatusReporter {
+    pub fn new(path: Option<PathBuf>, epochs: usize, lr: f64, checkpoint: &Path) -> Self {
+        let mut status = TrainStatus::new(epochs, lr);
+        status.checkpoint = Some(checkpoint.to_path_buf());
+        Self {
+            path,
+            status,
+            started: Instant::now(),
+            start_step: 0,
+        }
+    }
+
+    pub fn status(&self) -> &TrainStatus {
+        &self.status
+    }
+
+    /// Record the run length so the ETA can be estimated.
+    pub fn set_total_steps(&mut self, total_steps: u64) {
+        self.status.total_steps = Some(total_steps);
+    }
+
+    /// Continue counting from a resumed checkpoint's cursor.
+    pub fn resume_at(&mut self, epoch: usize, step: u64) {
+        self.status.epoch = epoch;
+        self.status.step = step;
+        self.start_step = step;
+        self.started = Instant::now();
+    }
+
+    /// Publish the initial `starting` status.
+    pub fn begin(&mut self) -> anyhow::Result<()> {
+        self.started = Instant::now();
+        self.write()
+    }
+
+    /// Record that `step` optimizer steps are done (the latest in `epoch`) and publish.
+    pub fn step(&mut self, epoch: usize, step: u64, lr: f64, loss: f32) -> anyhow::Result<()> {
+        self.status.state = TrainState::Running;
+        self.status.epoch = epoch;
+        self.status.step = step;
+        self.status.lr = lr;
+        self.status.loss = Some(loss);
+        let steps_taken = self.status.step.saturating_sub(self.start_step).max(1);
+        self.status.eta_secs = self.status.total_steps.map(|total| {
+            let per_step = self.started.elapsed().as_secs_f64() / steps_taken as f64;
+            per_step * total.saturating_sub(self.status.step) as f64
+        });
+        self.write()
+    }
+
+    pub fn finish(&mut self) -> anyhow::Result<()> {
+        self.status.state = TrainState::Completed;
+        self.status.eta_secs = Some(0.0);
+        self.write()
+    }
+
+    pub fn fail(&mut self, err: &anyhow::Error) -> anyhow::Result<()> {
+        self.status.state = TrainState::Failed;
+        self.status.eta_secs = None;
+        self.status.error = Some(format!("{err:#}"));
+        self.write()
+    }
+
+    fn write(&mut self) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        self.status.updated_at_unix = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs_f64())
+            .unwrap_or(0.0);
+        self.status
+            .write_atomic(path)
+            .map_err(|e| anyhow::anyhow!("failed to write status file {}: {e}", path.display()))
+    }
+}
+
+impl<B: AutodiffBackend, M: AutodiffModule<B>, O> TrainCallback<B, M, O> for StatusReporter {
+    fn on_step(&mut self, info: &StepInfo) -> anyhow::Result<()> {
+        self.step(info.epoch, info.step, info.lr, info.loss)
+    }
+}
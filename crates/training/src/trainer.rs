@@ -0,0 +1,840 @@
+//! Generic training loop shared by every model and data source.
+//!
+//! `Trainer` runs forward/loss/backward/step over a `BatchSource`, validates on named splits at
+//! the end of each epoch, and reports through `TrainCallback`s (status file, metrics log,
+//! checkpoints, ...). Models plug in via `TrainableModel`, objectives via `TrainLoss`, and
+//! validation scoring via `Validator`; each is implemented once and works with every source.
+//! The learning rate follows an `optim::LrSchedule`; gradients can be accumulated over several
+//! micro-batches and clipped by global norm before each optimizer step. An optional `ema::ModelEma`
+//! tracks a moving average of the weights and can stand in for them during validation.
+
+use burn::module::AutodiffModule;
+use burn::nn::loss::{MseLoss, Reduction};
+use burn::optim::{GradientsAccumulator, GradientsParams, Optimizer};
+use burn::tensor::backend::{AutodiffBackend, Backend};
+use burn::tensor::Tensor;
+use burn_dataset::{
+    BatchIter, CacheableTransformConfig, DatasetConfig, SampleIndex, TransformPipeline,
+    WarehouseLoaders,
+};
+use models::input::linear_input;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fs;
+use std::path::Path;
+
+use crate::ema::{select_weights, ModelEma, WeightsKind};
+use crate::loss::LossConfig;
+use crate::matcher::{
+    assign_slots, class_targets, targets_from_assignment, MatchCost, MatcherKind,
+};
+use crate::metrics::{nms, DetectionAccumulator, ValMetrics};
+use crate::optim::{clip_grad_norm, grad_norm, scale_grads, LrSchedule};
+use crate::{CollatedBatch, LinearClassifier, MultiboxModel, RunSample};
+
+/// Fallible stream of collated batches.
+pub type Batches<'a, B> = Box<dyn Iterator<Item = anyhow::Result<CollatedBatch<B>>> + 'a>;
+
+/// A dataset split the trainer can iterate once per epoch.
+pub trait BatchSource<B: Backend> {
+    /// Number of samples in one pass.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Batches for one pass over the split.
+    fn batches<'a>(&'a self, device: &'a B::Device) -> Batches<'a, B>;
+
+    /// Batches for training epoch `epoch`. Sources with random augmentations derive the epoch's
+    /// draws from it, so a resumed run continues the same stream instead of replaying epoch 0.
+    fn epoch_batches<'a>(&'a self, _epoch: usize, device: &'a B::Device) -> Batches<'a, B> {
+        self.batches(device)
+    }
+}
+
+/// Capture-log samples, decoded and collated with `collate` (or `collate_augmented`).
+#[derive(Debug, Clone)]
+pub struct SampleSource<'a> {
+    samples: &'a [RunSample],
+    batch_size: usize,
+    max_boxes: usize,
+    drop_last: bool,
+    augment: Option<(TransformPipeline, u64)>,
+}
+
+impl<'a> SampleSource<'a> {
+    pub fn new(samples: &'a [RunSample], batch_size: usize, max_boxes: usize) -> Self {
+        Self {
+            samples,
+            batch_size: batch_size.max(1),
+            max_boxes,
+            drop_last: false,
+            augment: None,
+        }
+    }
+
+    /// Skip the trailing partial batch.
+    pub fn drop_last(mut self, drop_last: bool) -> Self {
+        self.drop_last = drop_last;
+        self
+    }
+
+    /// Augment every sample with `pipeline`'s per-sample knobs, drawing each epoch's
+    /// randomness from `(seed, epoch)`.
+    pub fn augment(mut self, pipeline: TransformPipeline, seed: u64) -> Self {
+        self.augment = Some((pipeline, seed));
+        self
+    }
+}
+
+/// RNG for the augmentations of training epoch `epoch` under `seed`.
+fn epoch_rng(seed: u64, epoch: usize) -> StdRng {
+    // Spread the epoch over the seed so nearby seeds do not share shifted streams.
+    StdRng::seed_from_u64(seed ^ (epoch as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+impl<B: Backend> BatchSource<B> for SampleSource<'_> {
+    fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    fn batches<'a>(&'a self, device: &'a B::Device) -> Batches<'a, B> {
+        self.epoch_batches(0, device)
+    }
+
+    fn epoch_batches<'a>(&'a self, epoch: usize, _device: &'a B::Device) -> Batches<'a, B> {
+        let mut augment = self
+            .augment
+            .as_ref()
+            .map(|(pipeline, seed)| (pipeline, epoch_rng(*seed, epoch)));
+        Box::new(
+            self.samples
+                .chunks(self.batch_size)
+                .filter(|chunk| !self.drop_last || chunk.len() == self.batch_size)
+                .map(move |chunk| match &mut augment {
+                    Some((pipeline, rng)) => {
+                        crate::collate_augmented::<B>(chunk, self.max_boxes, pipeline, rng)
+                    }
+                    None => crate::collate::<B>(chunk, self.max_boxes),
+                }),
+        )
+    }
+}
+
+/// Which side of a warehouse train/val split to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarehouseSplit {
+    Train,
+    Val,
+}
+
+/// Warehouse shards, collated with `collate_from_burn_batch`.
+///
+/// `drop_last` is a property of the `WarehouseLoaders` themselves.
+#[derive(Clone, Copy)]
+pub struct WarehouseSource<'a> {
+    loaders: &'a WarehouseLoaders,
+    split: WarehouseSplit,
+    batch_size: usize,
+    max_boxes: usize,
+}
+
+impl<'a> WarehouseSource<'a> {
+    pub fn new(
+        loaders: &'a WarehouseLoaders,
+        split: WarehouseSplit,
+        batch_size: usize,
+        max_boxes: usize,
+    ) -> Self {
+        Self {
+            loaders,
+            split,
+            batch_size: batch_size.max(1),
+            max_boxes,
+        }
+    }
+}
+
+impl<B: Backend> BatchSource<B> for WarehouseSource<'_> {
+    fn len(&self) -> usize {
+        match self.split {
+            WarehouseSplit::Train => self.loaders.train_len(),
+            WarehouseSplit::Val => self.loaders.val_len(),
+        }
+    }
+
+    fn batches<'a>(&'a self, device: &'a B::Device) -> Batches<'a, B> {
+        let mut iter = match self.split {
+            WarehouseSplit::Train => self.loaders.train_iter(),
+            WarehouseSplit::Val => self.loaders.val_iter(),
+        };
+        let batch_size = self.batch_size;
+        let max_boxes = self.max_boxes;
+        Box::new(
+            std::iter::from_fn(move || iter.next_batch::<B>(batch_size, device).transpose())
+                .map(move |batch| crate::collate_from_burn_batch::<B>(batch?, max_boxes)),
+        )
+    }
+}
+
+/// Capture-log frames run through a warehouse's cacheable transform, so they are resized exactly
+/// like that warehouse's shards, and collated like `WarehouseSource`.
+pub struct TransformedSource {
+    indices: Vec<SampleIndex>,
+    cfg: DatasetConfig,
+    batch_size: usize,
+}
+
+impl TransformedSource {
+    /// Every labeled frame of the capture run at `root`, preprocessed per `transform`.
+    pub fn new(
+        root: &Path,
+        labels_subdir: &str,
+        images_subdir: &str,
+        transform: &CacheableTransformConfig,
+        batch_size: usize,
+        max_boxes: usize,
+    ) -> anyhow::Result<Self> {
+        let labels_dir = root.join(labels_subdir);
+        let mut indices = Vec::new();
+        for entry in fs::read_dir(&labels_dir)? {
+            let label_path = entry?.path();
+            if label_path.extension().and_then(|e| e.to_str()) == Some("json") {
+                indices.push(SampleIndex {
+                    run_dir: root.join(images_subdir),
+                    label_path,
+                });
+            }
+        }
+        indices.sort_by(|a, b| a.label_path.cmp(&b.label_path));
+        // Deterministic: no shuffling or augmentation, and empty frames are kept.
+        let cfg = DatasetConfig {
+            target_size: transform.target_size,
+            resize_mode: transform.resize_mode,
+            max_boxes,
+            skip_empty_labels: false,
+            flip_horizontal_prob: 0.0,
+            color_jitter_prob: 0.0,
+            color_jitter_strength: 0.0,
+            scale_jitter_prob: 0.0,
+            noise_prob: 0.0,
+            blur_prob: 0.0,
+            drop_last: false,
+            shuffle: false,
+            ..DatasetConfig::default()
+        };
+        Ok(Self {
+            indices,
+            cfg,
+            batch_size: batch_size.max(1),
+        })
+    }
+}
+
+impl<B: Backend> BatchSource<B> for TransformedSource {
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn batches<'a>(&'a self, device: &'a B::Device) -> Batches<'a, B> {
+        let mut iter = match BatchIter::from_indices(self.indices.clone(), self.cfg.clone()) {
+            Ok(iter) => iter,
+            Err(e) => return Box::new(std::iter::once(Err(e.into()))),
+        };
+        let batch_size = self.batch_size;
+        let max_boxes = self.cfg.max_boxes;
+        Box::new(
+            std::iter::from_fn(move || iter.next_batch::<B>(batch_size, device).transpose())
+                .map(move |batch| crate::collate_from_burn_batch::<B>(batch?, max_boxes)),
+        )
+    }
+}
+
+/// A model the trainer can run on collated batches.
+pub trait TrainableModel<B: Backend> {
+    type Output: Clone;
+
+    fn forward_batch(&self, batch: &CollatedBatch<B>) -> Self::Output;
+}
+
+impl<B: Backend> TrainableModel<B> for LinearClassifier<B> {
+    /// `[batch, 1]` box-presence score.
+    type Output = Tensor<B, 2>;
+
+    fn forward_batch(&self, batch: &CollatedBatch<B>) -> Self::Output {
+        // Input: image stats only (mean RGB + aspect); labels never feed the model.
+        self.forward(linear_input(batch.features.clone()))
+    }
+}
+
+impl<B: Backend> TrainableModel<B> for MultiboxModel<B> {
+    /// `([batch, slots, 4]` boxes, `[batch, slots]` scores, `[batch, slots, classes]` logits).
+    type Output = (Tensor<B, 3>, Tensor<B, 2>, Tensor<B, 3>);
+
+    fn forward_batch(&self, batch: &CollatedBatch<B>) -> Self::Output {
+        // Input: image tensor + image stats, built exactly as at inference time.
+        self.forward_multibox_images_with_classes(batch.images.clone(), batch.features.clone())
+    }
+}
+
+/// A scalar training objective over a model's outputs.
+pub trait TrainLoss<B: Backend, O> {
+    fn loss(&self, output: O, batch: &CollatedBatch<B>) -> Tensor<B, 1>;
+}
+
+/// MSE between the linear classifier's score and the GT box count.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BoxPresenceLoss;
+
+impl<B: Backend> TrainLoss<B, Tensor<B, 2>> for BoxPresenceLoss {
+    fn loss(&self, output: Tensor<B, 2>, batch: &CollatedBatch<B>) -> Tensor<B, 1> {
+        MseLoss::new().forward(output, box_presence(batch), Reduction::Mean)
+    }
+}
+
+/// Matched multibox loss: objectness over all slots plus box regression (and, for multi-class
+/// models, classification) on matched slots.
+#[derive(Debug, Clone, Copy)]
+pub struct MultiboxLoss {
+    pub matcher: MatcherKind,
+    pub cost: MatchCost,
+    pub loss: LossConfig,
+    pub lambda_box: f32,
+    pub lambda_obj: f32,
+    pub lambda_cls: f32,
+}
+
+impl Default for MultiboxLoss {
+    fn default() -> Self {
+        Self {
+            matcher: MatcherKind::default(),
+            cost: MatchCost::default(),
+            loss: LossConfig::default(),
+            lambda_box: 1.0,
+            lambda_obj: 1.0,
+            lambda_cls: 1.0,
+        }
+    }
+}
+
+impl<B: Backend> TrainLoss<B, (Tensor<B, 3>, Tensor<B, 2>, Tensor<B, 3>)> for MultiboxLoss {
+    fn loss(
+        &self,
+        (pred_boxes, pred_scores, class_logits): (Tensor<B, 3>, Tensor<B, 2>, Tensor<B, 3>),
+        batch: &CollatedBatch<B>,
+    ) -> Tensor<B, 1> {
+        // Assign GTs to prediction slots (greedy best-IoU or optimal Hungarian).
+        let max_pred = pred_boxes.dims()[1];
+        let assignment = assign_slots(
+            self.matcher,
+            &self.cost,
+            pred_boxes.clone(),
+            pred_scores.clone(),
+            batch.boxes.clone(),
+            batch.box_mask.clone(),
+        );
+        let (obj_targets, box_targets, box_weights) =
+            targets_from_assignment(&assignment, max_pred, batch.boxes.clone());
+        let obj_loss = self.loss.objectness_loss(pred_scores, obj_targets);
+        let box_loss = self.loss.box_loss(pred_boxes, box_targets, box_weights);
+        let total = box_loss * self.lambda_box + obj_loss * self.lambda_obj;
+
+        // Single-class models have nothing to classify.
+        let num_classes = class_logits.dims()[2];
+        if num_classes <= 1 {
+            return total;
+        }
+        let targets = class_targets(&assignment, max_pred, batch.classes.clone(), num_classes);
+        total + self.loss.classification_loss(class_logits, targets) * self.lambda_cls
+    }
+}
+
+/// Detection quality of one validation pass (loss is tracked by the trainer).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValScores {
+    pub frames: usize,
+    pub precision: f32,
+    pub recall: f32,
+    pub map50: Option<f32>,
+}
+
+/// Accumulates validation scores from a model's outputs.
+pub trait Validator<B: Backend, O> {
+    /// Forget the previous pass.
+    fn reset(&mut self);
+
+    fn update(&mut self, output: O, batch: &CollatedBatch<B>);
+
+    fn scores(&self) -> ValScores;
+}
+
+/// Frame-level "has a box" precision/recall for score-per-frame models.
+#[derive(Debug, Clone)]
+pub struct FrameValidator {
+    threshold: f32,
+    frames: usize,
+    tp: usize,
+    fp: usize,
+    fn_: usize,
+}
+
+impl FrameValidator {
+    /// Frames scoring at least `threshold` count as predicting a box.
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            frames: 0,
+            tp: 0,
+            fp: 0,
+            fn_: 0,
+        }
+    }
+}
+
+impl<B: Backend> Validator<B, Tensor<B, 2>> for FrameValidator {
+    fn reset(&mut self) {
+        *self = Self::new(self.threshold);
+    }
+
+    fn update(&mut self, output: Tensor<B, 2>, batch: &CollatedBatch<B>) {
+        let preds = output.into_data().to_vec::<f32>().unwrap_or_default();
+        let targets = box_presence(batch)
+            .into_data()
+            .to_vec::<f32>()
+            .unwrap_or_default();
+        for (pred, target) in preds.iter().zip(&targets) {
+            self.frames += 1;
+            match (*pred >= self.threshold, *target > 0.0) {
+                (true, true) => self.tp += 1,
+                (true, false) => self.fp += 1,
+                (false, true) => self.fn_ += 1,
+                (false, false) => {}
+            }
+        }
+    }
+
+    fn scores(&self) -> ValScores {
+        ValScores {
+            frames: self.frames,
+            precision: ratio(self.tp, self.tp + self.fp),
+            recall: ratio(self.tp, self.tp + self.fn_),
+            map50: None,
+        }
+    }
+}
+
+/// Box precision/recall/mAP@0.5 for multibox outputs.
+///
+/// Predictions go through the same NMS as inference; precision/recall are taken at the score
+/// threshold, AP over all surviving predictions.
+#[derive(Debug, Clone)]
+pub struct BoxValidator {
+    nms_iou: f32,
+    score_threshold: f32,
+    acc: DetectionAccumulator,
+}
+
+impl BoxValidator {
+    pub fn new(score_threshold: f32, nms_iou: f32) -> Self {
+        Self {
+            nms_iou,
+            score_threshold,
+            acc: DetectionAccumulator::new(0.5, score_threshold),
+        }
+    }
+}
+
+impl<B: Backend> Validator<B, (Tensor<B, 3>, Tensor<B, 2>, Tensor<B, 3>)> for BoxValidator {
+    fn reset(&mut self) {
+        *self = Self::new(self.score_threshold, self.nms_iou);
+    }
+
+    fn update(
+        &mut self,
+        // Class-agnostic: class logits are scored per class by `eval`.
+        (pred_boxes, pred_scores, _): (Tensor<B, 3>, Tensor<B, 2>, Tensor<B, 3>),
+        batch: &CollatedBatch<B>,
+    ) {
+        let [frames, slots, _] = pred_boxes.dims();
+        let max_gt = batch.boxes.dims()[1];
+        let boxes = pred_boxes.into_data().to_vec::<f32>().unwrap_or_default();
+        let scores = pred_scores.into_data().to_vec::<f32>().unwrap_or_default();
+        let gt_boxes = batch
+            .boxes
+            .clone()
+            .into_data()
+            .to_vec::<f32>()
+            .unwrap_or_default();
+        let gt_mask = batch
+            .box_mask
+            .clone()
+            .into_data()
+            .to_vec::<f32>()
+            .unwrap_or_default();
+        let corners = |v: &[f32], i: usize| [v[i * 4], v[i * 4 + 1], v[i * 4 + 2], v[i * 4 + 3]];
+        for b in 0..frames {
+            let frame_boxes: Vec<[f32; 4]> =
+                (0..slots).map(|p| corners(&boxes, b * slots + p)).collect();
+            let frame_scores = &scores[b * slots..(b + 1) * slots];
+            let keep = nms(&frame_boxes, frame_scores, self.nms_iou);
+            let kept_boxes: Vec<[f32; 4]> = keep.iter().map(|&i| frame_boxes[i]).collect();
+            let kept_scores: Vec<f32> = keep.iter().map(|&i| frame_scores[i]).collect();
+            let gts: Vec<[f32; 4]> = (0..max_gt)
+                .filter(|g| gt_mask[b * max_gt + g] >= 0.5)
+                .map(|g| corners(&gt_boxes, b * max_gt + g))
+                .collect();
+            self.acc.add_frame(&kept_boxes, &kept_scores, &gts);
+        }
+    }
+
+    fn scores(&self) -> ValScores {
+        ValScores {
+            frames: self.acc.frames(),
+            precision: self.acc.precision(),
+            recall: self.acc.recall(),
+            map50: Some(self.acc.average_precision()),
+        }
+    }
+}
+
+/// One optimizer step, as reported to callbacks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepInfo {
+    pub epoch: usize,
+    /// Optimizer steps taken so far, including this one.
+    pub step: u64,
+    /// Learning rate this step was taken with.
+    pub lr: f64,
+    /// Mean loss over the step's micro-batches.
+    pub loss: f32,
+    /// Global gradient norm before clipping.
+    pub grad_norm: f32,
+}
+
+/// One finished epoch, as reported to callbacks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EpochInfo {
+    pub epoch: usize,
+    pub step: u64,
+    pub train_loss: f32,
+    /// One entry per validation split that produced frames.
+    pub val: Vec<ValMetrics>,
+    /// Whether this is the run's last epoch: the scheduled last one, or one an earlier callback
+    /// returned `Control::Stop` on.
+    pub is_final: bool,
+}
+
+/// Whether training should go on after an epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    Stop,
+}
+
+/// Hooks invoked by `Trainer::fit`; every method defaults to a no-op.
+pub trait TrainCallback<B: AutodiffBackend, M: AutodiffModule<B>, O> {
+    fn on_step(&mut self, _info: &StepInfo) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called after validation; returning `Control::Stop` ends training after this epoch and
+    /// marks it `is_final` for the callbacks after this one.
+    ///
+    /// `ema` is the weight average when the trainer keeps one.
+    fn on_epoch_end(
+        &mut self,
+        _info: &EpochInfo,
+        _model: &M,
+        _optim: &O,
+        _ema: Option<&ModelEma<B, M>>,
+    ) -> anyhow::Result<Control> {
+        Ok(Control::Continue)
+    }
+}
+
+/// A named validation split.
+pub struct ValSplit<'a, B: Backend> {
+    pub name: String,
+    pub source: &'a dyn BatchSource<B>,
+}
+
+/// Model output on the inner (non-autodiff) backend used for validation.
+pub type InnerOutput<B, M> = <<M as AutodiffModule<B>>::InnerModule as TrainableModel<
+    <B as AutodiffBackend>::InnerBackend,
+>>::Output;
+
+/// Weights produced by `Trainer::fit`.
+pub struct Fitted<B: AutodiffBackend, M: AutodiffModule<B>> {
+    pub model: M,
+    pub ema: Option<ModelEma<B, M>>,
+}
+
+impl<B: AutodiffBackend, M: AutodiffModule<B>> Fitted<B, M> {
+    /// The selected weights on the inner backend (raw when no EMA was kept).
+    pub fn weights(self, kind: WeightsKind) -> M::InnerModule {
+        select_weights(&self.model, self.ema.as_ref(), kind)
+    }
+}
+
+/// Epoch loop over a model, optimizer, loss, and validator.
+pub struct Trainer<'a, B, M, O, L, V>
+where
+    B: AutodiffBackend,
+    M: AutodiffModule<B>,
+{
+    model: M,
+    optim: O,
+    loss: L,
+    validator: V,
+    ema: Option<ModelEma<B, M>>,
+    val_weights: WeightsKind,
+    schedule: LrSchedule,
+    grad_clip: Option<f32>,
+    grad_accum: usize,
+    epochs: usize,
+    start_epoch: usize,
+    step: u64,
+    callbacks: Vec<&'a mut dyn TrainCallback<B, M, O>>,
+}
+
+impl<'a, B, M, O, L, V> Trainer<'a, B, M, O, L, V>
+where
+    B: AutodiffBackend,
+    M: TrainableModel<B> + AutodiffModule<B>,
+    M::InnerModule: TrainableModel<B::InnerBackend>,
+    O: Optimizer<M, B>,
+    L: TrainLoss<B, M::Output> + TrainLoss<B::InnerBackend, InnerOutput<B, M>>,
+    V: Validator<B::InnerBackend, InnerOutput<B, M>>,
+{
+    /// Trainer with a constant learning rate `lr`, no clipping, and no accumulation.
+    pub fn new(model: M, optim: O, loss: L, validator: V, epochs: usize, lr: f64) -> Self {
+        Self {
+            model,
+            optim,
+            loss,
+            validator,
+            ema: None,
+            val_weights: WeightsKind::Raw,
+            schedule: LrSchedule::constant(lr),
+            grad_clip: None,
+            grad_accum: 1,
+            epochs,
+            start_epoch: 0,
+            step: 0,
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Continue a run at `epoch` with `step` optimizer steps already taken.
+    pub fn resume_at(mut self, epoch: usize, step: u64) -> Self {
+        self.start_epoch = epoch;
+        self.step = step;
+        self
+    }
+
+    /// Learning rate per global optimizer step.
+    pub fn schedule(mut self, schedule: LrSchedule) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// Clip gradients to this global L2 norm before each optimizer step.
+    pub fn grad_clip(mut self, max_norm: Option<f32>) -> Self {
+        self.grad_clip = max_norm;
+        self
+    }
+
+    /// Take one optimizer step per `micro_batches` batches, averaging their gradients.
+    ///
+    /// A trailing partial group at the end of an epoch still takes a step.
+    pub fn grad_accum(mut self, micro_batches: usize) -> Self {
+        self.grad_accum = micro_batches.max(1);
+        self
+    }
+
+    /// Keep a moving average of the weights, updated after every optimizer step.
+    pub fn ema(mut self, ema: Option<ModelEma<B, M>>) -> Self {
+        self.ema = ema;
+        self
+    }
+
+    /// Weights to validate each epoch (`Ema` falls back to raw without an EMA).
+    pub fn validate_weights(mut self, kind: WeightsKind) -> Self {
+        self.val_weights = kind;
+        self
+    }
+
+    /// Register a callback; callbacks run in registration order.
+    pub fn callback(mut self, callback: &'a mut dyn TrainCallback<B, M, O>) -> Self {
+        self.callbacks.push(callback);
+        self
+    }
+
+    /// Train for the remaining epochs and return the final weights.
+    pub fn fit(
+        self,
+        train: &dyn BatchSource<B>,
+        val: &[ValSplit<'_, B::InnerBackend>],
+        device: &B::Device,
+    ) -> anyhow::Result<Fitted<B, M>> {
+        let Self {
+            mut model,
+            mut optim,
+            loss,
+            mut validator,
+            mut ema,
+            val_weights,
+            schedule,
+            grad_clip,
+            grad_accum,
+            epochs,
+            start_epoch,
+            mut step,
+            mut callbacks,
+        } = self;
+
+        for epoch in start_epoch..epochs {
+            let mut losses = Vec::new();
+            let mut accumulator = GradientsAccumulator::<M>::new();
+            let mut pending = Vec::new();
+            let mut batches = train.epoch_batches(epoch, device).peekable();
+            while let Some(batch) = batches.next() {
+                let batch = batch?;
+                let output = model.forward_batch(&batch);
+                let loss_value = loss.loss(output, &batch);
+                pending.push(scalar(loss_value.clone().detach()));
+                let grads = GradientsParams::from_grads(loss_value.backward(), &model);
+                accumulator.accumulate(&model, grads);
+                if pending.len() < grad_accum && batches.peek().is_some() {
+                    continue;
+                }
+
+                let mut grads = accumulator.grads();
+                if pending.len() > 1 {
+                    scale_grads::<B, M>(&model, &mut grads, 1.0 / pending.len() as f32);
+                }
+                let norm = match grad_clip {
+                    Some(max_norm) => clip_grad_norm::<B, M>(&model, &mut grads, max_norm),
+                    None => grad_norm::<B, M>(&model, &grads),
+                };
+                let lr = schedule.lr_at(step);
+                model = optim.step(lr, model, grads);
+                if let Some(ema) = ema.as_mut() {
+                    ema.update(&model);
+                }
+                step += 1;
+                let loss_val = mean(&pending);
+                pending.clear();
+                losses.push(loss_val);
+
+                let info = StepInfo {
+                    epoch,
+                    step,
+                    lr,
+                    loss: loss_val,
+                    grad_norm: norm,
+                };
+                for callback in callbacks.iter_mut() {
+                    callback.on_step(&info)?;
+                }
+            }
+
+            let inner = select_weights(&model, ema.as_ref(), val_weights);
+            let mut val_metrics = Vec::new();
+            for split in val {
+                let metrics = validate(&inner, &loss, &mut validator, split, device)?;
+                val_metrics.extend(metrics);
+            }
+
+            let mut info = EpochInfo {
+                epoch,
+                step,
+                train_loss: mean(&losses),
+                val: val_metrics,
+                is_final: epoch + 1 == epochs,
+            };
+            let mut control = Control::Continue;
+            for callback in callbacks.iter_mut() {
+                if callback.on_epoch_end(&info, &model, &optim, ema.as_ref())? == Control::Stop {
+                    control = Control::Stop;
+                    info.is_final = true;
+                }
+            }
+            if control == Control::Stop {
+                break;
+            }
+        }
+        Ok(Fitted { model, ema })
+    }
+}
+
+/// Loss and scores of `model` over one validation split (`None` when the split is empty).
+fn validate<B, M, L, V>(
+    model: &M,
+    loss: &L,
+    validator: &mut V,
+    split: &ValSplit<'_, B>,
+    device: &B::Device,
+) -> anyhow::Result<Option<ValMetrics>>
+where
+    B: Backend,
+    M: TrainableModel<B>,
+    L: TrainLoss<B, M::Output>,
+    V: Validator<B, M::Output>,
+{
+    validator.reset();
+    let mut losses = Vec::new();
+    for batch in split.source.batches(device) {
+        let batch = batch?;
+        let output = model.forward_batch(&batch);
+        losses.push(scalar(loss.loss(output.clone(), &batch)));
+        validator.update(output, &batch);
+    }
+    let scores = validator.scores();
+    if scores.frames == 0 {
+        return Ok(None);
+    }
+    Ok(Some(ValMetrics {
+        name: split.name.clone(),
+        frames: scores.frames,
+        val_loss: mean(&losses),
+        precision: scores.precision,
+        recall: scores.recall,
+        map50: scores.map50,
+    }))
+}
+
+/// Per-frame GT box count, `[batch, 1]` (the linear classifier's regression target).
+fn box_presence<B: Backend>(batch: &CollatedBatch<B>) -> Tensor<B, 2> {
+    let mask = batch.box_mask.clone();
+    let frames = mask.dims()[0];
+    mask.sum_dim(1).reshape([frames, 1])
+}
+
+fn scalar<B: Backend>(value: Tensor<B, 1>) -> f32 {
+    value
+        .into_data()
+        .to_vec::<f32>()
+        .unwrap_or_default()
+        .into_iter()
+        .next()
+        .unwrap_or(0.0)
+}
+
+fn mean(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f32>() / values.len() as f32
+    }
+}
+
+fn ratio(num: usize, den: usize) -> f32 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f32 / den as f32
+    }
+}
@@ -1,29 +1,62 @@
 use burn::backend::Autodiff;
-use burn::module::Module;
-use burn::nn::loss::{MseLoss, Reduction};
-use burn::optim::{AdamConfig, GradientsParams, Optimizer};
-use burn::record::{BinFileRecorder, FullPrecisionSettings, RecorderError};
-use burn::tensor::{Tensor, TensorData};
-use burn_dataset::WarehouseLoaders;
-use std::path::Path;
-
+use burn::module::{AutodiffModule, Module};
+use burn::optim::{AdamConfig, Optimizer};
+use burn::record::{BinFileRecorder, FullPrecisionSettings};
+use burn::tensor::backend::Backend;
+use burn::tensor::Tensor;
+use burn_dataset::{DatasetConfig, TransformPipeline, WarehouseLoaders, WarehouseManifest};
+use data_contracts::RunManifest;
+use models::checkpoint::{self, CheckpointMetadata, ModelConfig};
+use models::input::MULTIBOX_INPUT_DIM;
+use std::path::{Path, PathBuf};
+
+use crate::config::write_resolved_config;
+use crate::early_stop::{EarlyStopping, MonitorMode, Plateau};
+use crate::ema::{EmaConfig, ModelEma, WeightsKind};
+use crate::loss::{BoxLossKind, ClassLossKind, LossConfig, ObjLossKind};
+use crate::matcher::{assign_greedy, targets_from_assignment, MatchCost, MatcherKind};
+use crate::metrics::{MetricsLog, MonitorMetric};
+use crate::optim::{LrSchedule, SchedulerKind};
+use crate::resume::{
+    resolve_resume_dir, restore, restore_ema, CheckpointPolicy, ResumeState, RunCheckpointer,
+};
+use crate::status::StatusReporter;
+use crate::trainer::{
+    BatchSource, BoxPresenceLoss, BoxValidator, FrameValidator, InnerOutput, MultiboxLoss,
+    SampleSource, TrainLoss, TrainableModel, Trainer, TransformedSource, ValSplit, Validator,
+    WarehouseSource, WarehouseSplit,
+};
 use crate::{
-    DatasetPathConfig, LinearClassifier, LinearClassifierConfig, MultiboxModel,
+    ConvBackboneConfig, DatasetPathConfig, LinearClassifier, LinearClassifierConfig, MultiboxModel,
     MultiboxModelConfig, TrainBackend,
 };
-use clap::{Parser, ValueEnum};
+use clap::{ArgAction, Parser, ValueEnum};
+use serde::Serialize;
 use std::fs;
 
+/// Load a `LinearClassifier`, rebuilding it from the checkpoint's metadata sidecar.
 pub fn load_linear_classifier_from_checkpoint<P: AsRef<Path>>(
     path: P,
     device: &<TrainBackend as burn::tensor::backend::Backend>::Device,
-) -> Result<LinearClassifier<TrainBackend>, RecorderError> {
+) -> anyhow::Result<LinearClassifier<TrainBackend>> {
+    let path = path.as_ref();
+    linear_classifier_from_metadata(path, CheckpointMetadata::load(path)?, device)
+}
+
+/// Load `LinearClassifier` weights from `path` into the architecture `meta` describes.
+pub fn linear_classifier_from_metadata(
+    path: &Path,
+    meta: CheckpointMetadata,
+    device: &<TrainBackend as burn::tensor::backend::Backend>::Device,
+) -> anyhow::Result<LinearClassifier<TrainBackend>> {
+    meta.expect_kind(checkpoint::ModelKind::LinearClassifier)?;
+    let ModelConfig::LinearClassifier(cfg) = meta.model else {
+        unreachable!("kind checked above");
+    };
     let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
-    LinearClassifier::<TrainBackend>::new(LinearClassifierConfig::default(), device).load_file(
-        path.as_ref(),
-        &recorder,
-        device,
-    )
+    LinearClassifier::<TrainBackend>::new(cfg, device)
+        .load_file(path, &recorder, device)
+        .map_err(|e| anyhow::anyhow!("failed to load checkpoint {}: {e}", path.display()))
 }
 
 #[derive(ValueEnum, Debug, Clone, Copy)]
@@ -38,35 +71,87 @@ pub enum BackendKind {
     Wgpu,
 }
 
-#[derive(ValueEnum, Debug, Clone, Copy)]
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TrainingInputSource {
     Warehouse,
     CaptureLogs,
 }
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Serialize)]
 #[command(
     name = "train",
-    about = "Train LinearClassifier/MultiboxModel (warehouse-first)"
+    about = "Train LinearClassifier/MultiboxModel (warehouse-first)",
+    args_override_self = true
 )]
 pub struct TrainArgs {
+    /// TOML/JSON experiment file (see `config`); flags on the command line override it.
+    #[arg(long)]
+    #[serde(skip)]
+    pub config: Option<String>,
     /// Model to train.
     #[arg(long, value_enum, default_value_t = ModelKind::Tiny)]
+    #[serde(serialize_with = "crate::config::value_enum")]
     pub model: ModelKind,
     /// Backend to use (ndarray or wgpu if enabled).
     #[arg(long, value_enum, default_value_t = BackendKind::NdArray)]
+    #[serde(serialize_with = "crate::config::value_enum")]
     pub backend: BackendKind,
     /// Maximum boxes per image (pads/truncates to this for training).
     #[arg(long, default_value_t = 64)]
     pub max_boxes: usize,
+    /// Conv backbone stages for the multibox model (0 = pooled-grid MLP without a backbone).
+    #[arg(long, default_value_t = 0)]
+    pub backbone_depth: usize,
+    /// Channel width of the first conv backbone stage (doubles per stage).
+    #[arg(long, default_value_t = 16)]
+    pub backbone_width: usize,
+    /// Object classes for the multibox model (above 1 adds a class head; class ids come from the
+    /// labels' `class_id`).
+    #[arg(long, default_value_t = 1)]
+    pub num_classes: usize,
     /// Loss weight for box regression.
     #[arg(long, default_value_t = 1.0)]
     pub lambda_box: f32,
     /// Loss weight for objectness.
     #[arg(long, default_value_t = 1.0)]
     pub lambda_obj: f32,
+    /// Loss weight for classification (multi-class models only).
+    #[arg(long, default_value_t = 1.0)]
+    pub lambda_cls: f32,
+    /// Box regression loss for the multibox model.
+    #[arg(long, value_enum, default_value_t = BoxLossKind::L1)]
+    #[serde(serialize_with = "crate::config::value_enum")]
+    pub box_loss: BoxLossKind,
+    /// Objectness loss for the multibox model (focal copes with mostly-empty slots).
+    #[arg(long, value_enum, default_value_t = ObjLossKind::Bce)]
+    #[serde(serialize_with = "crate::config::value_enum")]
+    pub obj_loss: ObjLossKind,
+    /// Classification loss on matched slots when `--num-classes` is above 1.
+    #[arg(long, value_enum, default_value_t = ClassLossKind::Softmax)]
+    #[serde(serialize_with = "crate::config::value_enum")]
+    pub class_loss: ClassLossKind,
+    /// Focal loss alpha (weight of positive slots).
+    #[arg(long, default_value_t = 0.25)]
+    pub focal_alpha: f32,
+    /// Focal loss gamma (focusing exponent).
+    #[arg(long, default_value_t = 2.0)]
+    pub focal_gamma: f32,
+    /// GT-to-slot matcher for multibox targets.
+    #[arg(long, value_enum, default_value_t = MatcherKind::Greedy)]
+    #[serde(serialize_with = "crate::config::value_enum")]
+    pub matcher: MatcherKind,
+    /// Hungarian matching cost weight for the L1 box distance.
+    #[arg(long, default_value_t = 5.0)]
+    pub match_cost_l1: f32,
+    /// Hungarian matching cost weight for (negative) GIoU.
+    #[arg(long, default_value_t = 2.0)]
+    pub match_cost_giou: f32,
+    /// Hungarian matching cost weight for (negative) predicted objectness.
+    #[arg(long, default_value_t = 1.0)]
+    pub match_cost_obj: f32,
     /// Training input source (warehouse by default).
     #[arg(long, value_enum, default_value_t = TrainingInputSource::Warehouse)]
+    #[serde(serialize_with = "crate::config::value_enum")]
     pub input_source: TrainingInputSource,
     /// Warehouse manifest path (used with --input-source warehouse).
     #[arg(long, default_value = "assets/warehouse/manifest.json")]
@@ -74,6 +159,10 @@ pub struct TrainArgs {
     /// Capture-log dataset root containing labels/ and images/.
     #[arg(long, default_value = "assets/datasets/captures_filtered")]
     pub dataset_root: String,
+    /// Capture-log root to train from; implies `--input-source capture-logs`.
+    #[arg(long, conflicts_with_all = ["input_source", "dataset_root"])]
+    #[serde(skip)]
+    pub input_root: Option<String>,
     /// Labels subdirectory relative to dataset root (capture-logs only).
     #[arg(long, default_value = "labels")]
     pub labels_subdir: String,
@@ -86,57 +175,289 @@ pub struct TrainArgs {
     /// Batch size.
     #[arg(long, default_value_t = 1)]
     pub batch_size: usize,
-    /// Learning rate.
-    #[arg(long, default_value_t = 1e-3)]
+    /// Skip the trailing partial training batch of each epoch.
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, require_equals = true,
+          default_missing_value = "true", default_value_t = false)]
+    pub drop_last: bool,
+    /// Learning rate (the base/peak rate when a `--scheduler` is set).
+    #[arg(long, alias = "lr-start", default_value_t = 1e-3)]
     pub lr: f32,
-    /// Objectness threshold (for future eval).
+    /// Learning-rate schedule applied after warmup.
+    #[arg(long, value_enum, default_value_t = SchedulerKind::Constant)]
+    #[serde(serialize_with = "crate::config::value_enum")]
+    pub scheduler: SchedulerKind,
+    /// Final learning rate for the cosine and one-cycle schedules.
+    #[arg(long, default_value_t = 0.0)]
+    pub lr_end: f32,
+    /// Linear warmup from 0 to `--lr` over this many optimizer steps.
+    #[arg(long, default_value_t = 0)]
+    pub warmup_steps: u64,
+    /// Epochs between decays for `--scheduler step`.
+    #[arg(long, default_value_t = 10)]
+    pub lr_step_epochs: usize,
+    /// Decay factor for `--scheduler step`.
+    #[arg(long, default_value_t = 0.1)]
+    pub lr_gamma: f32,
+    /// Fraction of the post-warmup steps spent ramping up for `--scheduler one-cycle`.
+    #[arg(long, default_value_t = 0.3)]
+    pub one_cycle_pct: f32,
+    /// Keep an exponential moving average of the weights.
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, require_equals = true,
+          default_missing_value = "true", default_value_t = false)]
+    pub ema: bool,
+    /// EMA decay per optimizer step.
+    #[arg(long, default_value_t = 0.999)]
+    pub ema_decay: f32,
+    /// Steps over which the EMA decay ramps up from 0 (0 uses `--ema-decay` immediately).
+    #[arg(long, default_value_t = 100)]
+    pub ema_warmup_steps: u64,
+    /// Weights validated each epoch and saved to `--checkpoint-out` (default: `ema` with `--ema`,
+    /// `raw` without; `ema` needs `--ema`).
+    #[arg(long, value_enum)]
+    #[serde(serialize_with = "crate::config::opt_value_enum")]
+    pub weights: Option<WeightsKind>,
+    /// Probability of mirroring a training frame left-to-right (capture-log input).
+    #[arg(long, default_value_t = 0.0)]
+    pub flip_prob: f32,
+    /// Probability of jittering a training frame's brightness and contrast (capture-log input).
+    #[arg(long, default_value_t = 0.0)]
+    pub color_jitter_prob: f32,
+    /// Maximum relative brightness/contrast change of a jittered frame.
+    #[arg(long, default_value_t = 0.1)]
+    pub color_jitter_strength: f32,
+    /// Clip gradients to this global L2 norm before each optimizer step.
+    #[arg(long)]
+    pub grad_clip: Option<f32>,
+    /// Average gradients over this many batches per optimizer step (effective batch size is
+    /// `--batch-size` x `--grad-accum`).
+    #[arg(long, default_value_t = 1)]
+    pub grad_accum: usize,
+    /// Fraction of the dataset held out for validation each epoch (0 disables validation).
+    #[arg(long, default_value_t = 0.0)]
+    pub val_ratio: f32,
+    /// Seed for the train/val split and augmentations (unshuffled when omitted; augmented runs
+    /// draw one and record it in `resolved.toml`).
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Real-image capture directory (labels/images laid out like `--dataset-root`) validated
+    /// each epoch as the `real` split, with the domain gap to `val` reported alongside.
+    #[arg(long)]
+    pub real_val_dir: Option<String>,
+    /// Objectness threshold for validation precision/recall.
     #[arg(long, default_value_t = 0.3)]
     pub infer_obj_thresh: f32,
-    /// IoU threshold (for future eval).
+    /// NMS IoU threshold applied to validation predictions.
     #[arg(long, default_value_t = 0.5)]
     pub infer_iou_thresh: f32,
     /// Checkpoint output path (defaults by model if not provided).
     #[arg(long)]
     pub checkpoint_out: Option<String>,
+    /// Per-epoch metrics log (JSON lines, appended).
+    #[arg(long, default_value = "logs/metrics.jsonl")]
+    pub metrics_out: String,
+    /// Live progress file (`data_contracts::TrainStatus` JSON, rewritten atomically each step).
+    #[arg(long)]
+    pub status_file: Option<String>,
+    /// Directory for periodic resumable checkpoints (default: `<checkpoint-out stem>_run/`).
+    #[arg(long)]
+    pub checkpoint_dir: Option<String>,
+    /// Save a resumable checkpoint every N epochs (0 disables; the last epoch is always saved).
+    #[arg(long, default_value_t = 1)]
+    pub save_every: usize,
+    /// Number of most recent resumable checkpoints to keep.
+    #[arg(long, default_value_t = 2)]
+    pub keep_last: usize,
+    /// Also keep the resumable checkpoint of the epoch `best.bin` came from (best by `--monitor`).
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, require_equals = true,
+          default_missing_value = "true", default_value_t = false)]
+    pub keep_best: bool,
+    /// Stop after this many epochs without improvement in `--monitor` (never when omitted).
+    #[arg(long)]
+    pub early_stop_patience: Option<usize>,
+    /// Validation metric for early stopping and `best.bin`.
+    #[arg(long, value_enum, default_value_t = MonitorMetric::ValLoss)]
+    #[serde(serialize_with = "crate::config::value_enum")]
+    pub monitor: MonitorMetric,
+    /// Direction in which `--monitor` improves (defaults to max for val_map50, min for val_loss).
+    #[arg(long, value_enum)]
+    #[serde(serialize_with = "crate::config::opt_value_enum")]
+    pub mode: Option<MonitorMode>,
+    /// Minimum change in `--monitor` that counts as an improvement.
+    #[arg(long, default_value_t = 0.0)]
+    pub early_stop_min_delta: f32,
+    /// Fail unless the warehouse manifest has this version (set by replaying a `resolved.toml`).
+    #[arg(long)]
+    #[serde(skip)]
+    pub expect_warehouse_version: Option<String>,
+    /// Resume from a resumable checkpoint (an `epoch_NNNN` dir, or a checkpoint dir for the latest).
+    #[arg(long)]
+    #[serde(skip)]
+    pub resume: Option<String>,
 }
 
-pub fn run_train(args: TrainArgs) -> anyhow::Result<()> {
+pub fn run_train(mut args: TrainArgs) -> anyhow::Result<()> {
     validate_backend_choice(args.backend)?;
+    if let Some(root) = args.input_root.take() {
+        args.input_source = TrainingInputSource::CaptureLogs;
+        args.dataset_root = root;
+    }
+    if args.seed.is_none() && augment_pipeline(&args).is_some() {
+        let seed = augment_seed(&args)?;
+        println!("augmentations without --seed: using seed {seed}");
+        args.seed = Some(seed);
+    }
+    if args.weights == Some(WeightsKind::Ema) && !args.ema {
+        anyhow::bail!("--weights ema needs --ema");
+    }
+    args.weights = Some(weights_kind(&args));
 
     let ckpt_path = args
         .checkpoint_out
-        .clone()
-        .unwrap_or_else(|| match args.model {
+        .get_or_insert_with(|| match args.model {
             ModelKind::Tiny => "checkpoints/linear_detector.bin".to_string(),
             ModelKind::Big => "checkpoints/convolutional_detector.bin".to_string(),
-        });
+        })
+        .clone();
 
     if let Some(parent) = Path::new(&ckpt_path).parent() {
         fs::create_dir_all(parent)?;
     }
 
+    let mut status = StatusReporter::new(
+        args.status_file.as_ref().map(PathBuf::from),
+        args.epochs,
+        args.lr as f64,
+        Path::new(&ckpt_path),
+    );
+    status.begin()?;
+    match train_and_save(&args, &ckpt_path, &mut status) {
+        Ok(()) => status.finish(),
+        Err(err) => {
+            // Report the training error even if the status file cannot be written.
+            if let Err(status_err) = status.fail(&err) {
+                eprintln!("failed to write training status: {status_err:#}");
+            }
+            Err(err)
+        }
+    }
+}
+
+fn train_and_save(
+    args: &TrainArgs,
+    ckpt_path: &str,
+    status: &mut StatusReporter,
+) -> anyhow::Result<()> {
+    check_monitor(args)?;
+    let manifest = match args.input_source {
+        TrainingInputSource::Warehouse => {
+            WarehouseManifest::load(Path::new(&args.warehouse_manifest)).ok()
+        }
+        TrainingInputSource::CaptureLogs => None,
+    };
+    let class_names = match &manifest {
+        Some(manifest) => manifest.class_names.clone(),
+        None => capture_class_names(Path::new(&args.dataset_root))?,
+    };
+    let class_names = checked_class_names(args, class_names)?;
+    let transform = manifest.as_ref().map(|manifest| manifest.transform.clone());
+    let warehouse_version = manifest.map(|manifest| manifest.version);
+    if let Some(expected) = &args.expect_warehouse_version {
+        if args.input_source == TrainingInputSource::Warehouse
+            && warehouse_version.as_ref() != Some(expected)
+        {
+            anyhow::bail!(
+                "config expects warehouse version {expected}, but {} has {}",
+                args.warehouse_manifest,
+                warehouse_version
+                    .as_deref()
+                    .unwrap_or("no readable manifest")
+            );
+        }
+    }
+    write_resolved_config(
+        &run_checkpoint_dir(args, ckpt_path),
+        args,
+        warehouse_version.as_deref(),
+    )?;
+    let mut metadata = checkpoint_metadata(args, warehouse_version);
+    metadata.class_names = class_names;
+    let mut checkpointer = RunCheckpointer::new(
+        run_checkpoint_dir(args, ckpt_path),
+        metadata.clone(),
+        checkpoint_policy(args),
+        args.seed,
+        args.epochs,
+    );
+    let mut early_stop = early_stopping(args, run_checkpoint_dir(args, ckpt_path), &metadata);
+
+    let batch_size = args.batch_size.max(1);
     match args.input_source {
         TrainingInputSource::Warehouse => {
+            if augment_pipeline(args).is_some() {
+                anyhow::bail!(
+                    "--flip-prob/--color-jitter-prob need capture-log input; warehouse shards are \
+                     preprocessed once by warehouse_etl"
+                );
+            }
             let manifest_path = Path::new(&args.warehouse_manifest);
-            let loaders = WarehouseLoaders::from_manifest_path(manifest_path, 0.0, None, false)
-                .map_err(|e| {
-                    anyhow::anyhow!(
-                        "failed to load warehouse manifest at {}: {e}",
-                        manifest_path.display()
-                    )
-                })?;
+            let loaders = WarehouseLoaders::from_manifest_path(
+                manifest_path,
+                args.val_ratio,
+                args.seed,
+                args.drop_last,
+            )
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "failed to load warehouse manifest at {}: {e}",
+                    manifest_path.display()
+                )
+            })?;
             if loaders.train_len() == 0 {
                 anyhow::bail!(
                     "warehouse manifest {} contains no training shards",
                     manifest_path.display()
                 );
             }
-            match args.model {
-                ModelKind::Tiny => train_linear_detector_warehouse(&args, &loaders, &ckpt_path)?,
-                ModelKind::Big => {
-                    train_convolutional_detector_warehouse(&args, &loaders, &ckpt_path)?
+            let train =
+                WarehouseSource::new(&loaders, WarehouseSplit::Train, batch_size, args.max_boxes);
+            let val =
+                WarehouseSource::new(&loaders, WarehouseSplit::Val, batch_size, args.max_boxes);
+            // Real captures get the warehouse's resize, so the domain gap reflects the images
+            // rather than a preprocessing difference.
+            let real_transformed = match (&args.real_val_dir, &transform) {
+                (Some(dir), Some(transform)) => {
+                    let source = TransformedSource::new(
+                        Path::new(dir),
+                        &args.labels_subdir,
+                        &args.images_subdir,
+                        transform,
+                        batch_size,
+                        args.max_boxes,
+                    )
+                    .map_err(|e| e.context(format!("failed to load --real-val-dir {dir}")))?;
+                    check_real_captures(dir, BatchSource::<TrainBackend>::len(&source))?;
+                    Some(source)
                 }
-            }
+                (Some(_), None) => anyhow::bail!(
+                    "--real-val-dir needs a readable warehouse manifest for its transform"
+                ),
+                (None, _) => None,
+            };
+            let real_val = real_transformed
+                .as_ref()
+                .map(|source| source as &dyn BatchSource<TrainBackend>);
+            train_model(
+                args,
+                RunContext {
+                    train: &train,
+                    val: &val,
+                    real_val,
+                    ckpt_path,
+                    status,
+                    checkpointer: &mut checkpointer,
+                    early_stop: &mut early_stop,
+                },
+            )?;
         }
         TrainingInputSource::CaptureLogs => {
             println!("training from capture logs (legacy path); prefer warehouse manifests");
@@ -150,358 +471,476 @@ pub fn run_train(args: TrainArgs) -> anyhow::Result<()> {
                 println!("No samples found under {}", cfg.root.display());
                 return Ok(());
             }
-            match args.model {
-                ModelKind::Tiny => train_linear_detector(&args, &samples, &ckpt_path)?,
-                ModelKind::Big => train_convolutional_detector(&args, &samples, &ckpt_path)?,
+            let (train, val) = crate::split_samples(samples, args.val_ratio, args.seed);
+            if train.is_empty() {
+                anyhow::bail!(
+                    "--val-ratio {} leaves no training samples under {}",
+                    args.val_ratio,
+                    cfg.root.display()
+                );
+            }
+            let mut train =
+                SampleSource::new(&train, batch_size, args.max_boxes).drop_last(args.drop_last);
+            if let Some(pipeline) = augment_pipeline(args) {
+                let seed = args.seed.expect("run_train sets a seed for augmented runs");
+                train = train.augment(pipeline, seed);
             }
+            let val = SampleSource::new(&val, batch_size, args.max_boxes);
+            let real_samples = match &args.real_val_dir {
+                Some(dir) => {
+                    let cfg = DatasetPathConfig {
+                        root: dir.into(),
+                        labels_subdir: args.labels_subdir.clone(),
+                        images_subdir: args.images_subdir.clone(),
+                    };
+                    let samples = cfg.load().map_err(|e| {
+                        anyhow::anyhow!("failed to load --real-val-dir {dir}: {e:#}")
+                    })?;
+                    check_real_captures(dir, samples.len())?;
+                    samples
+                }
+                None => Vec::new(),
+            };
+            let real = SampleSource::new(&real_samples, batch_size, args.max_boxes);
+            let real_val = args
+                .real_val_dir
+                .is_some()
+                .then_some(&real as &dyn BatchSource<TrainBackend>);
+            train_model(
+                args,
+                RunContext {
+                    train: &train,
+                    val: &val,
+                    real_val,
+                    ckpt_path,
+                    status,
+                    checkpointer: &mut checkpointer,
+                    early_stop: &mut early_stop,
+                },
+            )?;
         }
     }
 
+    metadata
+        .save(Path::new(ckpt_path))
+        .map_err(|e| anyhow::anyhow!("failed to write checkpoint metadata: {e}"))?;
     println!("Saved checkpoint to {}", ckpt_path);
+    if let Some(best) = early_stop.best() {
+        println!(
+            "Best {} {:.4} at epoch {} saved to {}",
+            best.monitor.as_str(),
+            best.value,
+            best.epoch,
+            early_stop.best_path().display()
+        );
+    }
     Ok(())
 }
 
 type ADBackend = Autodiff<TrainBackend>;
 
-fn train_linear_detector(
+/// Reject an empty `--real-val-dir` and log how many captures it holds.
+fn check_real_captures(dir: &str, captures: usize) -> anyhow::Result<()> {
+    if captures == 0 {
+        anyhow::bail!("--real-val-dir {dir} contains no labeled captures");
+    }
+    println!("real-image validation: {captures} captures from {dir}");
+    Ok(())
+}
+
+/// Class names from a capture-log root's `run_manifest.json`, if it has one.
+fn capture_class_names(root: &Path) -> anyhow::Result<Option<Vec<String>>> {
+    let path = root.join("run_manifest.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let manifest: RunManifest = serde_json::from_slice(&fs::read(&path)?)
+        .map_err(|e| anyhow::anyhow!("invalid run manifest {}: {e}", path.display()))?;
+    Ok(manifest.class_names)
+}
+
+/// Class names to store with the checkpoint: the data's table when it matches `--num-classes`.
+///
+/// Single-class training ignores a larger table (every class collapses into one); a multi-class
+/// run whose table has a different length is an error.
+fn checked_class_names(
     args: &TrainArgs,
-    samples: &[crate::RunSample],
-    ckpt_path: &str,
-) -> anyhow::Result<()> {
-    let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
-    let mut model = LinearClassifier::<ADBackend>::new(LinearClassifierConfig::default(), &device);
-    let mut optim = AdamConfig::new().init();
+    class_names: Option<Vec<String>>,
+) -> anyhow::Result<Option<Vec<String>>> {
+    let num_classes = args.num_classes.max(1);
+    let Some(names) = class_names else {
+        return Ok(None);
+    };
+    if names.len() == num_classes {
+        return Ok(Some(names));
+    }
+    if num_classes == 1 || !matches!(args.model, ModelKind::Big) {
+        return Ok(None);
+    }
+    anyhow::bail!(
+        "--num-classes {num_classes} does not match the {} classes named by the data ({})",
+        names.len(),
+        names.join(", ")
+    )
+}
 
-    let batch_size = args.batch_size.max(1);
-    let data = samples.to_vec();
-    for epoch in 0..args.epochs {
-        let mut losses = Vec::new();
-        for batch in data.chunks(batch_size) {
-            let batch = crate::collate::<ADBackend>(batch, args.max_boxes)?;
-            // Feature: take the first box (or zeros) as the input vector.
-            let boxes = batch.boxes.clone();
-            let first_box = boxes
-                .clone()
-                .slice([0..boxes.dims()[0], 0..1, 0..4])
-                .reshape([boxes.dims()[0], 4]);
-
-            // Target: 1.0 if any box present, else 0.0.
-            let mask = batch.box_mask.clone();
-            let has_box = mask.clone().sum_dim(1).reshape([mask.dims()[0], 1]);
-
-            let preds = model.forward(first_box);
-            let mse = MseLoss::new();
-            let loss = mse.forward(preds, has_box, Reduction::Mean);
-            let loss_detached = loss.clone().detach();
-            let grads = GradientsParams::from_grads(loss.backward(), &model);
-            model = optim.step(args.lr as f64, model, grads);
-
-            let loss_val: f32 = loss_detached
-                .into_data()
-                .to_vec::<f32>()
-                .unwrap_or_default()
-                .into_iter()
-                .next()
-                .unwrap_or(0.0);
-            losses.push(loss_val);
-        }
-        let avg_loss: f32 = if losses.is_empty() {
-            0.0
-        } else {
-            losses.iter().sum::<f32>() / losses.len() as f32
-        };
-        println!("epoch {epoch}: avg loss {avg_loss:.4}");
+/// Multibox architecture selected by the CLI flags.
+pub fn multibox_config(args: &TrainArgs) -> MultiboxModelConfig {
+    MultiboxModelConfig {
+        input_dim: Some(MULTIBOX_INPUT_DIM),
+        max_boxes: args.max_boxes,
+        backbone: (args.backbone_depth > 0).then(|| ConvBackboneConfig {
+            width: args.backbone_width,
+            depth: args.backbone_depth,
+            ..Default::default()
+        }),
+        num_classes: args.num_classes.max(1),
+        ..Default::default()
     }
+}
 
-    let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
-    model
-        .clone()
-        .save_file(Path::new(ckpt_path), &recorder)
-        .map_err(|e| anyhow::anyhow!("failed to save checkpoint: {e}"))?;
+/// Multibox loss selection from the CLI flags.
+pub fn loss_config(args: &TrainArgs) -> LossConfig {
+    LossConfig {
+        box_loss: args.box_loss,
+        obj_loss: args.obj_loss,
+        class_loss: args.class_loss,
+        focal_alpha: args.focal_alpha,
+        focal_gamma: args.focal_gamma,
+    }
+}
 
-    Ok(())
+/// Hungarian matching cost weights selected by the CLI flags.
+pub fn match_cost(args: &TrainArgs) -> MatchCost {
+    MatchCost {
+        l1: args.match_cost_l1,
+        giou: args.match_cost_giou,
+        obj: args.match_cost_obj,
+    }
 }
 
-fn train_linear_detector_warehouse(
-    args: &TrainArgs,
-    loaders: &WarehouseLoaders,
-    ckpt_path: &str,
-) -> anyhow::Result<()> {
-    let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
-    let mut model = LinearClassifier::<ADBackend>::new(LinearClassifierConfig::default(), &device);
-    let mut optim = AdamConfig::new().init();
+/// Multibox training objective (matcher, losses, weights) selected by the CLI flags.
+pub fn multibox_loss(args: &TrainArgs) -> MultiboxLoss {
+    MultiboxLoss {
+        matcher: args.matcher,
+        cost: match_cost(args),
+        loss: loss_config(args),
+        lambda_box: args.lambda_box,
+        lambda_obj: args.lambda_obj,
+        lambda_cls: args.lambda_cls,
+    }
+}
 
-    let batch_size = args.batch_size.max(1);
-    for epoch in 0..args.epochs {
-        let mut losses = Vec::new();
-        let mut iter = loaders.train_iter();
-        loop {
-            let batch = match iter.next_batch::<ADBackend>(batch_size, &device)? {
-                Some(batch) => batch,
-                None => break,
-            };
-            let batch = crate::collate_from_burn_batch::<ADBackend>(batch, args.max_boxes)?;
-
-            // Feature: take the first box (or zeros) as the input vector.
-            let boxes = batch.boxes.clone();
-            let first_box = boxes
-                .clone()
-                .slice([0..boxes.dims()[0], 0..1, 0..4])
-                .reshape([boxes.dims()[0], 4]);
-
-            // Target: 1.0 if any box present, else 0.0.
-            let mask = batch.box_mask.clone();
-            let has_box = mask.clone().sum_dim(1).reshape([mask.dims()[0], 1]);
-
-            let preds = model.forward(first_box);
-            let mse = MseLoss::new();
-            let loss = mse.forward(preds, has_box, Reduction::Mean);
-            let loss_detached = loss.clone().detach();
-            let grads = GradientsParams::from_grads(loss.backward(), &model);
-            model = optim.step(args.lr as f64, model, grads);
-
-            let loss_val: f32 = loss_detached
-                .into_data()
-                .to_vec::<f32>()
-                .unwrap_or_default()
-                .into_iter()
-                .next()
-                .unwrap_or(0.0);
-            losses.push(loss_val);
-        }
-        let avg_loss: f32 = if losses.is_empty() {
-            0.0
-        } else {
-            losses.iter().sum::<f32>() / losses.len() as f32
-        };
-        println!("epoch {epoch}: avg loss {avg_loss:.4}");
+/// Per-sample training augmentations selected by the CLI flags, as a `burn_dataset` pipeline
+/// (`None` when every knob is off).
+pub fn augment_pipeline(args: &TrainArgs) -> Option<TransformPipeline> {
+    let enabled =
+        args.flip_prob > 0.0 || (args.color_jitter_prob > 0.0 && args.color_jitter_strength > 0.0);
+    enabled.then(|| {
+        TransformPipeline::from_config(&DatasetConfig {
+            // Frames keep their size; only the augmentation knobs are set.
+            target_size: None,
+            flip_horizontal_prob: args.flip_prob,
+            color_jitter_prob: args.color_jitter_prob,
+            color_jitter_strength: args.color_jitter_strength,
+            scale_jitter_prob: 0.0,
+            noise_prob: 0.0,
+            blur_prob: 0.0,
+            max_boxes: args.max_boxes,
+            ..DatasetConfig::default()
+        })
+    })
+}
+
+/// Seed for an augmented run given without `--seed`: the resumed checkpoint's, or a fresh one.
+fn augment_seed(args: &TrainArgs) -> anyhow::Result<u64> {
+    let resumed = match &args.resume {
+        Some(dir) => ResumeState::load(&resolve_resume_dir(Path::new(dir))?)?.seed,
+        None => None,
+    };
+    // TOML integers are signed, so keep a drawn seed writable to `resolved.toml`.
+    Ok(resumed.unwrap_or_else(|| rand::random::<u64>() >> 1))
+}
+
+/// EMA decay and warmup selected by the CLI flags.
+pub fn ema_config(args: &TrainArgs) -> EmaConfig {
+    EmaConfig {
+        decay: args.ema_decay,
+        warmup_steps: args.ema_warmup_steps,
     }
+}
 
-    let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
-    model
-        .clone()
-        .save_file(Path::new(ckpt_path), &recorder)
-        .map_err(|e| anyhow::anyhow!("failed to save checkpoint: {e}"))?;
+/// The `--weights` selection, defaulting to the EMA when one is kept.
+pub fn weights_kind(args: &TrainArgs) -> WeightsKind {
+    match (args.weights, args.ema) {
+        (Some(kind), _) => kind,
+        (None, true) => WeightsKind::Ema,
+        (None, false) => WeightsKind::Raw,
+    }
+}
 
+/// Reject `--monitor`/`--early-stop-patience` settings under which no epoch is ever scored,
+/// so patience and `best.bin` would silently do nothing.
+fn check_monitor(args: &TrainArgs) -> anyhow::Result<()> {
+    if args.monitor == MonitorMetric::ValMap50 && matches!(args.model, ModelKind::Tiny) {
+        anyhow::bail!(
+            "--monitor val_map50 needs --model big; the linear classifier reports no mAP"
+        );
+    }
+    if args.early_stop_patience.is_some() && args.val_ratio <= 0.0 && args.real_val_dir.is_none() {
+        anyhow::bail!(
+            "--early-stop-patience needs a validation split (--val-ratio > 0 or --real-val-dir)"
+        );
+    }
     Ok(())
 }
 
-fn train_convolutional_detector(
+/// `best.bin`/`last.bin` writer and early stopping selected by the CLI flags.
+pub fn early_stopping(
     args: &TrainArgs,
-    samples: &[crate::RunSample],
-    ckpt_path: &str,
-) -> anyhow::Result<()> {
-    let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
-    let mut model = MultiboxModel::<ADBackend>::new(
-        MultiboxModelConfig {
-            input_dim: Some(4 + 8), // first box (4) + features (8)
-            max_boxes: args.max_boxes,
-            ..Default::default()
-        },
-        &device,
-    );
-    let mut optim = AdamConfig::new().init();
+    dir: PathBuf,
+    metadata: &CheckpointMetadata,
+) -> EarlyStopping {
+    let mode = args
+        .mode
+        .unwrap_or_else(|| MonitorMode::for_metric(args.monitor));
+    EarlyStopping::new(
+        dir,
+        metadata.clone(),
+        weights_kind(args),
+        args.monitor,
+        Plateau::new(mode, args.early_stop_min_delta),
+        args.early_stop_patience,
+    )
+}
 
-    let batch_size = args.batch_size.max(1);
-    let data = samples.to_vec();
-    for epoch in 0..args.epochs {
-        let mut losses = Vec::new();
-        for batch in data.chunks(batch_size) {
-            let batch = crate::collate::<ADBackend>(batch, args.max_boxes)?;
-            // Features: first box (or zeros) + pooled image features.
-            let boxes = batch.boxes.clone();
-            let first_box = boxes
-                .clone()
-                .slice([0..boxes.dims()[0], 0..1, 0..4])
-                .reshape([boxes.dims()[0], 4]);
-            let features = batch.features.clone();
-            let input = burn::tensor::Tensor::cat(vec![first_box, features], 1);
-
-            let (pred_boxes, pred_scores) = model.forward_multibox(input);
-
-            // Targets
-            let gt_boxes = batch.boxes.clone();
-            let gt_mask = batch.box_mask.clone();
-
-            // Greedy matching per GT: for each GT box, pick best pred by IoU.
-            let (obj_targets, box_targets, box_weights) =
-                build_greedy_targets(pred_boxes.clone(), gt_boxes.clone(), gt_mask.clone());
-            // Greedy IoU matcher is deterministic/cheap; swap to Hungarian if finer matching is needed later.
-
-            // Objectness loss (BCE) with targets; unassigned preds stay at 0.0.
-            let eps = 1e-6;
-            let pred_scores_clamped = pred_scores.clamp(eps, 1.0 - eps);
-            let obj_targets_inv =
-                Tensor::<ADBackend, 2>::ones(obj_targets.dims(), &obj_targets.device())
-                    - obj_targets.clone();
-            let obj_loss = -((obj_targets.clone() * pred_scores_clamped.clone().log())
-                + (obj_targets_inv
-                    * (Tensor::<ADBackend, 2>::ones(
-                        pred_scores_clamped.dims(),
-                        &pred_scores_clamped.device(),
-                    ) - pred_scores_clamped)
-                        .log()))
-            .sum()
-            .div_scalar((obj_targets.dims()[0] * obj_targets.dims()[1]) as f32);
-
-            // Box regression loss on matched preds only.
-            let box_err = (pred_boxes - box_targets.clone()).abs() * box_weights.clone();
-            let matched = box_weights.clone().sum().div_scalar(4.0);
-            let matched_scalar = matched
-                .into_data()
-                .to_vec::<f32>()
-                .unwrap_or_default()
-                .first()
-                .copied()
-                .unwrap_or(0.0);
-            let box_loss = if matched_scalar > 0.0 {
-                box_err.sum().div_scalar(matched_scalar)
-            } else {
-                // Return a zero scalar in the same tensor rank as div output (rank 1).
-                let zeros = vec![0.0f32; 1];
-                Tensor::<ADBackend, 1>::from_data(
-                    TensorData::new(zeros, [1]),
-                    &box_weights.device(),
-                )
-            };
+/// Periodic checkpoint schedule and retention selected by the CLI flags.
+pub fn checkpoint_policy(args: &TrainArgs) -> CheckpointPolicy {
+    CheckpointPolicy {
+        save_every: args.save_every,
+        keep_last: args.keep_last,
+        keep_best: args.keep_best,
+    }
+}
 
-            let loss = box_loss * args.lambda_box + obj_loss * args.lambda_obj;
-            let loss_detached = loss.clone().detach();
-            let grads = GradientsParams::from_grads(loss.backward(), &model);
-            model = optim.step(args.lr as f64, model, grads);
-
-            let loss_val: f32 = loss_detached
-                .into_data()
-                .to_vec::<f32>()
-                .unwrap_or_default()
-                .into_iter()
-                .next()
-                .unwrap_or(0.0);
-            losses.push(loss_val);
-        }
-        let avg_loss: f32 = if losses.is_empty() {
-            0.0
-        } else {
-            losses.iter().sum::<f32>() / losses.len() as f32
-        };
-        println!("epoch {epoch}: avg loss {avg_loss:.4}");
+/// Sidecar describing the checkpoint `run_train` writes for `args`.
+pub fn checkpoint_metadata(
+    args: &TrainArgs,
+    warehouse_version: Option<String>,
+) -> CheckpointMetadata {
+    let model = match args.model {
+        ModelKind::Tiny => ModelConfig::LinearClassifier(LinearClassifierConfig::default()),
+        ModelKind::Big => ModelConfig::Multibox(multibox_config(args)),
+    };
+    CheckpointMetadata::new(
+        model,
+        warehouse_version,
+        WarehouseManifest::resolve_code_version(),
+    )
+}
+
+/// Data sources and run outputs shared by every model.
+struct RunContext<'a> {
+    train: &'a dyn BatchSource<ADBackend>,
+    val: &'a dyn BatchSource<TrainBackend>,
+    /// Real captures from `--real-val-dir`.
+    real_val: Option<&'a dyn BatchSource<TrainBackend>>,
+    ckpt_path: &'a str,
+    status: &'a mut StatusReporter,
+    checkpointer: &'a mut RunCheckpointer,
+    early_stop: &'a mut EarlyStopping,
+}
+
+/// Build the model selected by `--model` and train it.
+fn train_model(args: &TrainArgs, ctx: RunContext<'_>) -> anyhow::Result<()> {
+    let device = <ADBackend as Backend>::Device::default();
+    ctx.status
+        .set_total_steps(steps_per_epoch(args, ctx.train.len()) * args.epochs as u64);
+    match args.model {
+        ModelKind::Tiny => fit(
+            args,
+            LinearClassifier::<ADBackend>::new(LinearClassifierConfig::default(), &device),
+            BoxPresenceLoss,
+            FrameValidator::new(args.infer_obj_thresh),
+            ctx,
+        ),
+        ModelKind::Big => fit(
+            args,
+            MultiboxModel::<ADBackend>::new(multibox_config(args), &device),
+            multibox_loss(args),
+            BoxValidator::new(args.infer_obj_thresh, args.infer_iou_thresh),
+            ctx,
+        ),
+    }
+}
+
+/// Run the shared `Trainer` loop for `model` and save the final weights.
+fn fit<M, L, V>(
+    args: &TrainArgs,
+    model: M,
+    loss: L,
+    validator: V,
+    ctx: RunContext<'_>,
+) -> anyhow::Result<()>
+where
+    M: TrainableModel<ADBackend> + AutodiffModule<ADBackend>,
+    M::InnerModule: TrainableModel<TrainBackend>,
+    L: TrainLoss<ADBackend, M::Output> + TrainLoss<TrainBackend, InnerOutput<ADBackend, M>>,
+    V: Validator<TrainBackend, InnerOutput<ADBackend, M>>,
+{
+    let device = <ADBackend as Backend>::Device::default();
+    let optim = AdamConfig::new().init();
+    let start = start_state(args, model, optim, &device, ctx.status)?;
+    if args.resume.is_some() {
+        ctx.early_stop.resume(start.epoch)?;
+    } else {
+        ctx.early_stop.discard_best_score()?;
+    }
+
+    let mut metrics_log = MetricsLog::new(&args.metrics_out);
+    // Early stopping and best-checkpoint selection follow the first split with frames: `val`,
+    // or `real` when there is no synthetic hold-out.
+    let mut val = vec![ValSplit {
+        name: "val".to_string(),
+        source: ctx.val,
+    }];
+    if let Some(real) = ctx.real_val {
+        val.push(ValSplit {
+            name: "real".to_string(),
+            source: real,
+        });
+        metrics_log = metrics_log.domain_gap("val", "real");
     }
+    let schedule = lr_schedule(args, steps_per_epoch(args, ctx.train.len()));
+    let fitted = Trainer::new(
+        start.model,
+        start.optim,
+        loss,
+        validator,
+        args.epochs,
+        args.lr as f64,
+    )
+    .schedule(schedule)
+    .grad_clip(args.grad_clip)
+    .grad_accum(args.grad_accum)
+    .ema(start.ema)
+    .validate_weights(weights_kind(args))
+    .resume_at(start.epoch, start.step)
+    .callback(ctx.status)
+    .callback(&mut metrics_log)
+    // Early stopping first, so retention already sees this epoch's best and the checkpointer
+    // saves the epoch it stops on.
+    .callback(ctx.early_stop)
+    .callback(ctx.checkpointer)
+    .fit(ctx.train, &val, &device)?;
 
     let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
-    model
-        .clone()
-        .save_file(Path::new(ckpt_path), &recorder)
+    fitted
+        .weights(weights_kind(args))
+        .save_file(Path::new(ctx.ckpt_path), &recorder)
         .map_err(|e| anyhow::anyhow!("failed to save checkpoint: {e}"))?;
-
     Ok(())
 }
 
-fn train_convolutional_detector_warehouse(
+/// Directory for periodic checkpoints: `--checkpoint-dir`, or `<checkpoint-out stem>_run/`.
+pub fn run_checkpoint_dir(args: &TrainArgs, ckpt_path: &str) -> PathBuf {
+    match &args.checkpoint_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let ckpt = Path::new(ckpt_path);
+            let stem = ckpt
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "checkpoint".to_string());
+            ckpt.with_file_name(format!("{stem}_run"))
+        }
+    }
+}
+
+/// Fresh `model`/`optim` starting at epoch 0, or the `--resume` checkpoint's state.
+fn start_state<M, O>(
     args: &TrainArgs,
-    loaders: &WarehouseLoaders,
-    ckpt_path: &str,
-) -> anyhow::Result<()> {
-    let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
-    let mut model = MultiboxModel::<ADBackend>::new(
-        MultiboxModelConfig {
-            input_dim: Some(4 + 8), // first box (4) + features (8)
-            max_boxes: args.max_boxes,
-            ..Default::default()
-        },
-        &device,
+    model: M,
+    optim: O,
+    device: &<ADBackend as Backend>::Device,
+    status: &mut StatusReporter,
+) -> anyhow::Result<StartState<M, O>>
+where
+    M: AutodiffModule<ADBackend>,
+    O: Optimizer<M, ADBackend>,
+{
+    let ema_config = args.ema.then(|| ema_config(args));
+    let Some(resume) = &args.resume else {
+        return Ok(StartState {
+            ema: ema_config.map(|config| ModelEma::new(&model, config)),
+            model,
+            optim,
+            epoch: 0,
+            step: 0,
+        });
+    };
+    let dir = resolve_resume_dir(Path::new(resume))?;
+    let resumed = restore::<ADBackend, _, _>(
+        &dir,
+        model,
+        optim,
+        &checkpoint_metadata(args, None),
+        args.seed,
+        device,
+    )?;
+    let state = resumed.state;
+    println!(
+        "resuming from {} at epoch {} (step {})",
+        dir.display(),
+        state.next_epoch,
+        state.step
     );
-    let mut optim = AdamConfig::new().init();
+    status.resume_at(state.next_epoch, state.step);
+    let ema = match ema_config {
+        Some(config) => Some(restore_ema(&dir, &resumed.model, config, &state, device)?),
+        None => None,
+    };
+    Ok(StartState {
+        model: resumed.model,
+        optim: resumed.optim,
+        ema,
+        epoch: state.next_epoch,
+        step: state.step,
+    })
+}
+
+/// Where a run starts: fresh weights, or the state restored by `--resume`.
+struct StartState<M: AutodiffModule<ADBackend>, O> {
+    model: M,
+    optim: O,
+    ema: Option<ModelEma<ADBackend, M>>,
+    epoch: usize,
+    step: u64,
+}
 
+/// Optimizer steps per epoch over `samples` training samples.
+fn steps_per_epoch(args: &TrainArgs, samples: usize) -> u64 {
     let batch_size = args.batch_size.max(1);
-    for epoch in 0..args.epochs {
-        let mut losses = Vec::new();
-        let mut iter = loaders.train_iter();
-        loop {
-            let batch = match iter.next_batch::<ADBackend>(batch_size, &device)? {
-                Some(batch) => batch,
-                None => break,
-            };
-            let batch = crate::collate_from_burn_batch::<ADBackend>(batch, args.max_boxes)?;
-
-            let boxes = batch.boxes.clone();
-            let first_box = boxes
-                .clone()
-                .slice([0..boxes.dims()[0], 0..1, 0..4])
-                .reshape([boxes.dims()[0], 4]);
-            let features = batch.features.clone();
-            let input = burn::tensor::Tensor::cat(vec![first_box, features], 1);
-
-            let (pred_boxes, pred_scores) = model.forward_multibox(input);
-
-            let gt_boxes = batch.boxes.clone();
-            let gt_mask = batch.box_mask.clone();
-
-            let (obj_targets, box_targets, box_weights) =
-                build_greedy_targets(pred_boxes.clone(), gt_boxes.clone(), gt_mask.clone());
-
-            let eps = 1e-6;
-            let pred_scores_clamped = pred_scores.clamp(eps, 1.0 - eps);
-            let obj_targets_inv =
-                Tensor::<ADBackend, 2>::ones(obj_targets.dims(), &obj_targets.device())
-                    - obj_targets.clone();
-            let obj_loss = -((obj_targets.clone() * pred_scores_clamped.clone().log())
-                + (obj_targets_inv
-                    * (Tensor::<ADBackend, 2>::ones(
-                        pred_scores_clamped.dims(),
-                        &pred_scores_clamped.device(),
-                    ) - pred_scores_clamped)
-                        .log()))
-            .sum()
-            .div_scalar((obj_targets.dims()[0] * obj_targets.dims()[1]) as f32);
-
-            let box_err = (pred_boxes - box_targets.clone()).abs() * box_weights.clone();
-            let matched = box_weights.clone().sum().div_scalar(4.0);
-            let matched_scalar = matched
-                .into_data()
-                .to_vec::<f32>()
-                .unwrap_or_default()
-                .first()
-                .copied()
-                .unwrap_or(0.0);
-            let box_loss = if matched_scalar > 0.0 {
-                box_err.sum().div_scalar(matched_scalar)
-            } else {
-                let zeros = vec![0.0f32; 1];
-                Tensor::<ADBackend, 1>::from_data(
-                    TensorData::new(zeros, [1]),
-                    &box_weights.device(),
-                )
-            };
+    let batches = if args.drop_last {
+        samples / batch_size
+    } else {
+        samples.div_ceil(batch_size)
+    };
+    batches.div_ceil(args.grad_accum.max(1)) as u64
+}
 
-            let loss = box_loss * args.lambda_box + obj_loss * args.lambda_obj;
-            let loss_detached = loss.clone().detach();
-            let grads = GradientsParams::from_grads(loss.backward(), &model);
-            model = optim.step(args.lr as f64, model, grads);
-
-            let loss_val: f32 = loss_detached
-                .into_data()
-                .to_vec::<f32>()
-                .unwrap_or_default()
-                .into_iter()
-                .next()
-                .unwrap_or(0.0);
-            losses.push(loss_val);
-        }
-        let avg_loss: f32 = if losses.is_empty() {
-            0.0
-        } else {
-            losses.iter().sum::<f32>() / losses.len() as f32
-        };
-        println!("epoch {epoch}: avg loss {avg_loss:.4}");
+/// Learning-rate schedule selected by the CLI flags for a run of `steps_per_epoch` x `--epochs`.
+pub fn lr_schedule(args: &TrainArgs, steps_per_epoch: u64) -> LrSchedule {
+    LrSchedule {
+        kind: args.scheduler,
+        lr_start: args.lr as f64,
+        lr_end: args.lr_end as f64,
+        warmup_steps: args.warmup_steps,
+        total_steps: steps_per_epoch * args.epochs as u64,
+        step_size: steps_per_epoch * args.lr_step_epochs.max(1) as u64,
+        gamma: args.lr_gamma as f64,
+        pct_start: args.one_cycle_pct as f64,
     }
-
-    let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
-    model
-        .clone()
-        .save_file(Path::new(ckpt_path), &recorder)
-        .map_err(|e| anyhow::anyhow!("failed to save checkpoint: {e}"))?;
-
-    Ok(())
 }
 
 pub fn validate_backend_choice(kind: BackendKind) -> anyhow::Result<()> {
@@ -518,124 +957,38 @@ pub fn validate_backend_choice(kind: BackendKind) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn iou_xyxy(a: [f32; 4], b: [f32; 4]) -> f32 {
-    let ax0 = a[0].min(a[2]);
-    let ay0 = a[1].min(a[3]);
-    let ax1 = a[0].max(a[2]);
-    let ay1 = a[1].max(a[3]);
-    let bx0 = b[0].min(b[2]);
-    let by0 = b[1].min(b[3]);
-    let bx1 = b[0].max(b[2]);
-    let by1 = b[1].max(b[3]);
-
-    let inter_x0 = ax0.max(bx0);
-    let inter_y0 = ay0.max(by0);
-    let inter_x1 = ax1.min(bx1);
-    let inter_y1 = ay1.min(by1);
-
-    let inter_w = (inter_x1 - inter_x0).max(0.0);
-    let inter_h = (inter_y1 - inter_y0).max(0.0);
-    let inter_area = inter_w * inter_h;
-
-    let area_a = (ax1 - ax0).max(0.0) * (ay1 - ay0).max(0.0);
-    let area_b = (bx1 - bx0).max(0.0) * (by1 - by0).max(0.0);
-    let denom = area_a + area_b - inter_area;
-    if denom <= 0.0 {
-        0.0
-    } else {
-        inter_area / denom
-    }
-}
+/// Load a `MultiboxModel`, rebuilding its architecture from the checkpoint's metadata sidecar.
 pub fn load_multibox_model_from_checkpoint<P: AsRef<Path>>(
     path: P,
     device: &<TrainBackend as burn::tensor::backend::Backend>::Device,
-    max_boxes: usize,
-) -> Result<MultiboxModel<TrainBackend>, RecorderError> {
+) -> anyhow::Result<MultiboxModel<TrainBackend>> {
+    let path = path.as_ref();
+    multibox_model_from_metadata(path, CheckpointMetadata::load(path)?, device)
+}
+
+/// Load `MultiboxModel` weights from `path` into the architecture `meta` describes.
+pub fn multibox_model_from_metadata(
+    path: &Path,
+    meta: CheckpointMetadata,
+    device: &<TrainBackend as burn::tensor::backend::Backend>::Device,
+) -> anyhow::Result<MultiboxModel<TrainBackend>> {
+    meta.expect_kind(checkpoint::ModelKind::Multibox)?;
+    let ModelConfig::Multibox(cfg) = meta.model else {
+        unreachable!("kind checked above");
+    };
     let recorder = BinFileRecorder::<FullPrecisionSettings>::new();
-    MultiboxModel::<TrainBackend>::new(
-        MultiboxModelConfig {
-            max_boxes,
-            input_dim: Some(4 + 8),
-            ..Default::default()
-        },
-        device,
-    )
-    .load_file(path.as_ref(), &recorder, device)
+    MultiboxModel::<TrainBackend>::new(cfg, device)
+        .load_file(path, &recorder, device)
+        .map_err(|e| anyhow::anyhow!("failed to load checkpoint {}: {e}", path.display()))
 }
 
+/// Greedy best-IoU target assignment; GTs may share a slot (see `matcher` for the alternative).
 pub fn build_greedy_targets<B: burn::tensor::backend::Backend>(
     pred_boxes: Tensor<B, 3>,
     gt_boxes: Tensor<B, 3>,
     gt_mask: Tensor<B, 2>,
 ) -> (Tensor<B, 2>, Tensor<B, 3>, Tensor<B, 3>) {
-    let batch = pred_boxes.dims()[0];
     let max_pred = pred_boxes.dims()[1];
-    let max_gt = gt_boxes.dims()[1];
-
-    let gt_mask_vec = gt_mask
-        .clone()
-        .into_data()
-        .to_vec::<f32>()
-        .unwrap_or_default();
-    let gt_boxes_vec = gt_boxes
-        .clone()
-        .into_data()
-        .to_vec::<f32>()
-        .unwrap_or_default();
-    let pred_boxes_vec = pred_boxes
-        .clone()
-        .into_data()
-        .to_vec::<f32>()
-        .unwrap_or_default();
-
-    let mut obj_targets = vec![0.0f32; batch * max_pred];
-    let mut box_targets = vec![0.0f32; batch * max_pred * 4];
-    let mut box_weights = vec![0.0f32; batch * max_pred * 4];
-
-    for b in 0..batch {
-        for g in 0..max_gt {
-            let mask_idx = b * max_gt + g;
-            if gt_mask_vec.get(mask_idx).copied().unwrap_or(0.0) < 0.5 {
-                continue;
-            }
-            let gb = [
-                gt_boxes_vec[(b * max_gt + g) * 4],
-                gt_boxes_vec[(b * max_gt + g) * 4 + 1],
-                gt_boxes_vec[(b * max_gt + g) * 4 + 2],
-                gt_boxes_vec[(b * max_gt + g) * 4 + 3],
-            ];
-
-            let mut best_iou = -1.0f32;
-            let mut best_p = 0usize;
-            for p in 0..max_pred {
-                let pb = [
-                    pred_boxes_vec[(b * max_pred + p) * 4],
-                    pred_boxes_vec[(b * max_pred + p) * 4 + 1],
-                    pred_boxes_vec[(b * max_pred + p) * 4 + 2],
-                    pred_boxes_vec[(b * max_pred + p) * 4 + 3],
-                ];
-                let iou = iou_xyxy(pb, gb);
-                if iou > best_iou {
-                    best_iou = iou;
-                    best_p = p;
-                }
-            }
-
-            let obj_idx = b * max_pred + best_p;
-            obj_targets[obj_idx] = 1.0;
-            let bt_base = (b * max_pred + best_p) * 4;
-            box_targets[bt_base..bt_base + 4].copy_from_slice(&gb);
-            box_weights[bt_base..bt_base + 4].copy_from_slice(&[1.0, 1.0, 1.0, 1.0]);
-        }
-    }
-
-    let device = &B::Device::default();
-    let obj_targets =
-        Tensor::<B, 2>::from_data(TensorData::new(obj_targets, [batch, max_pred]), device);
-    let box_targets =
-        Tensor::<B, 3>::from_data(TensorData::new(box_targets, [batch, max_pred, 4]), device);
-    let box_weights =
-        Tensor::<B, 3>::from_data(TensorData::new(box_weights, [batch, max_pred, 4]), device);
-
-    (obj_targets, box_targets, box_weights)
+    let assignment = assign_greedy(pred_boxes, gt_boxes.clone(), gt_mask);
+    targets_from_assignment(&assignment, max_pred, gt_boxes)
 }
@@ -0,0 +1,82 @@
+mod common;
+
+use burn_dataset::{DatasetConfig, TransformPipeline};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use training::trainer::{BatchSource, SampleSource};
+use training::{collate, collate_augmented, TrainBackend};
+
+fn values<const D: usize>(t: burn::tensor::Tensor<TrainBackend, D>) -> Vec<f32> {
+    t.into_data().to_vec::<f32>().unwrap()
+}
+
+#[test]
+fn collate_augmented_applies_the_pipeline_per_sample() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 2);
+    let samples = common::load_samples(tmp.path());
+    let flip = TransformPipeline::from_config(&DatasetConfig {
+        target_size: None,
+        flip_horizontal_prob: 1.0,
+        ..DatasetConfig::default()
+    });
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let plain = collate::<TrainBackend>(&samples, 2).unwrap();
+    let flipped = collate_augmented::<TrainBackend>(&samples, 2, &flip, &mut rng).unwrap();
+    // [0.2, 0.2, 0.6, 0.7] mirrors to [0.4, 0.2, 0.8, 0.7]; padding stays zero.
+    let boxes = values(flipped.boxes);
+    for (actual, expected) in boxes[..8]
+        .iter()
+        .zip([0.4, 0.2, 0.8, 0.7, 0.0, 0.0, 0.0, 0.0])
+    {
+        assert!((actual - expected).abs() < 1e-6, "{boxes:?}");
+    }
+    assert_eq!(values(flipped.box_mask), values(plain.box_mask));
+    // Solid frames look the same mirrored, so the stat features match too.
+    assert_eq!(values(flipped.features), values(plain.features));
+}
+
+#[test]
+fn disabled_pipeline_leaves_samples_untouched() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 1);
+    let samples = common::load_samples(tmp.path());
+    let none = TransformPipeline::from_config(&DatasetConfig {
+        target_size: None,
+        ..DatasetConfig::default()
+    });
+    let mut rng = StdRng::seed_from_u64(0);
+    let plain = collate::<TrainBackend>(&samples, 2).unwrap();
+    let same = collate_augmented::<TrainBackend>(&samples, 2, &none, &mut rng).unwrap();
+    assert_eq!(values(same.images), values(plain.images));
+    assert_eq!(values(same.boxes), values(plain.boxes));
+}
+
+#[test]
+fn sample_source_draws_per_epoch_from_the_seed() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 8);
+    let samples = common::load_samples(tmp.path());
+    let flip = || {
+        TransformPipeline::from_config(&DatasetConfig {
+            target_size: None,
+            flip_horizontal_prob: 0.5,
+            ..DatasetConfig::default()
+        })
+    };
+    let device = Default::default();
+    let boxes = |source: &SampleSource, epoch: usize| {
+        let batch = BatchSource::<TrainBackend>::epoch_batches(source, epoch, &device)
+            .next()
+            .unwrap()
+            .unwrap();
+        values(batch.boxes)
+    };
+    let source = SampleSource::new(&samples, 8, 1).augment(flip(), 3);
+    // A rebuilt source (as after `--resume`) draws the same flips for the same epoch...
+    let rebuilt = SampleSource::new(&samples, 8, 1).augment(flip(), 3);
+    assert_eq!(boxes(&source, 1), boxes(&rebuilt, 1));
+    // ...and each epoch draws afresh rather than replaying epoch 0.
+    assert_ne!(boxes(&source, 0), boxes(&source, 1));
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use std::fs;
+
+use models::checkpoint::{sidecar_path, CheckpointMetadata, ModelConfig, ModelKind};
+use models::input::FEATURE_DIM;
+use training::util::{
+    load_linear_classifier_from_checkpoint, load_multibox_model_from_checkpoint, run_train,
+};
+use training::TrainBackend;
+
+#[test]
+fn multibox_checkpoint_rebuilds_architecture_from_sidecar() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 2);
+    let ckpt = common::checkpoint_path(tmp.path());
+    let args = common::train_args(
+        tmp.path(),
+        tmp.path(),
+        &[
+            "--model",
+            "big",
+            "--max-boxes",
+            "3",
+            "--backbone-depth",
+            "1",
+        ],
+    );
+    run_train(args).unwrap();
+
+    let meta = CheckpointMetadata::load(&ckpt).unwrap();
+    assert_eq!(meta.kind(), ModelKind::Multibox);
+    assert_eq!(meta.warehouse_version, None);
+    let ModelConfig::Multibox(cfg) = &meta.model else {
+        panic!("expected multibox config");
+    };
+    assert_eq!(cfg.max_boxes, 3);
+    assert!(cfg.backbone.is_some());
+
+    // No max_boxes/backbone flags needed: the loader reads them from the sidecar.
+    let device = <TrainBackend as burn::tensor::backend::Backend>::Device::default();
+    let model = load_multibox_model_from_checkpoint(&ckpt, &device).unwrap();
+    assert!(model.has_backbone());
+    let images = burn::tensor::Tensor::<TrainBackend, 4>::zeros([1, 3, 8, 8], &device);
+    let features = burn::tensor::Tensor::<TrainBackend, 2>::zeros([1, FEATURE_DIM], &device);
+    let (boxes, scores) = model.forward_multibox_images(images, features);
+    assert_eq!(boxes.dims(), [1, 3, 4]);
+    assert_eq!(scores.dims(), [1, 3]);
+
+    let err = load_linear_classifier_from_checkpoint(&ckpt, &device).unwrap_err();
+    assert!(
+        err.to_string().contains("expected linear_classifier"),
+        "{err}"
+    );
+}
+
+#[test]
+fn incompatible_checkpoints_are_rejected() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 2);
+    let ckpt = common::checkpoint_path(tmp.path());
+    run_train(common::train_args(
+        tmp.path(),
+        tmp.path(),
+        &["--model", "tiny"],
+    ))
+    .unwrap();
+    let device = <TrainBackend as burn::tensor::backend::Backend>::Device::default();
+    load_linear_classifier_from_checkpoint(&ckpt, &device).unwrap();
+
+    // Sidecar from a newer format version.
+    let mut meta = CheckpointMetadata::load(&ckpt).unwrap();
+    meta.format_version += 1;
+    meta.save(&ckpt).unwrap();
+    let err = load_linear_classifier_from_checkpoint(&ckpt, &device).unwrap_err();
+    assert!(err.to_string().contains("unsupported"), "{err}");
+
+    // Input spec that does not match the stored architecture.
+    meta.format_version -= 1;
+    meta.input.dim += 1;
+    meta.save(&ckpt).unwrap();
+    let err = load_linear_classifier_from_checkpoint(&ckpt, &device).unwrap_err();
+    assert!(err.to_string().contains("input spec"), "{err}");
+
+    // Bare weights without a sidecar are rejected unless legacy loading is asked for.
+    fs::remove_file(sidecar_path(&ckpt)).unwrap();
+    let err = load_linear_classifier_from_checkpoint(&ckpt, &device).unwrap_err();
+    assert!(err.to_string().contains("metadata not found"), "{err}");
+    assert!(err.to_string().contains("--legacy-checkpoint"), "{err}");
+    let legacy = CheckpointMetadata::load_or_legacy(&ckpt, ModelKind::LinearClassifier).unwrap();
+    assert_eq!(legacy.kind(), ModelKind::LinearClassifier);
+}
@@ -0,0 +1,95 @@
+use training::metrics::{AreaRange, CocoEvaluator};
+
+const IMAGE: (u32, u32) = (100, 100);
+
+fn close(actual: Option<f32>, expected: f32) -> bool {
+    actual.is_some_and(|v| (v - expected).abs() < 1e-4)
+}
+
+#[test]
+fn perfect_detection_scores_one_in_its_size_bucket() {
+    let mut coco = CocoEvaluator::new();
+    // 20x20 px box: "small" (below 32²).
+    let gt = [0.1, 0.1, 0.3, 0.3];
+    coco.add_frame(&[gt], &[0.9], &[gt], IMAGE);
+    let m = coco.evaluate();
+
+    assert_eq!((m.frames, m.num_gt), (1, 1));
+    assert!(close(m.map, 1.0));
+    assert!(close(m.map50, 1.0));
+    assert!(close(m.map_small, 1.0));
+    assert_eq!(m.map_medium, None);
+    assert_eq!(m.map_large, None);
+    assert!(close(m.ar1, 1.0));
+    assert!(close(m.ar100, 1.0));
+}
+
+#[test]
+fn loose_box_counts_only_at_low_iou_thresholds() {
+    let mut coco = CocoEvaluator::new();
+    // IoU 0.72: a hit for 0.50..=0.70 (5 of 10 thresholds), a miss from 0.75 up.
+    coco.add_frame(
+        &[[0.0, 0.0, 0.4, 0.288]],
+        &[0.8],
+        &[[0.0, 0.0, 0.4, 0.4]],
+        IMAGE,
+    );
+    let m = coco.evaluate();
+
+    assert!(close(m.map50, 1.0));
+    assert!(close(m.map75, 0.0));
+    assert!(close(m.map, 0.5));
+    assert!(close(m.ar100, 0.5));
+    // 40x40 px ground truth sits in the medium bucket.
+    assert!(close(m.map_medium, 0.5));
+    assert_eq!(m.map_small, None);
+}
+
+#[test]
+fn ranking_and_detection_limits_drive_ap_and_ar() {
+    let mut coco = CocoEvaluator::new();
+    let a = [0.1, 0.1, 0.3, 0.3];
+    let b = [0.5, 0.5, 0.7, 0.7];
+    // Frame 1: a confident false positive ranked above the true positive.
+    coco.add_frame(&[b, a], &[0.9, 0.8], &[a], IMAGE);
+    // Frame 2: two objects, both found.
+    coco.add_frame(&[a, b], &[0.7, 0.6], &[a, b], IMAGE);
+
+    // Ranked: FP(0.9), TP(0.8), TP(0.7), TP(0.6) over 3 GTs.
+    let curve = coco.pr_curve(0.5);
+    let scores: Vec<f32> = curve.points.iter().map(|p| p.score).collect();
+    assert_eq!(scores, vec![0.9, 0.8, 0.7, 0.6]);
+    let last = curve.points.last().unwrap();
+    assert!((last.precision - 0.75).abs() < 1e-6);
+    assert!((last.recall - 1.0).abs() < 1e-6);
+    // Precision envelope is 0.75 at every recall level.
+    assert!(close(coco.average_precision(0.5, AreaRange::All), 0.75));
+
+    // Keeping one detection per frame leaves frame 1 with its false positive: 1 of 3 found.
+    assert!(close(coco.average_recall(1), 1.0 / 3.0));
+    assert!(close(coco.average_recall(10), 1.0));
+}
+
+#[test]
+fn ground_truth_outside_a_bucket_is_ignored() {
+    let mut coco = CocoEvaluator::new();
+    let small = [0.1, 0.1, 0.2, 0.2];
+    let large = [0.0, 0.0, 1.0, 1.0];
+    coco.add_frame(&[small, large], &[0.9, 0.8], &[small, large], IMAGE);
+
+    // Each bucket sees only its own object; the other detection matches ignored ground truth.
+    assert!(close(coco.map(AreaRange::Small), 1.0));
+    assert!(close(coco.map(AreaRange::Large), 1.0));
+    assert_eq!(coco.map(AreaRange::Medium), None);
+    assert!(close(coco.map(AreaRange::All), 1.0));
+}
+
+#[test]
+fn empty_ground_truth_has_no_ap() {
+    let mut coco = CocoEvaluator::new();
+    coco.add_frame(&[[0.1, 0.1, 0.3, 0.3]], &[0.9], &[], IMAGE);
+    let m = coco.evaluate();
+    assert_eq!(m.map, None);
+    assert_eq!(m.ar100, None);
+    assert_eq!(m.pr_curves.len(), 2);
+}
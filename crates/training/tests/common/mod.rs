@@ -0,0 +1,95 @@
+//! Fixtures shared by the training integration tests.
+// Each test binary compiles this module and uses only part of it.
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use data_contracts::capture::{CaptureMetadata, DetectionLabel};
+use training::util::TrainArgs;
+use training::{DatasetPathConfig, RunSample};
+
+/// Pixel color of the frames written by `write_capture_run`.
+pub const FRAME_COLOR: [u8; 3] = [40, 80, 120];
+
+/// Write a capture run of `frames` solid 8x8 frames under `root`, each labeled with one box.
+pub fn write_capture_run(root: &Path, frames: u64) {
+    write_capture_run_colored(root, frames, FRAME_COLOR);
+}
+
+/// `write_capture_run` with frames of the given `color`.
+pub fn write_capture_run_colored(root: &Path, frames: u64, color: [u8; 3]) {
+    let labels_dir = root.join("labels");
+    fs::create_dir_all(&labels_dir).unwrap();
+    for frame_id in 0..frames {
+        let image = format!("frame_{frame_id:05}.png");
+        let meta = CaptureMetadata {
+            frame_id,
+            sim_time: 0.0,
+            unix_time: 0.0,
+            image: image.clone(),
+            image_present: true,
+            camera_active: true,
+            label_seed: 1,
+            labels: vec![DetectionLabel {
+                center_world: [0.0, 0.0, 0.0],
+                bbox_px: None,
+                bbox_norm: Some([0.2, 0.2, 0.6, 0.7]),
+                source: None,
+                source_confidence: None,
+                class_id: 0,
+            }],
+        };
+        fs::write(
+            labels_dir.join(format!("frame_{frame_id:05}.json")),
+            serde_json::to_vec(&meta).unwrap(),
+        )
+        .unwrap();
+        image::RgbImage::from_pixel(8, 8, image::Rgb(color))
+            .save(root.join(image))
+            .unwrap();
+    }
+}
+
+/// Samples of a capture run written by `write_capture_run`.
+pub fn load_samples(root: &Path) -> Vec<RunSample> {
+    DatasetPathConfig {
+        root: root.to_path_buf(),
+        labels_subdir: "labels".into(),
+        images_subdir: ".".into(),
+    }
+    .load()
+    .unwrap()
+}
+
+/// Checkpoint written by a `train_args` run with output directory `out`.
+pub fn checkpoint_path(out: &Path) -> PathBuf {
+    out.join("model.bin")
+}
+
+/// Metrics log written by a `train_args` run with output directory `out`.
+pub fn metrics_path(out: &Path) -> PathBuf {
+    out.join("metrics.jsonl")
+}
+
+/// `train` argv over the capture run at `root`, writing its checkpoint and metrics under `out`,
+/// followed by `extra`.
+pub fn train_argv(root: &Path, out: &Path, extra: &[&str]) -> Vec<String> {
+    let mut argv = vec![
+        "train".to_string(),
+        "--input-root".into(),
+        root.display().to_string(),
+        "--checkpoint-out".into(),
+        checkpoint_path(out).display().to_string(),
+        "--metrics-out".into(),
+        metrics_path(out).display().to_string(),
+    ];
+    argv.extend(extra.iter().map(|s| s.to_string()));
+    argv
+}
+
+/// Parsed `train_argv`.
+pub fn train_args(root: &Path, out: &Path, extra: &[&str]) -> TrainArgs {
+    TrainArgs::parse_from(train_argv(root, out, extra))
+}
@@ -24,6 +24,7 @@ fn load_and_collate_synthetic() {
             bbox_norm: Some([0.1, 0.1, 0.2, 0.2]),
             source: None,
             source_confidence: None,
+            class_id: 0,
         }],
     };
     let json = serde_json::to_vec(&meta).unwrap();
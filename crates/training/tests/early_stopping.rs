@@ -0,0 +1,127 @@
+mod common;
+
+use std::fs;
+
+use training::early_stop::{MonitorMode, Plateau};
+use training::metrics::{EpochMetrics, MonitorMetric};
+use training::util::{load_multibox_model_from_checkpoint, run_train};
+
+#[test]
+fn plateau_tracks_best_and_stale_epochs() {
+    let mut min = Plateau::new(MonitorMode::Min, 0.1);
+    assert!(min.observe(MonitorMetric::ValLoss, 0, 1.0));
+    // Within min_delta of the best: not an improvement.
+    assert!(!min.observe(MonitorMetric::ValLoss, 1, 0.95));
+    assert!(min.observe(MonitorMetric::ValLoss, 2, 0.5));
+    assert!(!min.observe(MonitorMetric::ValLoss, 3, 0.7));
+    assert_eq!(min.best().unwrap().epoch, 2);
+    assert_eq!(min.stale_epochs(), 1);
+
+    let mut max = Plateau::new(MonitorMode::Max, 0.0);
+    assert!(max.observe(MonitorMetric::ValMap50, 0, 0.2));
+    assert!(!max.observe(MonitorMetric::ValMap50, 1, 0.1));
+    assert!(max.observe(MonitorMetric::ValMap50, 2, 0.3));
+
+    let resumed = Plateau::new(MonitorMode::Min, 0.0).resume(min.best().unwrap(), 5);
+    assert_eq!(resumed.stale_epochs(), 2);
+    assert_eq!(
+        MonitorMode::for_metric(MonitorMetric::ValMap50),
+        MonitorMode::Max
+    );
+}
+
+#[test]
+fn run_train_stops_on_plateau_and_writes_best_and_last() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    common::write_capture_run(root, 4);
+    let out = root.join("out");
+    let extra = [
+        "--model",
+        "big",
+        "--max-boxes",
+        "2",
+        "--val-ratio",
+        "0.5",
+        "--seed",
+        "1",
+        "--epochs",
+        "6",
+        "--monitor",
+        "val_loss",
+        "--early-stop-patience",
+        "2",
+        // No change can beat the first epoch by this much, so the run plateaus immediately.
+        "--early-stop-min-delta",
+        "1000",
+        // Off-schedule: only the forced save of the stopping epoch lands.
+        "--save-every",
+        "5",
+    ];
+    run_train(common::train_args(root, &out, &extra)).unwrap();
+
+    let epochs = fs::read_to_string(common::metrics_path(&out))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<EpochMetrics>(line).unwrap().epoch)
+        .collect::<Vec<_>>();
+    assert_eq!(epochs, vec![0, 1, 2]);
+
+    let run_dir = root.join("out/model_run");
+    for file in ["best.bin", "last.bin", "best.meta.json", "last.meta.json"] {
+        assert!(run_dir.join(file).is_file(), "missing {file}");
+    }
+    let best: serde_json::Value =
+        serde_json::from_slice(&fs::read(run_dir.join("best_score.json")).unwrap()).unwrap();
+    assert_eq!(best["epoch"], 0);
+    assert_eq!(best["monitor"], "val_loss");
+    load_multibox_model_from_checkpoint(run_dir.join("best.bin"), &Default::default()).unwrap();
+    // The stopping epoch is resumable.
+    assert!(run_dir.join("epoch_0003/state.json").is_file());
+}
+
+#[test]
+fn fresh_runs_discard_an_earlier_best_score() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    common::write_capture_run(root, 4);
+    let out = root.join("out");
+    let run_dir = out.join("model_run");
+    fs::create_dir_all(&run_dir).unwrap();
+    fs::write(
+        run_dir.join("best_score.json"),
+        r#"{"epoch": 7, "monitor": "val_loss", "value": 0.1}"#,
+    )
+    .unwrap();
+
+    let extra = ["--epochs", "2", "--keep-best"];
+    run_train(common::train_args(root, &out, &extra)).unwrap();
+    assert!(!run_dir.join("best_score.json").exists());
+    assert!(run_dir.join("last.bin").is_file());
+}
+
+#[test]
+fn unscorable_monitor_settings_are_rejected() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    common::write_capture_run(root, 4);
+    let out = root.join("out");
+
+    let no_val = ["--model", "big", "--early-stop-patience", "2"];
+    let err = run_train(common::train_args(root, &out, &no_val)).unwrap_err();
+    assert!(
+        err.to_string().contains("needs a validation split"),
+        "{err}"
+    );
+
+    let no_map = [
+        "--model",
+        "tiny",
+        "--val-ratio",
+        "0.5",
+        "--monitor",
+        "val_map50",
+    ];
+    let err = run_train(common::train_args(root, &out, &no_map)).unwrap_err();
+    assert!(err.to_string().contains("needs --model big"), "{err}");
+}
@@ -0,0 +1,63 @@
+use burn::backend::Autodiff;
+use burn::module::{Module, ModuleVisitor, Param};
+use burn::tensor::backend::Backend;
+use burn::tensor::Tensor;
+use training::ema::{EmaConfig, ModelEma};
+use training::{LinearClassifier, LinearClassifierConfig, TrainBackend};
+
+type ADBackend = Autodiff<TrainBackend>;
+
+/// All float parameters, flattened in traversal order.
+struct Flatten(Vec<f32>);
+
+impl<B: Backend> ModuleVisitor<B> for Flatten {
+    fn visit_float<const D: usize>(&mut self, param: &Param<Tensor<B, D>>) {
+        self.0
+            .extend(param.val().into_data().to_vec::<f32>().unwrap());
+    }
+}
+
+fn flatten<B: Backend, M: Module<B>>(module: &M) -> Vec<f32> {
+    let mut visitor = Flatten(Vec::new());
+    module.visit(&mut visitor);
+    visitor.0
+}
+
+#[test]
+fn decay_ramps_up_to_the_configured_value() {
+    let config = EmaConfig {
+        decay: 0.99,
+        warmup_steps: 10,
+    };
+    assert!(config.decay_at(0) < 0.1);
+    assert!(config.decay_at(9) < config.decay_at(10));
+    assert!((config.decay_at(1000) - 0.99).abs() < 1e-4);
+    let flat = EmaConfig {
+        warmup_steps: 0,
+        ..config
+    };
+    assert_eq!(flat.decay_at(0), 0.99);
+}
+
+#[test]
+fn update_blends_weights_into_the_shadow() {
+    let device = <ADBackend as Backend>::Device::default();
+    let start = LinearClassifier::<ADBackend>::new(LinearClassifierConfig::default(), &device);
+    let next = LinearClassifier::<ADBackend>::new(LinearClassifierConfig::default(), &device);
+    let mut ema = ModelEma::new(
+        &start,
+        EmaConfig {
+            decay: 0.25,
+            warmup_steps: 0,
+        },
+    );
+    ema.update(&next);
+    assert_eq!(ema.updates(), 1);
+
+    let (a, b) = (flatten(&start), flatten(&next));
+    let shadow = flatten(ema.shadow());
+    assert_eq!(shadow.len(), a.len());
+    for ((s, a), b) in shadow.iter().zip(&a).zip(&b) {
+        assert!((s - (0.25 * a + 0.75 * b)).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,252 @@
+use std::path::Path;
+use std::process::Command;
+
+use burn_dataset::{
+    CacheableTransformConfig, DatasetSummary, Endianness, ResizeMode, ShardDType, ShardMetadata,
+    ValidationThresholds, WarehouseManifest,
+};
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 8;
+const SAMPLES: usize = 4;
+
+/// A single f32 shard of `SAMPLES` gray frames with one box each, plus its manifest.
+fn write_warehouse(root: &Path) -> std::path::PathBuf {
+    write_warehouse_sized(root, None)
+}
+
+/// `write_warehouse` with the manifest's resize target.
+fn write_warehouse_sized(root: &Path, target_size: Option<(u32, u32)>) -> std::path::PathBuf {
+    let channels = 3u32;
+    let max_boxes = 1usize;
+    let header_len = 64usize;
+    let img_bytes = SAMPLES * (WIDTH * HEIGHT * channels) as usize * 4;
+    let box_bytes = SAMPLES * max_boxes * 4 * 4;
+    let mask_bytes = SAMPLES * max_boxes * 4;
+    let image_offset = header_len;
+    let boxes_offset = image_offset + img_bytes;
+    let mask_offset = boxes_offset + box_bytes;
+    let mut data = Vec::with_capacity(mask_offset + mask_bytes);
+    data.extend_from_slice(b"TWH1");
+    for v in [1u32, 0, 0, WIDTH, HEIGHT, channels, max_boxes as u32] {
+        data.extend_from_slice(&v.to_le_bytes());
+    }
+    for v in [SAMPLES, image_offset, boxes_offset, mask_offset] {
+        data.extend_from_slice(&(v as u64).to_le_bytes());
+    }
+    for _ in 0..img_bytes / 4 {
+        data.extend_from_slice(&0.5f32.to_le_bytes());
+    }
+    for _ in 0..SAMPLES {
+        for v in [0.25f32, 0.25, 0.75, 0.75] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    for _ in 0..SAMPLES {
+        data.extend_from_slice(&1.0f32.to_le_bytes());
+    }
+    std::fs::write(root.join("shard.bin"), data).unwrap();
+
+    let meta = ShardMetadata {
+        id: "eval".into(),
+        relative_path: "shard.bin".into(),
+        shard_version: 1,
+        samples: SAMPLES,
+        width: WIDTH,
+        height: HEIGHT,
+        channels,
+        max_boxes,
+        checksum_sha256: None,
+        dtype: ShardDType::F32,
+        endianness: Endianness::Little,
+    };
+    let transform = CacheableTransformConfig {
+        target_size,
+        resize_mode: ResizeMode::Force,
+        max_boxes,
+    };
+    let code_version = WarehouseManifest::default_code_version();
+    let version = WarehouseManifest::compute_version(root, &transform, false, &code_version);
+    let manifest = WarehouseManifest::new(
+        root.to_path_buf(),
+        transform,
+        version,
+        "test".into(),
+        code_version,
+        vec![meta],
+        DatasetSummary::default(),
+        ValidationThresholds::default(),
+    );
+    let manifest_path = root.join("manifest.json");
+    manifest.save(&manifest_path).unwrap();
+    manifest_path
+}
+
+fn run_eval(manifest: &Path, json_out: &Path, extra: &[&str]) -> serde_json::Value {
+    let output = Command::new(env!("CARGO_BIN_EXE_eval"))
+        .args(["--model", "big", "--input-source", "warehouse"])
+        .arg("--warehouse-manifest")
+        .arg(manifest)
+        .arg("--json-out")
+        .arg(json_out)
+        .args(extra)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "eval failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&std::fs::read(json_out).unwrap()).unwrap()
+}
+
+#[test]
+fn eval_reads_warehouse_splits() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = write_warehouse(tmp.path());
+    let json_out = tmp.path().join("eval.json");
+
+    let all = run_eval(&manifest, &json_out, &[]);
+    let report = &all[0];
+    assert_eq!(report["frames"], SAMPLES);
+    assert_eq!(report["coco"]["num_gt"], SAMPLES);
+    assert_eq!(report["coco"]["pr_curves"].as_array().unwrap().len(), 2);
+    assert!(report.get("areas_resized_to").is_none());
+
+    let val = run_eval(
+        &manifest,
+        &json_out,
+        &["--split", "val", "--val-ratio", "0.25", "--seed", "7"],
+    );
+    assert_eq!(val[0]["frames"], 1);
+    let train = run_eval(
+        &manifest,
+        &json_out,
+        &["--split", "train", "--val-ratio", "0.25", "--seed", "7"],
+    );
+    assert_eq!(train[0]["frames"], SAMPLES - 1);
+}
+
+#[test]
+fn eval_writes_per_frame_predictions_and_report() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = write_warehouse(tmp.path());
+    let preds = tmp.path().join("preds.jsonl");
+    let report_dir = tmp.path().join("report");
+    let all = run_eval(
+        &manifest,
+        &tmp.path().join("eval.json"),
+        &[
+            "--score-threshold",
+            "0",
+            "--predictions-out",
+            preds.to_str().unwrap(),
+            "--report-dir",
+            report_dir.to_str().unwrap(),
+            "--report-top",
+            "2",
+        ],
+    );
+
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(&preds)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), SAMPLES);
+    for (i, line) in lines.iter().enumerate() {
+        assert_eq!(line["frame"], i);
+        assert_eq!(line["ground_truth"].as_array().unwrap().len(), 1);
+    }
+    // Per-frame counts add up to the aggregate line.
+    for key in ["tp", "fp", "fn"] {
+        let total: u64 = lines.iter().map(|l| l[key].as_u64().unwrap()).sum();
+        assert_eq!(all[0][key].as_u64().unwrap(), total, "{key}");
+    }
+
+    // Every frame has errors with a fresh model at score threshold 0 (more slots than GT).
+    let report = std::fs::read_to_string(report_dir.join("report.md")).unwrap();
+    let ranked_rows = report
+        .lines()
+        .filter(|line| line.starts_with("| ") && line.as_bytes()[2].is_ascii_digit())
+        .count();
+    assert_eq!(ranked_rows, 2);
+    let overlays = std::fs::read_dir(report_dir.join("overlays"))
+        .unwrap()
+        .count();
+    assert_eq!(overlays, 2);
+}
+
+#[test]
+fn eval_writes_threshold_sweep() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = write_warehouse(tmp.path());
+    let sweep_out = tmp.path().join("sweep.json");
+    run_eval(
+        &manifest,
+        &tmp.path().join("eval.json"),
+        &[
+            "--sweep-out",
+            sweep_out.to_str().unwrap(),
+            "--target-recall",
+            "0.5",
+        ],
+    );
+
+    let sweeps: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&sweep_out).unwrap()).unwrap();
+    let sweep = &sweeps[0];
+    assert_eq!(sweep["checkpoint"], "fresh");
+    assert_eq!(sweep["positives"], SAMPLES);
+    assert!(sweep["predictions"].as_u64().unwrap() > 0);
+    let curve = sweep["curve"].as_array().unwrap();
+    assert!(!curve.is_empty());
+    let thresholds: Vec<f64> = curve
+        .iter()
+        .map(|p| p["threshold"].as_f64().unwrap())
+        .collect();
+    assert!(thresholds.windows(2).all(|w| w[0] > w[1]));
+    assert_eq!(sweep["reliability"]["bins"].as_array().unwrap().len(), 10);
+}
+
+#[test]
+fn eval_runs_tta_variants() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = write_warehouse(tmp.path());
+    let all = run_eval(
+        &manifest,
+        &tmp.path().join("eval.json"),
+        &["--tta", "--tta-scales", "1.0,0.5"],
+    );
+    assert_eq!(all[0]["frames"], SAMPLES);
+    assert_eq!(all[0]["coco"]["num_gt"], SAMPLES);
+}
+
+#[test]
+fn eval_rejects_calibration_for_linear_models() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = write_warehouse(tmp.path());
+    let output = Command::new(env!("CARGO_BIN_EXE_eval"))
+        .args(["--model", "tiny", "--input-source", "warehouse"])
+        .arg("--warehouse-manifest")
+        .arg(&manifest)
+        .arg("--sweep-out")
+        .arg(tmp.path().join("sweep.json"))
+        .arg("--write-calibration")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("only applies to multibox"), "{stderr}");
+}
+
+#[test]
+fn eval_flags_size_buckets_of_resized_shards() {
+    let tmp = tempfile::tempdir().unwrap();
+    let manifest = write_warehouse_sized(tmp.path(), Some((WIDTH, HEIGHT)));
+    let all = run_eval(&manifest, &tmp.path().join("eval.json"), &[]);
+    assert_eq!(
+        all[0]["areas_resized_to"],
+        serde_json::json!([WIDTH, HEIGHT])
+    );
+}
@@ -1,5 +1,6 @@
 use burn::backend::{ndarray::NdArray, Autodiff};
 use burn::tensor::Tensor;
+use models::input::MULTIBOX_INPUT_DIM;
 use training::{MultiboxModel, MultiboxModelConfig};
 
 type ADBackend = Autodiff<NdArray<f32>>;
@@ -8,7 +9,7 @@ type ADBackend = Autodiff<NdArray<f32>>;
 fn forward_shapes_bigdet_quick() {
     let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
     let max_boxes = 3;
-    let input_dim = 4 + 8; // box features + global features in collate
+    let input_dim = MULTIBOX_INPUT_DIM; // pooled image grid + image stats
 
     let model = MultiboxModel::<ADBackend>::new(
         MultiboxModelConfig {
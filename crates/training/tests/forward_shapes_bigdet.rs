@@ -1,6 +1,7 @@
 use burn::backend::Autodiff;
 use burn::tensor::Tensor;
 use burn_ndarray::NdArray;
+use models::input::MULTIBOX_INPUT_DIM;
 use training::{MultiboxModel, MultiboxModelConfig};
 
 // Force a CPU backend for this shape check to avoid requiring a GPU even when backend-wgpu is enabled.
@@ -10,7 +11,7 @@ type ADBackend = Autodiff<NdArray<f32>>;
 fn forward_shapes_bigdet() {
     let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
     let max_boxes = 5;
-    let input_dim = 4 + 8; // match collate features shape
+    let input_dim = MULTIBOX_INPUT_DIM; // match models::input::multibox_input
 
     let model = MultiboxModel::<ADBackend>::new(
         MultiboxModelConfig {
@@ -0,0 +1,63 @@
+use burn::backend::Autodiff;
+use burn::tensor::Tensor;
+use burn_ndarray::NdArray;
+use models::input::FEATURE_DIM;
+use training::{ConvBackbone, ConvBackboneConfig, MultiboxModel, MultiboxModelConfig};
+
+// Force a CPU backend for this shape check to avoid requiring a GPU even when backend-wgpu is enabled.
+type ADBackend = Autodiff<NdArray<f32>>;
+
+#[test]
+fn forward_shapes_conv_backbone() {
+    let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
+    let cfg = ConvBackboneConfig {
+        width: 4,
+        depth: 3,
+        ..Default::default()
+    };
+    assert_eq!(cfg.out_channels(), 16);
+
+    let backbone = ConvBackbone::<ADBackend>::new(cfg, &device);
+    // Non-square, non-power-of-two input to exercise padding on every stage.
+    let images = Tensor::<ADBackend, 4>::ones([2, 3, 24, 40], &device);
+    let embedding = backbone.forward(images);
+
+    assert_eq!(embedding.dims(), [2, 16]);
+}
+
+#[test]
+fn forward_shapes_bigdet_with_backbone() {
+    let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
+    let max_boxes = 5;
+
+    let model = MultiboxModel::<ADBackend>::new(
+        MultiboxModelConfig {
+            max_boxes,
+            backbone: Some(ConvBackboneConfig {
+                width: 4,
+                depth: 2,
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+        &device,
+    );
+    assert!(model.has_backbone());
+
+    let batch = 2;
+    // Tiny images (as in the synthetic collate tests) still reduce to a 1x1 map.
+    let images = Tensor::<ADBackend, 4>::zeros([batch, 3, 2, 2], &device);
+    let features = Tensor::<ADBackend, 2>::zeros([batch, FEATURE_DIM], &device);
+    let (boxes, scores) = model.forward_multibox_images(images, features);
+
+    assert_eq!(boxes.dims(), [batch, max_boxes, 4]);
+    assert_eq!(scores.dims(), [batch, max_boxes]);
+
+    let bmin: f32 = boxes.clone().min().into_data().to_vec::<f32>().unwrap()[0];
+    let bmax: f32 = boxes.max().into_data().to_vec::<f32>().unwrap()[0];
+    let smin: f32 = scores.clone().min().into_data().to_vec::<f32>().unwrap()[0];
+    let smax: f32 = scores.max().into_data().to_vec::<f32>().unwrap()[0];
+
+    assert!(bmin >= 0.0 - 1e-6 && bmax <= 1.0 + 1e-6);
+    assert!(smin >= 0.0 - 1e-6 && smax <= 1.0 + 1e-6);
+}
@@ -0,0 +1,78 @@
+use std::fs;
+use std::path::PathBuf;
+
+use data_contracts::capture::{CaptureMetadata, DetectionLabel};
+use models::input::{linear_input, multibox_input, LINEAR_INPUT_DIM, MULTIBOX_INPUT_DIM};
+use training::{collate, DatasetPathConfig};
+
+type Backend = burn_ndarray::NdArray<f32>;
+
+fn write_sample(root: &std::path::Path, labels: Vec<DetectionLabel>) -> Vec<training::RunSample> {
+    let labels_dir = root.join("labels");
+    fs::create_dir_all(&labels_dir).unwrap();
+    let meta = CaptureMetadata {
+        frame_id: 1,
+        sim_time: 0.0,
+        unix_time: 0.0,
+        image: "frame_00001.png".into(),
+        image_present: true,
+        camera_active: true,
+        label_seed: 42,
+        labels,
+    };
+    fs::write(
+        labels_dir.join("frame_00001.json"),
+        serde_json::to_vec(&meta).unwrap(),
+    )
+    .unwrap();
+    let img = image::RgbImage::from_fn(8, 4, |x, _y| image::Rgb([(x * 30) as u8, 0, 255]));
+    img.save(root.join("frame_00001.png")).unwrap();
+
+    DatasetPathConfig {
+        root: PathBuf::from(root),
+        labels_subdir: "labels".into(),
+        images_subdir: ".".into(),
+    }
+    .load()
+    .unwrap()
+}
+
+fn label(bbox_norm: [f32; 4]) -> DetectionLabel {
+    DetectionLabel {
+        center_world: [0.0, 0.0, 0.0],
+        bbox_px: None,
+        bbox_norm: Some(bbox_norm),
+        source: None,
+        source_confidence: None,
+        class_id: 0,
+    }
+}
+
+#[test]
+fn model_inputs_depend_only_on_the_image() {
+    let with_box = tempfile::tempdir().unwrap();
+    let without_box = tempfile::tempdir().unwrap();
+    let a = collate::<Backend>(
+        &write_sample(with_box.path(), vec![label([0.1, 0.1, 0.4, 0.6])]),
+        4,
+    )
+    .unwrap();
+    let b = collate::<Backend>(&write_sample(without_box.path(), Vec::new()), 4).unwrap();
+
+    let multi_a = multibox_input(a.images.clone(), a.features.clone());
+    let multi_b = multibox_input(b.images.clone(), b.features.clone());
+    assert_eq!(multi_a.dims(), [1, MULTIBOX_INPUT_DIM]);
+    assert_eq!(
+        multi_a.into_data().to_vec::<f32>().unwrap(),
+        multi_b.into_data().to_vec::<f32>().unwrap()
+    );
+
+    let lin_a = linear_input(a.features);
+    let lin_b = linear_input(b.features);
+    assert_eq!(lin_a.dims(), [1, LINEAR_INPUT_DIM]);
+    let lin_a = lin_a.into_data().to_vec::<f32>().unwrap();
+    assert_eq!(lin_a, lin_b.into_data().to_vec::<f32>().unwrap());
+    // Blue channel is saturated; aspect ratio is 8/4.
+    assert!((lin_a[2] - 1.0).abs() < 1e-5);
+    assert!((lin_a[3] - 2.0).abs() < 1e-5);
+}
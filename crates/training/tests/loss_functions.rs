@@ -0,0 +1,160 @@
+use burn::backend::Autodiff;
+use burn::tensor::{Tensor, TensorData};
+use training::loss::{bce_objectness, box_loss, box_loss_per_slot, focal_objectness, BoxLossKind};
+
+type Backend = burn_ndarray::NdArray<f32>;
+type ADBackend = Autodiff<Backend>;
+
+fn boxes<B: burn::tensor::backend::Backend>(data: Vec<f32>) -> Tensor<B, 3> {
+    let slots = data.len() / 4;
+    Tensor::from_data(TensorData::new(data, [1, slots, 4]), &Default::default())
+}
+
+fn scalar<B: burn::tensor::backend::Backend>(t: Tensor<B, 1>) -> f32 {
+    t.into_data().to_vec::<f32>().unwrap()[0]
+}
+
+const ALL: [BoxLossKind; 4] = [
+    BoxLossKind::L1,
+    BoxLossKind::Giou,
+    BoxLossKind::Diou,
+    BoxLossKind::Ciou,
+];
+
+#[test]
+fn iou_losses_vanish_on_perfect_boxes_and_grow_with_distance() {
+    let target = vec![0.2, 0.2, 0.4, 0.5];
+    for kind in ALL {
+        let per_slot = box_loss_per_slot::<Backend>(
+            kind,
+            boxes(vec![
+                0.2, 0.2, 0.4, 0.5, 0.25, 0.2, 0.45, 0.5, 0.7, 0.2, 0.9, 0.5,
+            ]),
+            boxes([target.clone(), target.clone(), target.clone()].concat()),
+        )
+        .into_data()
+        .to_vec::<f32>()
+        .unwrap();
+        assert!(per_slot[0].abs() < 1e-3, "{kind:?}: {per_slot:?}");
+        assert!(
+            per_slot[0] < per_slot[1] && per_slot[1] < per_slot[2],
+            "{kind:?}"
+        );
+        if kind != BoxLossKind::L1 {
+            // Disjoint boxes: IoU is 0, so the IoU-family losses exceed 1.
+            assert!(per_slot[2] > 1.0, "{kind:?}: {per_slot:?}");
+        }
+    }
+}
+
+#[test]
+fn ciou_adds_aspect_penalty_over_diou() {
+    let target = boxes::<Backend>(vec![0.4, 0.4, 0.6, 0.6]);
+    // Same center and aspect ratio: no aspect term.
+    let same_aspect = boxes::<Backend>(vec![0.35, 0.35, 0.65, 0.65]);
+    let diou = box_loss_per_slot(BoxLossKind::Diou, same_aspect.clone(), target.clone());
+    let ciou = box_loss_per_slot(BoxLossKind::Ciou, same_aspect, target.clone());
+    let (diou, ciou) = (
+        diou.into_data().to_vec::<f32>().unwrap()[0],
+        ciou.into_data().to_vec::<f32>().unwrap()[0],
+    );
+    assert!((diou - ciou).abs() < 1e-4);
+
+    // Same center and area overlap but a different aspect ratio.
+    let wide = boxes::<Backend>(vec![0.3, 0.45, 0.7, 0.55]);
+    let diou = box_loss_per_slot(BoxLossKind::Diou, wide.clone(), target.clone());
+    let ciou = box_loss_per_slot(BoxLossKind::Ciou, wide, target);
+    assert!(
+        ciou.into_data().to_vec::<f32>().unwrap()[0]
+            > diou.into_data().to_vec::<f32>().unwrap()[0] + 1e-3
+    );
+}
+
+#[test]
+fn box_loss_averages_matched_slots_only() {
+    let pred = boxes::<Backend>(vec![0.1, 0.1, 0.3, 0.3, 0.0, 0.0, 1.0, 1.0]);
+    let target = boxes::<Backend>(vec![0.1, 0.1, 0.3, 0.4, 0.5, 0.5, 0.6, 0.6]);
+    let only_first = boxes::<Backend>(vec![1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+    let l1 = scalar(box_loss(
+        BoxLossKind::L1,
+        pred.clone(),
+        target.clone(),
+        only_first,
+    ));
+    assert!((l1 - 0.1).abs() < 1e-5);
+
+    let none = boxes::<Backend>(vec![0.0; 8]);
+    for kind in ALL {
+        assert_eq!(
+            scalar(box_loss(kind, pred.clone(), target.clone(), none.clone())),
+            0.0
+        );
+    }
+}
+
+#[test]
+fn focal_loss_discounts_easy_negatives() {
+    // One positive slot and many confidently-empty slots, as in a 64-slot image.
+    let slots = 64;
+    let mut scores = vec![0.05f32; slots];
+    scores[0] = 0.6;
+    let mut targets = vec![0.0f32; slots];
+    targets[0] = 1.0;
+    let scores =
+        Tensor::<Backend, 2>::from_data(TensorData::new(scores, [1, slots]), &Default::default());
+    let targets =
+        Tensor::<Backend, 2>::from_data(TensorData::new(targets, [1, slots]), &Default::default());
+
+    // gamma = 0, alpha = 0.5 reduces to half the summed BCE over the positive count.
+    let bce = scalar(bce_objectness(scores.clone(), targets.clone()));
+    let plain = scalar(focal_objectness(scores.clone(), targets.clone(), 0.5, 0.0));
+    assert!((plain - 0.5 * bce * slots as f32).abs() < 1e-4);
+
+    // With focusing, the easy negatives contribute far less than the positive.
+    let focal_all = scalar(focal_objectness(scores.clone(), targets.clone(), 0.25, 2.0));
+    let focal_pos_only = scalar(focal_objectness(
+        scores.slice([0..1, 0..1]),
+        targets.slice([0..1, 0..1]),
+        0.25,
+        2.0,
+    ));
+    assert!(focal_all < focal_pos_only * 1.5);
+}
+
+#[test]
+fn losses_are_differentiable() {
+    let device = Default::default();
+    for kind in ALL {
+        let pred = Tensor::<ADBackend, 3>::from_data(
+            TensorData::new(vec![0.2, 0.1, 0.5, 0.6, 0.6, 0.6, 0.7, 0.9], [1, 2, 4]),
+            &device,
+        )
+        .require_grad();
+        let target = boxes::<ADBackend>(vec![0.25, 0.15, 0.45, 0.7, 0.5, 0.5, 0.8, 0.8]);
+        let weights = boxes::<ADBackend>(vec![1.0; 8]);
+        let scores =
+            Tensor::<ADBackend, 2>::from_data(TensorData::new(vec![0.3, 0.8], [1, 2]), &device)
+                .require_grad();
+        let obj_targets =
+            Tensor::<ADBackend, 2>::from_data(TensorData::new(vec![1.0, 0.0], [1, 2]), &device);
+
+        let loss = box_loss(kind, pred.clone(), target, weights)
+            + focal_objectness(scores.clone(), obj_targets, 0.25, 2.0);
+        let grads = loss.backward();
+        let pred_grad = pred
+            .grad(&grads)
+            .unwrap()
+            .into_data()
+            .to_vec::<f32>()
+            .unwrap();
+        let score_grad = scores
+            .grad(&grads)
+            .unwrap()
+            .into_data()
+            .to_vec::<f32>()
+            .unwrap();
+        assert!(pred_grad.iter().all(|g| g.is_finite()), "{kind:?}");
+        assert!(pred_grad.iter().any(|g| g.abs() > 0.0), "{kind:?}");
+        assert!(score_grad.iter().all(|g| g.is_finite() && g.abs() > 0.0));
+    }
+}
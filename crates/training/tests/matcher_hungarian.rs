@@ -0,0 +1,149 @@
+use burn::tensor::{Tensor, TensorData};
+use training::matcher::{build_hungarian_targets, giou_xyxy, hungarian, MatchCost};
+use training::util::build_greedy_targets;
+
+type Backend = burn_ndarray::NdArray<f32>;
+
+fn tensor3(data: Vec<f32>, shape: [usize; 3]) -> Tensor<Backend, 3> {
+    Tensor::from_data(TensorData::new(data, shape), &Default::default())
+}
+
+fn tensor2(data: Vec<f32>, shape: [usize; 2]) -> Tensor<Backend, 2> {
+    Tensor::from_data(TensorData::new(data, shape), &Default::default())
+}
+
+fn brute_force_min(cost: &[f32], rows: usize, cols: usize) -> f32 {
+    fn go(cost: &[f32], rows: usize, cols: usize, r: usize, used: &mut Vec<bool>) -> f32 {
+        if r == rows {
+            return 0.0;
+        }
+        let mut best = f32::INFINITY;
+        for c in 0..cols {
+            if !used[c] {
+                used[c] = true;
+                best = best.min(cost[r * cols + c] + go(cost, rows, cols, r + 1, used));
+                used[c] = false;
+            }
+        }
+        best
+    }
+    go(cost, rows, cols, 0, &mut vec![false; cols])
+}
+
+#[test]
+fn hungarian_is_optimal_and_one_to_one() {
+    // Deterministic pseudo-random matrices; compare against exhaustive search.
+    let mut state = 7u32;
+    let mut next = || {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        (state >> 8) as f32 / (1u32 << 24) as f32
+    };
+    for (rows, cols) in [(1, 1), (2, 3), (3, 3), (3, 5), (4, 4)] {
+        let cost: Vec<f32> = (0..rows * cols).map(|_| next()).collect();
+        let assign = hungarian(&cost, rows, cols);
+        let cols_used: Vec<usize> = assign.iter().map(|c| c.expect("row assigned")).collect();
+        let mut dedup = cols_used.clone();
+        dedup.sort_unstable();
+        dedup.dedup();
+        assert_eq!(dedup.len(), rows, "column reused in {assign:?}");
+
+        let total: f32 = cols_used
+            .iter()
+            .enumerate()
+            .map(|(r, &c)| cost[r * cols + c])
+            .sum();
+        assert!((total - brute_force_min(&cost, rows, cols)).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn hungarian_leaves_surplus_rows_unassigned() {
+    // Three rows competing for two columns: the cheapest two rows win.
+    let cost = vec![0.1, 0.9, 0.2, 0.8, 5.0, 5.0];
+    let assign = hungarian(&cost, 3, 2);
+    assert_eq!(assign, vec![Some(0), Some(1), None]);
+}
+
+#[test]
+fn hungarian_targets_never_share_a_slot() {
+    // Slot 0 overlaps both GTs best, so the greedy matcher sends both GTs there.
+    let pred_boxes = tensor3(
+        vec![
+            0.10, 0.10, 0.50, 0.50, // slot 0
+            0.70, 0.70, 0.90, 0.90, // slot 1
+            0.00, 0.60, 0.20, 0.90, // slot 2
+        ],
+        [1, 3, 4],
+    );
+    let pred_scores = tensor2(vec![0.5, 0.5, 0.5], [1, 3]);
+    let gt_boxes = tensor3(
+        vec![0.10, 0.10, 0.45, 0.45, 0.15, 0.15, 0.50, 0.50],
+        [1, 2, 4],
+    );
+    let gt_mask = tensor2(vec![1.0, 1.0], [1, 2]);
+
+    let (greedy_obj, _, _) =
+        build_greedy_targets(pred_boxes.clone(), gt_boxes.clone(), gt_mask.clone());
+    let greedy_obj = greedy_obj.into_data().to_vec::<f32>().unwrap();
+    assert_eq!(
+        greedy_obj.iter().sum::<f32>(),
+        1.0,
+        "greedy collapses both GTs"
+    );
+
+    let (obj, boxes, weights) = build_hungarian_targets(
+        pred_boxes,
+        pred_scores,
+        gt_boxes,
+        gt_mask,
+        &MatchCost::default(),
+    );
+    assert_eq!(obj.dims(), [1, 3]);
+    assert_eq!(boxes.dims(), [1, 3, 4]);
+    assert_eq!(weights.dims(), [1, 3, 4]);
+
+    let obj = obj.into_data().to_vec::<f32>().unwrap();
+    assert_eq!(obj.iter().sum::<f32>(), 2.0, "each GT gets its own slot");
+    let boxes = boxes.into_data().to_vec::<f32>().unwrap();
+    let weights = weights.into_data().to_vec::<f32>().unwrap();
+    let mut targets: Vec<[f32; 4]> = (0..3)
+        .filter(|&p| obj[p] > 0.5)
+        .map(|p| {
+            assert_eq!(&weights[p * 4..p * 4 + 4], &[1.0; 4]);
+            [
+                boxes[p * 4],
+                boxes[p * 4 + 1],
+                boxes[p * 4 + 2],
+                boxes[p * 4 + 3],
+            ]
+        })
+        .collect();
+    targets.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    assert_eq!(
+        targets,
+        vec![[0.10, 0.10, 0.45, 0.45], [0.15, 0.15, 0.50, 0.50]]
+    );
+}
+
+#[test]
+fn giou_penalizes_distant_boxes() {
+    let a = [0.0, 0.0, 0.2, 0.2];
+    assert!((giou_xyxy(a, a) - 1.0).abs() < 1e-6);
+    let near = giou_xyxy(a, [0.3, 0.0, 0.5, 0.2]);
+    let far = giou_xyxy(a, [0.8, 0.0, 1.0, 0.2]);
+    assert!(near < 0.0 && far < near);
+}
+
+#[test]
+fn hungarian_terminates_on_non_finite_costs() {
+    let nan = f32::NAN;
+    let cost = vec![nan, nan, nan, nan];
+    let assignment = hungarian(&cost, 2, 2);
+    assert_eq!(assignment.iter().flatten().count(), 2);
+
+    // Finite entries are preferred over NaN/inf ones.
+    let cost = vec![nan, 0.5, 0.2, f32::INFINITY];
+    assert_eq!(hungarian(&cost, 2, 2), vec![Some(1), Some(0)]);
+    let cost = vec![f32::NEG_INFINITY, 0.1, 0.1, 0.1, nan, 0.3];
+    assert_eq!(hungarian(&cost, 3, 2)[0], Some(0));
+}
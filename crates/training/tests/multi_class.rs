@@ -0,0 +1,213 @@
+use burn::backend::Autodiff;
+use burn::tensor::{Tensor, TensorData};
+use burn_dataset::{
+    CacheableTransformConfig, DatasetSummary, Endianness, ResizeMode, ShardDType, ShardMetadata,
+    ValidationThresholds, WarehouseLoaders, WarehouseManifest, SHARD_VERSION_CLASSES,
+};
+use models::input::MULTIBOX_INPUT_DIM;
+use std::path::Path;
+use training::loss::{ClassLossKind, LossConfig};
+use training::matcher::{assign_greedy, class_targets};
+use training::trainer::{MultiboxLoss, TrainLoss, TrainableModel};
+use training::{MultiboxModel, MultiboxModelConfig};
+
+type Backend = burn_ndarray::NdArray<f32>;
+type ADBackend = Autodiff<Backend>;
+
+fn tensor3(data: Vec<f32>, dims: [usize; 3]) -> Tensor<Backend, 3> {
+    Tensor::from_data(TensorData::new(data, dims), &Default::default())
+}
+
+fn tensor2(data: Vec<f32>, dims: [usize; 2]) -> Tensor<Backend, 2> {
+    Tensor::from_data(TensorData::new(data, dims), &Default::default())
+}
+
+fn scalar<B: burn::tensor::backend::Backend>(t: Tensor<B, 1>) -> f32 {
+    t.into_data().to_vec::<f32>().unwrap()[0]
+}
+
+#[test]
+fn class_targets_are_one_hot_on_matched_slots() {
+    // Slot 0 overlaps GT 1 (class 2), slot 1 overlaps GT 0 (class 1), slot 2 matches nothing.
+    let pred = tensor3(
+        vec![0.6, 0.6, 0.9, 0.9, 0.1, 0.1, 0.3, 0.3, 0.4, 0.0, 0.5, 0.05],
+        [1, 3, 4],
+    );
+    let gt = tensor3(vec![0.1, 0.1, 0.3, 0.3, 0.6, 0.6, 0.9, 0.9], [1, 2, 4]);
+    let mask = tensor2(vec![1.0, 1.0], [1, 2]);
+    let assignment = assign_greedy(pred, gt, mask);
+    assert_eq!(assignment, vec![Some(1), Some(0), None]);
+
+    let targets = class_targets(&assignment, 3, tensor2(vec![1.0, 2.0], [1, 2]), 3)
+        .into_data()
+        .to_vec::<f32>()
+        .unwrap();
+    assert_eq!(
+        targets,
+        vec![0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0],
+        "one-hot rows for matched slots, zeros elsewhere"
+    );
+
+    // Ids beyond the model's classes land on the last class.
+    let clamped = class_targets(&assignment, 3, tensor2(vec![7.0, 0.0], [1, 2]), 2)
+        .into_data()
+        .to_vec::<f32>()
+        .unwrap();
+    assert_eq!(clamped, vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+}
+
+#[test]
+fn classification_loss_averages_over_matched_slots() {
+    let softmax = LossConfig::default();
+    let focal = LossConfig {
+        class_loss: ClassLossKind::Focal,
+        ..LossConfig::default()
+    };
+    // Uniform logits over two classes: cross-entropy is ln 2 on every matched slot; the
+    // unmatched slot's (confidently wrong) logits must not count.
+    let logits = tensor3(vec![0.0, 0.0, 0.0, 0.0, 9.0, -9.0], [1, 3, 2]);
+    let targets = tensor3(vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0], [1, 3, 2]);
+    let ce = scalar(softmax.classification_loss(logits.clone(), targets.clone()));
+    assert!((ce - std::f32::consts::LN_2).abs() < 1e-4, "{ce}");
+    let fl = scalar(focal.classification_loss(logits, targets.clone()));
+    assert!((fl - 0.25 * std::f32::consts::LN_2).abs() < 1e-4, "{fl}");
+
+    // Confident correct logits cost almost nothing.
+    let confident = tensor3(vec![9.0, -9.0, -9.0, 9.0, 0.0, 0.0], [1, 3, 2]);
+    assert!(scalar(softmax.classification_loss(confident, targets)) < 1e-3);
+}
+
+#[test]
+fn multi_class_model_adds_class_logits_and_loss() {
+    let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
+    let config = |num_classes| MultiboxModelConfig {
+        max_boxes: 4,
+        input_dim: Some(MULTIBOX_INPUT_DIM),
+        num_classes,
+        ..Default::default()
+    };
+    let batch = training::CollatedBatch::<ADBackend> {
+        images: Tensor::zeros([1, 3, 8, 8], &device),
+        boxes: Tensor::from_data(
+            TensorData::new(vec![0.1, 0.1, 0.4, 0.4, 0.0, 0.0, 0.0, 0.0], [1, 2, 4]),
+            &device,
+        ),
+        box_mask: Tensor::from_data(TensorData::new(vec![1.0, 0.0], [1, 2]), &device),
+        classes: Tensor::from_data(TensorData::new(vec![2.0, 0.0], [1, 2]), &device),
+        features: Tensor::zeros([1, 8], &device),
+    };
+
+    let single = MultiboxModel::<ADBackend>::new(config(1), &device);
+    let (_, _, logits) = single.forward_batch(&batch);
+    assert_eq!(logits.dims(), [1, 4, 1]);
+
+    let model = MultiboxModel::<ADBackend>::new(config(3), &device);
+    assert_eq!(model.num_classes(), 3);
+    let (boxes, scores, logits) = model.forward_batch(&batch);
+    assert_eq!(logits.dims(), [1, 4, 3]);
+
+    let with_cls = MultiboxLoss::default();
+    let without_cls = MultiboxLoss {
+        lambda_cls: 0.0,
+        ..MultiboxLoss::default()
+    };
+    let total = scalar(with_cls.loss((boxes.clone(), scores.clone(), logits.clone()), &batch));
+    let base = scalar(without_cls.loss((boxes, scores, logits), &batch));
+    assert!(total.is_finite() && total > base, "{total} vs {base}");
+}
+
+/// A two-sample v2 shard whose single box slot carries class ids 3 and 0.
+fn write_v2_shard(root: &Path) -> std::io::Result<ShardMetadata> {
+    let (width, height, channels, max_boxes, samples) = (2u32, 1u32, 3u32, 1usize, 2usize);
+    let header_len = 80usize;
+    let img_bytes = samples * (width * height * channels) as usize * 4;
+    let box_bytes = samples * max_boxes * 4 * 4;
+    let mask_bytes = samples * max_boxes * 4;
+    let image_offset = header_len;
+    let boxes_offset = image_offset + img_bytes;
+    let mask_offset = boxes_offset + box_bytes;
+    let classes_offset = mask_offset + mask_bytes;
+    let mut data = vec![0u8; classes_offset + mask_bytes];
+    data[0..4].copy_from_slice(b"TWH1");
+    data[4..8].copy_from_slice(&SHARD_VERSION_CLASSES.to_le_bytes());
+    data[16..20].copy_from_slice(&width.to_le_bytes());
+    data[20..24].copy_from_slice(&height.to_le_bytes());
+    data[24..28].copy_from_slice(&channels.to_le_bytes());
+    data[28..32].copy_from_slice(&(max_boxes as u32).to_le_bytes());
+    data[32..40].copy_from_slice(&(samples as u64).to_le_bytes());
+    data[40..48].copy_from_slice(&(image_offset as u64).to_le_bytes());
+    data[48..56].copy_from_slice(&(boxes_offset as u64).to_le_bytes());
+    data[56..64].copy_from_slice(&(mask_offset as u64).to_le_bytes());
+    data[64..72].copy_from_slice(&(classes_offset as u64).to_le_bytes());
+
+    let mut write_f32s = |offset: usize, values: &[f32]| {
+        for (i, v) in values.iter().enumerate() {
+            data[offset + i * 4..offset + i * 4 + 4].copy_from_slice(&v.to_le_bytes());
+        }
+    };
+    write_f32s(boxes_offset, &[0.0, 0.0, 0.5, 0.5, 0.1, 0.2, 0.3, 0.4]);
+    write_f32s(mask_offset, &[1.0, 1.0]);
+    write_f32s(classes_offset, &[3.0, 0.0]);
+    std::fs::write(root.join("shard.bin"), data)?;
+
+    Ok(ShardMetadata {
+        id: "classes".into(),
+        relative_path: "shard.bin".into(),
+        shard_version: SHARD_VERSION_CLASSES,
+        samples,
+        width,
+        height,
+        channels,
+        max_boxes,
+        checksum_sha256: None,
+        dtype: ShardDType::F32,
+        endianness: Endianness::Little,
+    })
+}
+
+#[test]
+fn v2_shards_carry_class_ids_into_batches() -> anyhow::Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let root = tmp.path();
+    let meta = write_v2_shard(root)?;
+    let transform = CacheableTransformConfig {
+        target_size: None,
+        resize_mode: ResizeMode::Force,
+        max_boxes: meta.max_boxes,
+    };
+    let code_version = WarehouseManifest::default_code_version();
+    let version = WarehouseManifest::compute_version(root, &transform, false, &code_version);
+    let mut manifest = WarehouseManifest::new(
+        root.to_path_buf(),
+        transform,
+        version,
+        "test".into(),
+        code_version,
+        vec![meta],
+        DatasetSummary::default(),
+        ValidationThresholds::default(),
+    );
+    manifest.class_names = Some(vec!["a".into(), "b".into(), "c".into(), "d".into()]);
+    let manifest_path = root.join("manifest.json");
+    manifest.save(&manifest_path)?;
+    assert_eq!(
+        WarehouseManifest::load(&manifest_path)?.class_names,
+        manifest.class_names
+    );
+
+    let device = <Backend as burn::tensor::backend::Backend>::Device::default();
+    for stream in [false, true] {
+        let loaders = WarehouseLoaders::from_manifest_path(&manifest_path, 0.0, None, stream)?;
+        let mut iter = loaders.train_iter();
+        let batch = iter
+            .next_batch::<Backend>(2, &device)?
+            .expect("expected one batch");
+        let collated = training::collate_from_burn_batch::<Backend>(batch, 1)?;
+        assert_eq!(
+            collated.classes.into_data().to_vec::<f32>().unwrap(),
+            vec![3.0, 0.0],
+            "stream={stream}"
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use burn::backend::Autodiff;
+use burn::module::AutodiffModule;
+use burn::optim::{AdamConfig, GradientsParams};
+use burn::tensor::backend::Backend;
+use clap::Parser;
+use training::optim::{clip_grad_norm, grad_norm, LrSchedule, SchedulerKind};
+use training::trainer::{
+    BoxPresenceLoss, FrameValidator, SampleSource, StepInfo, TrainCallback, TrainLoss,
+    TrainableModel, Trainer,
+};
+use training::util::{lr_schedule, TrainArgs};
+use training::{collate, LinearClassifier, LinearClassifierConfig, TrainBackend};
+
+type ADBackend = Autodiff<TrainBackend>;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-9
+}
+
+#[test]
+fn schedules_follow_warmup_then_decay() {
+    let base = LrSchedule {
+        kind: SchedulerKind::Cosine,
+        lr_start: 1.0,
+        lr_end: 0.0,
+        warmup_steps: 2,
+        total_steps: 12,
+        step_size: 5,
+        gamma: 0.5,
+        pct_start: 0.2,
+    };
+    // Warmup ramps linearly to lr_start.
+    assert!(close(base.lr_at(0), 0.5));
+    assert!(close(base.lr_at(1), 1.0));
+    // Cosine: peak right after warmup, half way at the midpoint, lr_end at the end.
+    assert!(close(base.lr_at(2), 1.0));
+    assert!(close(base.lr_at(7), 0.5));
+    assert!(close(base.lr_at(12), 0.0));
+
+    let step = LrSchedule {
+        kind: SchedulerKind::Step,
+        ..base
+    };
+    assert!(close(step.lr_at(6), 1.0));
+    assert!(close(step.lr_at(7), 0.5));
+    assert!(close(step.lr_at(12), 0.25));
+
+    let one_cycle = LrSchedule {
+        kind: SchedulerKind::OneCycle,
+        warmup_steps: 0,
+        total_steps: 10,
+        ..base
+    };
+    assert!(close(one_cycle.lr_at(0), 1.0 / 25.0));
+    assert!(close(one_cycle.lr_at(2), 1.0));
+    assert!(one_cycle.lr_at(6) < 1.0 && one_cycle.lr_at(6) > one_cycle.lr_at(9));
+
+    assert!(close(LrSchedule::constant(0.3).lr_at(1000), 0.3));
+}
+
+#[test]
+fn cli_schedule_uses_optimizer_steps() {
+    let args = TrainArgs::parse_from([
+        "train",
+        "--scheduler",
+        "cosine",
+        "--lr-start",
+        "3e-4",
+        "--lr-end",
+        "1e-5",
+        "--epochs",
+        "4",
+        "--lr-step-epochs",
+        "2",
+    ]);
+    let schedule = lr_schedule(&args, 5);
+    assert_eq!(schedule.kind, SchedulerKind::Cosine);
+    assert!((schedule.lr_start - 3e-4).abs() < 1e-9);
+    assert_eq!((schedule.total_steps, schedule.step_size), (20, 10));
+}
+
+#[test]
+fn clip_grad_norm_bounds_the_global_norm() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 2);
+    let samples = common::load_samples(tmp.path());
+    let device = <ADBackend as Backend>::Device::default();
+    let model = LinearClassifier::<ADBackend>::new(LinearClassifierConfig::default(), &device);
+    let batch = collate::<ADBackend>(&samples, 1).unwrap();
+    let loss = BoxPresenceLoss.loss(model.forward_batch(&batch).mul_scalar(100.0), &batch);
+    let mut grads = GradientsParams::from_grads(loss.backward(), &model);
+
+    let before = clip_grad_norm::<ADBackend, _>(&model, &mut grads, 0.01);
+    assert!(before > 0.01);
+    let after = grad_norm::<ADBackend, _>(&model, &grads);
+    assert!(after <= 0.0101, "norm after clipping: {after}");
+}
+
+#[derive(Default)]
+struct Steps(Vec<StepInfo>);
+
+impl<M: AutodiffModule<ADBackend>, O> TrainCallback<ADBackend, M, O> for Steps {
+    fn on_step(&mut self, info: &StepInfo) -> anyhow::Result<()> {
+        self.0.push(*info);
+        Ok(())
+    }
+}
+
+#[test]
+fn grad_accum_takes_one_step_per_group() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 5);
+    let samples = common::load_samples(tmp.path());
+    let train = SampleSource::new(&samples, 1, 1);
+    let device = <ADBackend as Backend>::Device::default();
+    let model = LinearClassifier::<ADBackend>::new(LinearClassifierConfig::default(), &device);
+    let schedule = LrSchedule {
+        kind: SchedulerKind::Step,
+        step_size: 1,
+        gamma: 0.5,
+        ..LrSchedule::constant(1e-2)
+    };
+    let mut steps = Steps::default();
+    Trainer::new(
+        model,
+        AdamConfig::new().init(),
+        BoxPresenceLoss,
+        FrameValidator::new(0.5),
+        1,
+        1e-2,
+    )
+    .schedule(schedule)
+    .grad_accum(2)
+    .grad_clip(Some(1.0))
+    .callback(&mut steps)
+    .fit(&train, &[], &device)
+    .unwrap();
+
+    // Five micro-batches in groups of two: the trailing single batch still steps.
+    let taken: Vec<(u64, f64)> = steps.0.iter().map(|s| (s.step, s.lr)).collect();
+    assert_eq!(taken, vec![(1, 1e-2), (2, 5e-3), (3, 2.5e-3)]);
+    assert!(steps
+        .0
+        .iter()
+        .all(|s| s.loss.is_finite() && s.grad_norm >= 0.0));
+}
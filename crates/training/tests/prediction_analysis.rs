@@ -0,0 +1,87 @@
+use burn::tensor::{Tensor, TensorData};
+use training::analysis::{
+    image_from_tensor, match_frame, render_overlay, worst_frames, FramePredictions, MatchStatus,
+    GT_COLOR, PRED_COLOR,
+};
+use training::TrainBackend;
+
+#[test]
+fn match_frame_labels_every_box() {
+    let gt_a = [0.1, 0.1, 0.4, 0.4];
+    let gt_b = [0.6, 0.6, 0.9, 0.9];
+    let boxes = [
+        [0.1, 0.1, 0.4, 0.38], // hit on A
+        [0.1, 0.1, 0.4, 0.4],  // duplicate of A: FP
+        [0.0, 0.6, 0.2, 0.9],  // nowhere near: FP
+        [0.6, 0.6, 0.9, 0.9],  // below the score threshold: dropped
+    ];
+    let scores = [0.9, 0.8, 0.7, 0.2];
+    let (preds, gts) = match_frame(&boxes, &scores, &[gt_a, gt_b], 0.5, 0.5);
+
+    let statuses: Vec<MatchStatus> = preds.iter().map(|p| p.status).collect();
+    assert_eq!(
+        statuses,
+        vec![MatchStatus::Tp, MatchStatus::Fp, MatchStatus::Fp]
+    );
+    assert_eq!(preds[0].gt, Some(0));
+    assert!(preds[0].iou > 0.9);
+    // The duplicate still reports how well it overlaps the (already claimed) GT.
+    assert!((preds[1].iou - 1.0).abs() < 1e-6);
+    assert_eq!(preds[2].iou, 0.0);
+
+    assert_eq!(gts[0].status, MatchStatus::Tp);
+    assert_eq!(gts[0].pred, Some(0));
+    assert_eq!(gts[1].status, MatchStatus::Fn);
+    assert_eq!(gts[1].iou, 0.0);
+
+    let frame = FramePredictions::new("ckpt", 3, None, preds, gts);
+    assert_eq!((frame.tp, frame.fp, frame.fn_), (1, 2, 1));
+    let json = serde_json::to_value(&frame).unwrap();
+    assert_eq!(json["fn"], 1);
+    assert_eq!(json["predictions"][1]["status"], "fp");
+}
+
+#[test]
+fn worst_frames_rank_by_error_count() {
+    let frame = |index: usize, gts: &[[f32; 4]], boxes: &[[f32; 4]]| {
+        let scores = vec![0.9; boxes.len()];
+        let (preds, gts) = match_frame(boxes, &scores, gts, 0.5, 0.5);
+        FramePredictions::new("ckpt", index, None, preds, gts)
+    };
+    let a = [0.1, 0.1, 0.4, 0.4];
+    let b = [0.6, 0.6, 0.9, 0.9];
+    let frames = vec![
+        frame(0, &[a], &[a]),   // clean
+        frame(1, &[a], &[b]),   // 1 FN + 1 FP
+        frame(2, &[a, b], &[]), // 2 FN
+        frame(3, &[], &[a]),    // 1 FP
+    ];
+    let worst: Vec<usize> = worst_frames(&frames, 10).iter().map(|f| f.frame).collect();
+    assert_eq!(worst, vec![2, 1, 3]);
+    assert_eq!(worst_frames(&frames, 1).len(), 1);
+}
+
+#[test]
+fn overlay_draws_gt_and_predictions_in_different_colors() {
+    let device = Default::default();
+    let pixels = vec![0.5f32; 3 * 20 * 20];
+    let image = Tensor::<TrainBackend, 3>::from_data(TensorData::new(pixels, [3, 20, 20]), &device);
+    let mut img = image_from_tensor(image);
+    assert_eq!(img.dimensions(), (20, 20));
+    assert_eq!(img.get_pixel(10, 10).0, [128, 128, 128, 255]);
+
+    let (preds, gts) = match_frame(
+        &[[0.5, 0.5, 0.9, 0.9]],
+        &[0.9],
+        &[[0.0, 0.0, 0.4, 0.4]],
+        0.5,
+        0.5,
+    );
+    render_overlay(
+        &mut img,
+        &FramePredictions::new("ckpt", 0, None, preds, gts),
+    );
+    assert_eq!(*img.get_pixel(0, 0), GT_COLOR);
+    assert_eq!(*img.get_pixel(10, 10), PRED_COLOR);
+    assert_eq!(img.get_pixel(5, 5).0, [128, 128, 128, 255]);
+}
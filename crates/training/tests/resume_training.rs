@@ -0,0 +1,147 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use data_contracts::TrainStatus;
+use training::metrics::EpochMetrics;
+use training::resume::ResumeState;
+use training::util::{load_multibox_model_from_checkpoint, run_train};
+
+/// Train with `--seed 5` unless `extra` overrides it.
+fn train(root: &Path, extra: &[&str]) -> anyhow::Result<()> {
+    let status = root.join("out/status.json");
+    let mut flags = vec![
+        "--model",
+        "big",
+        "--max-boxes",
+        "2",
+        "--batch-size",
+        "2",
+        "--val-ratio",
+        "0.25",
+        "--status-file",
+        status.to_str().unwrap(),
+    ];
+    if !extra.contains(&"--seed") {
+        flags.extend(["--seed", "5"]);
+    }
+    flags.extend_from_slice(extra);
+    run_train(common::train_args(root, &root.join("out"), &flags))
+}
+
+fn epoch_dirs(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.starts_with("epoch_"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn resume_continues_from_the_saved_cursor() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    common::write_capture_run(root, 4);
+    let run_dir = root.join("out/model_run");
+
+    train(root, &["--epochs", "1"]).unwrap();
+    let state = ResumeState::load(&run_dir.join("epoch_0001")).unwrap();
+    assert_eq!(state.next_epoch, 1);
+    assert_eq!(state.step, 2);
+    assert_eq!(state.seed, Some(5));
+    assert!(run_dir.join("epoch_0001/optim.bin").is_file());
+    assert!(run_dir.join("epoch_0001/model.meta.json").is_file());
+
+    train(
+        root,
+        &["--epochs", "3", "--resume", run_dir.to_str().unwrap()],
+    )
+    .unwrap();
+    let status = TrainStatus::read(&root.join("out/status.json")).unwrap();
+    assert_eq!(status.step, 6);
+    let epochs: Vec<usize> = fs::read_to_string(root.join("out/metrics.jsonl"))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<EpochMetrics>(line).unwrap().epoch)
+        .collect();
+    assert_eq!(epochs, vec![0, 1, 2]);
+    // Default retention keeps the two newest checkpoints.
+    assert_eq!(epoch_dirs(&run_dir), vec!["epoch_0002", "epoch_0003"]);
+
+    // The split depends on the seed, so a different seed cannot resume this run.
+    let err = train(
+        root,
+        &[
+            "--epochs",
+            "4",
+            "--seed",
+            "6",
+            "--resume",
+            run_dir.to_str().unwrap(),
+        ],
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("--seed"), "{err}");
+}
+
+#[test]
+fn retention_keeps_last_n_and_best() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    common::write_capture_run(root, 4);
+    let run_dir = root.join("out/model_run");
+
+    train(root, &["--epochs", "3", "--keep-last", "1", "--keep-best"]).unwrap();
+    let kept = epoch_dirs(&run_dir);
+    assert!(kept.contains(&"epoch_0003".to_string()), "{kept:?}");
+    assert!(kept.len() <= 2, "{kept:?}");
+
+    let losses: Vec<f32> = fs::read_to_string(root.join("out/metrics.jsonl"))
+        .unwrap()
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<EpochMetrics>(line)
+                .unwrap()
+                .val_metrics[0]
+                .val_loss
+        })
+        .collect();
+    let best_epoch = (0..losses.len())
+        .min_by(|&a, &b| losses[a].total_cmp(&losses[b]))
+        .unwrap();
+    assert!(
+        kept.contains(&format!("epoch_{:04}", best_epoch + 1)),
+        "{kept:?}"
+    );
+}
+
+#[test]
+fn ema_weights_are_checkpointed_and_resumed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    common::write_capture_run(root, 4);
+    let run_dir = root.join("out/model_run");
+
+    train(root, &["--epochs", "1", "--ema"]).unwrap();
+    assert!(run_dir.join("epoch_0001/ema.bin").is_file());
+    // The saved EMA weights load like any other checkpoint.
+    load_multibox_model_from_checkpoint(root.join("out/model.bin"), &Default::default()).unwrap();
+
+    train(
+        root,
+        &[
+            "--epochs",
+            "2",
+            "--ema",
+            "--weights",
+            "raw",
+            "--resume",
+            run_dir.to_str().unwrap(),
+        ],
+    )
+    .unwrap();
+    assert!(run_dir.join("epoch_0002/ema.bin").is_file());
+}
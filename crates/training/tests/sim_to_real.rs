@@ -0,0 +1,135 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use burn_dataset::{CacheableTransformConfig, ResizeMode};
+use training::metrics::{DomainGap, EpochMetrics, ValMetrics};
+use training::trainer::{BatchSource, TransformedSource};
+use training::util::{run_train, TrainArgs};
+use training::TrainBackend;
+
+/// Train a small multibox model on the sim captures at `sim`, writing under `out`.
+fn train_args(sim: &Path, out: &Path, extra: &[&str]) -> TrainArgs {
+    let mut flags = vec![
+        "--model",
+        "big",
+        "--max-boxes",
+        "2",
+        "--epochs",
+        "2",
+        "--seed",
+        "3",
+    ];
+    flags.extend_from_slice(extra);
+    common::train_args(sim, out, &flags)
+}
+
+fn val(name: &str, val_loss: f32, precision: f32, recall: f32, map50: Option<f32>) -> ValMetrics {
+    ValMetrics {
+        name: name.to_string(),
+        frames: 4,
+        val_loss,
+        precision,
+        recall,
+        map50,
+    }
+}
+
+#[test]
+fn domain_gap_is_positive_when_real_is_worse() {
+    let sim = val("val", 0.2, 0.9, 0.8, Some(0.7));
+    let real = val("real", 0.5, 0.6, 0.5, Some(0.3));
+    let gap = DomainGap::between(&sim, &real);
+    assert_eq!(
+        (gap.reference.as_str(), gap.target.as_str()),
+        ("val", "real")
+    );
+    assert!((gap.val_loss - 0.3).abs() < 1e-6);
+    assert!((gap.precision - 0.3).abs() < 1e-6);
+    assert!((gap.recall - 0.3).abs() < 1e-6);
+    assert!((gap.map50.unwrap() - 0.4).abs() < 1e-6);
+
+    let linear = val("real", 0.5, 0.6, 0.5, None);
+    assert_eq!(DomainGap::between(&sim, &linear).map50, None);
+    assert!(DomainGap::from_splits(std::slice::from_ref(&sim), "val", "real").is_none());
+    assert!(DomainGap::from_splits(&[real, sim], "val", "real").is_some());
+}
+
+#[test]
+fn run_train_validates_on_real_captures_and_logs_the_gap() {
+    let tmp = tempfile::tempdir().unwrap();
+    let sim = tmp.path().join("sim");
+    let real = tmp.path().join("real");
+    common::write_capture_run(&sim, 4);
+    common::write_capture_run_colored(&real, 3, [150, 110, 70]);
+    let args = train_args(
+        &sim,
+        tmp.path(),
+        &[
+            "--val-ratio",
+            "0.5",
+            "--real-val-dir",
+            real.to_str().unwrap(),
+        ],
+    );
+    run_train(args).unwrap();
+
+    let rows: Vec<EpochMetrics> = fs::read_to_string(common::metrics_path(tmp.path()))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(rows.len(), 2);
+    for row in &rows {
+        let names: Vec<&str> = row.val_metrics.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["val", "real"]);
+        assert_eq!(row.val_metrics[1].frames, 3);
+        let gap = row
+            .domain_gap
+            .as_ref()
+            .expect("domain gap with a real split");
+        let expected = DomainGap::between(&row.val_metrics[0], &row.val_metrics[1]);
+        assert_eq!(gap, &expected);
+    }
+}
+
+#[test]
+fn run_train_rejects_an_empty_real_dir() {
+    let tmp = tempfile::tempdir().unwrap();
+    let sim = tmp.path().join("sim");
+    let real = tmp.path().join("real");
+    common::write_capture_run(&sim, 2);
+    fs::create_dir_all(real.join("labels")).unwrap();
+    let args = train_args(
+        &sim,
+        tmp.path(),
+        &["--real-val-dir", real.to_str().unwrap()],
+    );
+    let err = run_train(args).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("no labeled captures"),
+        "{err:#}"
+    );
+}
+
+#[test]
+fn transformed_source_resizes_real_captures_like_the_warehouse() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 3);
+    let transform = CacheableTransformConfig {
+        target_size: Some((16, 16)),
+        resize_mode: ResizeMode::Force,
+        max_boxes: 2,
+    };
+    let source = TransformedSource::new(tmp.path(), "labels", ".", &transform, 2, 2).unwrap();
+    assert_eq!(BatchSource::<TrainBackend>::len(&source), 3);
+
+    let device = Default::default();
+    let batches: Vec<_> = BatchSource::<TrainBackend>::batches(&source, &device)
+        .collect::<anyhow::Result<_>>()
+        .unwrap();
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].images.dims(), [2, 3, 16, 16]);
+    assert_eq!(batches[1].box_mask.dims(), [1, 2]);
+}
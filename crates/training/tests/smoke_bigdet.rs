@@ -3,6 +3,7 @@ use burn::module::Module;
 use burn::optim::{AdamConfig, GradientsParams, Optimizer};
 use burn::record::{BinFileRecorder, FullPrecisionSettings};
 use data_contracts::capture::{CaptureMetadata, DetectionLabel};
+use models::input::{multibox_input, MULTIBOX_INPUT_DIM};
 use std::fs;
 use std::path::PathBuf;
 use training::dataset::{collate, DatasetPathConfig};
@@ -27,6 +28,7 @@ fn synthetic_dataset(tmp: &tempfile::TempDir) -> anyhow::Result<Vec<training::Ru
             bbox_norm: Some([0.1, 0.1, 0.2, 0.2]),
             source: None,
             source_confidence: None,
+            class_id: 0,
         }],
     };
     let json = serde_json::to_vec(&meta)?;
@@ -57,20 +59,14 @@ fn smoke_train_step_bigdet() {
     let mut model = MultiboxModel::<ADBackend>::new(
         MultiboxModelConfig {
             max_boxes: 4,
-            input_dim: Some(4 + 8),
+            input_dim: Some(MULTIBOX_INPUT_DIM),
             ..Default::default()
         },
         &device,
     );
     let mut optim = AdamConfig::new().init();
 
-    let boxes = batch.boxes.clone();
-    let first_box = boxes
-        .clone()
-        .slice([0..boxes.dims()[0], 0..1, 0..4])
-        .reshape([boxes.dims()[0], 4]);
-    let features = batch.features.clone();
-    let input = burn::tensor::Tensor::cat(vec![first_box, features], 1);
+    let input = multibox_input(batch.images.clone(), batch.features.clone());
 
     let (pred_boxes, pred_scores) = model.forward_multibox(input);
     let (obj_targets, box_targets, box_weights) = training::util::build_greedy_targets(
@@ -138,7 +134,7 @@ fn smoke_train_step_bigdet() {
     let _loaded = MultiboxModel::<ADBackend>::new(
         MultiboxModelConfig {
             max_boxes: 4,
-            input_dim: Some(4 + 8),
+            input_dim: Some(MULTIBOX_INPUT_DIM),
             ..Default::default()
         },
         &device,
@@ -0,0 +1,107 @@
+use models::calibration::Calibration;
+use training::calibration::{
+    fit_calibration, reliability_diagram, sweep, threshold_curve, CalibrationMethod,
+};
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-5
+}
+
+#[test]
+fn curve_reports_each_distinct_threshold() {
+    // Ranked: TP 0.9, FP 0.8, TP 0.6 (twice, one FP), with 4 ground-truth positives.
+    let scored = [
+        (0.6, true),
+        (0.9, true),
+        (0.8, false),
+        (0.6, false),
+        (0.3, true),
+    ];
+    let curve = threshold_curve(&scored, 4);
+    let thresholds: Vec<f32> = curve.iter().map(|p| p.threshold).collect();
+    assert_eq!(thresholds, vec![0.9, 0.8, 0.6, 0.3]);
+    // Ties at 0.6 are counted together: 2 TP of 4 predictions.
+    assert!(close(curve[2].precision, 0.5));
+    assert!(close(curve[2].recall, 0.5));
+    assert!(close(curve[3].recall, 0.75));
+
+    let report = sweep(&scored, 4, CalibrationMethod::None, 0.7, 10).unwrap();
+    // F1: 0.4, 0.333, 0.5, 0.667 -> the last point wins.
+    let best = report.best_f1.unwrap();
+    assert!(close(best.threshold, 0.3));
+    assert!(best.calibrated_threshold.is_none());
+    assert!(close(report.precision_at_recall.unwrap().threshold, 0.3));
+    assert!(sweep(&scored, 4, CalibrationMethod::None, 0.9, 10)
+        .unwrap()
+        .precision_at_recall
+        .is_none());
+}
+
+#[test]
+fn reliability_bins_and_ece() {
+    let scored = [(0.95, true), (0.85, false), (0.15, false), (0.05, false)];
+    let diagram = reliability_diagram(&scored, 2);
+    assert_eq!(diagram.bins.len(), 2);
+    assert_eq!((diagram.bins[0].count, diagram.bins[1].count), (2, 2));
+    assert!(close(diagram.bins[1].mean_confidence, 0.9));
+    assert!(close(diagram.bins[1].accuracy, 0.5));
+    // Each bin is off by 0.1 and 0.4 respectively, weighted equally.
+    assert!(close(diagram.ece, 0.25));
+}
+
+/// Scores that are right far less often than they claim: 0.9 is correct half the time and 0.6
+/// a tenth of the time.
+fn overconfident() -> Vec<(f32, bool)> {
+    let mut scored = Vec::new();
+    for i in 0..20 {
+        scored.push((0.9, i % 2 == 0));
+        scored.push((0.6, i % 10 == 0));
+    }
+    scored
+}
+
+#[test]
+fn calibration_fits_overconfident_scores() {
+    let scored = overconfident();
+    let platt = fit_calibration(&scored, CalibrationMethod::Platt)
+        .unwrap()
+        .unwrap();
+    assert!(matches!(platt, Calibration::Platt { a, .. } if a > 0.0));
+    assert!((platt.apply(0.9) - 0.5).abs() < 0.05);
+    assert!((platt.apply(0.6) - 0.1).abs() < 0.05);
+
+    let report = sweep(&scored, 12, CalibrationMethod::Platt, 0.5, 10).unwrap();
+    let after = report.calibrated_reliability.unwrap();
+    assert!(after.ece < report.reliability.ece);
+    // Calibration is monotonic: thresholds keep their order.
+    let calibrated: Vec<f32> = report
+        .curve
+        .iter()
+        .map(|p| p.calibrated_threshold.unwrap())
+        .collect();
+    assert!(calibrated[0] > calibrated[1]);
+
+    // Temperature has no offset, so it needs errors on both sides of 0.5: 0.9 is right 70% of
+    // the time and 0.1 is right 30% of the time.
+    let symmetric: Vec<(f32, bool)> = (0..20)
+        .flat_map(|i| [(0.9, i % 10 < 7), (0.1, i % 10 < 3)])
+        .collect();
+    let temperature = fit_calibration(&symmetric, CalibrationMethod::Temperature)
+        .unwrap()
+        .unwrap();
+    let Calibration::Temperature { temperature: t } = temperature else {
+        panic!("expected a temperature, got {temperature:?}");
+    };
+    assert!(t > 1.0, "overconfident scores need softening: {t}");
+    assert!((temperature.apply(0.9) - 0.7).abs() < 0.05);
+}
+
+#[test]
+fn calibration_needs_both_outcomes() {
+    let all_correct = [(0.9, true), (0.7, true)];
+    assert!(fit_calibration(&all_correct, CalibrationMethod::Platt).is_err());
+    assert_eq!(
+        fit_calibration(&all_correct, CalibrationMethod::None).unwrap(),
+        None
+    );
+}
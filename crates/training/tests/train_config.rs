@@ -0,0 +1,204 @@
+mod common;
+
+use std::fs;
+
+use training::config::{parse_train_args, resolved_config, RESOLVED_CONFIG_FILE};
+use training::optim::SchedulerKind;
+use training::util::{run_train, ModelKind};
+
+#[test]
+fn toml_config_is_applied_and_cli_overrides_it() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("exp.toml");
+    fs::write(
+        &path,
+        r#"
+seed = 7
+
+[model]
+model = "big"
+max_boxes = 4
+
+[optimizer]
+lr = 0.01
+ema = true
+
+[schedule]
+epochs = 3
+scheduler = "one-cycle"
+
+[augment]
+flip_prob = 0.5
+"#,
+    )
+    .unwrap();
+    let args =
+        parse_train_args(["train", "--config", path.to_str().unwrap(), "--epochs", "5"]).unwrap();
+    assert!(matches!(args.model, ModelKind::Big));
+    assert_eq!(args.max_boxes, 4);
+    assert_eq!(args.seed, Some(7));
+    assert!(args.ema);
+    assert_eq!(args.scheduler, SchedulerKind::OneCycle);
+    assert!((args.lr - 0.01).abs() < 1e-9);
+    assert!((args.flip_prob - 0.5).abs() < 1e-9);
+    // The command line wins over the file.
+    assert_eq!(args.epochs, 5);
+}
+
+#[test]
+fn boolean_keys_pass_explicit_values() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("exp.toml");
+    fs::write(
+        &path,
+        "[data]\ndrop_last = false\n\n[optimizer]\nema = true\n",
+    )
+    .unwrap();
+    let flags = training::config::load_config_flags(&path).unwrap();
+    assert!(flags.contains(&"--drop-last=false".into()));
+    assert!(flags.contains(&"--ema=true".into()));
+
+    // Either value can be overridden from the command line.
+    let config = path.to_str().unwrap();
+    let args =
+        parse_train_args(["train", "--config", config, "--drop-last", "--ema=false"]).unwrap();
+    assert!(args.drop_last);
+    assert!(!args.ema);
+}
+
+#[test]
+fn json_config_and_errors() {
+    let tmp = tempfile::tempdir().unwrap();
+    let json = tmp.path().join("exp.json");
+    fs::write(&json, r#"{"data": {"batch_size": 3, "drop_last": true}}"#).unwrap();
+    let args = parse_train_args(["train", "--config", json.to_str().unwrap()]).unwrap();
+    assert_eq!(args.batch_size, 3);
+    assert!(args.drop_last);
+
+    let bad_section = tmp.path().join("bad.toml");
+    fs::write(&bad_section, "[optimiser]\nlr = 0.1\n").unwrap();
+    let err = parse_train_args(["train", "--config", bad_section.to_str().unwrap()]).unwrap_err();
+    assert!(
+        err.to_string().contains("unknown section [optimiser]"),
+        "{err}"
+    );
+
+    let bad_key = tmp.path().join("bad_key.toml");
+    fs::write(&bad_key, "[model]\nmax_boxs = 3\n").unwrap();
+    let err = parse_train_args(["train", "--config", bad_key.to_str().unwrap()]).unwrap_err();
+    assert!(err.to_string().contains("max-boxs"), "{err}");
+}
+
+#[test]
+fn resolved_config_replays_the_run() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    common::write_capture_run(root, 4);
+    let args = parse_train_args(common::train_argv(
+        root,
+        &root.join("out"),
+        &[
+            "--model",
+            "big",
+            "--max-boxes",
+            "2",
+            "--seed",
+            "9",
+            "--scheduler",
+            "cosine",
+            "--color-jitter-prob",
+            "0.2",
+            "--flip-prob",
+            "0.5",
+        ],
+    ))
+    .unwrap();
+    run_train(args).unwrap();
+
+    let resolved = root.join("out/model_run").join(RESOLVED_CONFIG_FILE);
+    let text = fs::read_to_string(&resolved).unwrap();
+    let doc: toml::Table = toml::from_str(&text).unwrap();
+    assert_eq!(doc["seed"].as_integer(), Some(9));
+    assert_eq!(doc["model"]["model"].as_str(), Some("big"));
+    assert_eq!(doc["schedule"]["scheduler"].as_str(), Some("cosine"));
+    assert_eq!(doc["data"]["input_source"].as_str(), Some("capture-logs"));
+    // No EMA is kept, so the raw weights are what the run saved.
+    assert_eq!(doc["optimizer"]["weights"].as_str(), Some("raw"));
+    assert!(doc["provenance"].get("code_version").is_some());
+
+    // Replaying the snapshot resolves to exactly the same settings.
+    let replay = parse_train_args(["train", "--config", resolved.to_str().unwrap()]).unwrap();
+    assert_eq!(resolved_config(&replay, None).unwrap(), text);
+}
+
+#[test]
+fn augmented_runs_without_a_seed_record_the_drawn_one() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    common::write_capture_run(root, 2);
+    let args = parse_train_args(common::train_argv(
+        root,
+        &root.join("out"),
+        &["--flip-prob", "0.5"],
+    ))
+    .unwrap();
+    assert_eq!(args.seed, None);
+    run_train(args).unwrap();
+
+    let resolved = root.join("out/model_run").join(RESOLVED_CONFIG_FILE);
+    let doc: toml::Table = toml::from_str(&fs::read_to_string(&resolved).unwrap()).unwrap();
+    assert!(doc["seed"].as_integer().is_some(), "{doc}");
+}
+
+#[test]
+fn input_root_overrides_a_replayed_data_source() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    common::write_capture_run(root, 2);
+    let path = root.join(RESOLVED_CONFIG_FILE);
+    fs::write(
+        &path,
+        "[data]\ninput_source = \"warehouse\"\ndataset_root = \"elsewhere\"\n",
+    )
+    .unwrap();
+    // What `cortenforge_tools::services::train_command_with_config` sends with a config.
+    let mut argv = common::train_argv(root, &root.join("out"), &["--epochs", "1"]);
+    argv.splice(1..1, ["--config".to_string(), path.display().to_string()]);
+    let args = parse_train_args(argv).unwrap();
+    assert_eq!(args.input_root.as_deref(), root.to_str());
+    run_train(args).unwrap();
+    assert!(common::checkpoint_path(&root.join("out")).is_file());
+}
+
+#[test]
+fn pinned_warehouse_version_must_match() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("pinned.toml");
+    let manifest = tmp.path().join("missing/manifest.json");
+    let ckpt = tmp.path().join("out/model.bin");
+    fs::write(
+        &path,
+        format!(
+            "[data]\nwarehouse_manifest = {manifest:?}\n\n[output]\ncheckpoint_out = {ckpt:?}\n\n[provenance]\nwarehouse_version = \"abc123\"\n"
+        ),
+    )
+    .unwrap();
+    let args = parse_train_args(["train", "--config", path.to_str().unwrap()]).unwrap();
+    let err = run_train(args).unwrap_err();
+    assert!(
+        err.to_string().contains("expects warehouse version abc123"),
+        "{err}"
+    );
+}
+
+#[test]
+fn ema_weights_need_ema() {
+    let tmp = tempfile::tempdir().unwrap();
+    let root = tmp.path();
+    let args = common::train_args(root, &root.join("out"), &["--weights", "ema"]);
+    let err = run_train(args).unwrap_err();
+    assert!(
+        err.to_string().contains("--weights ema needs --ema"),
+        "{err}"
+    );
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use clap::Parser;
+use cortenforge_tools::services::{train_command_with_config, TrainOptions};
+use cortenforge_tools::ToolConfig;
+use data_contracts::{TrainState, TrainStatus};
+use training::config::parse_train_args;
+use training::util::{run_train, TrainArgs};
+
+#[test]
+fn services_flag_set_parses_and_writes_completed_status() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 5);
+    let status_path = tmp.path().join("logs/train_status.json");
+    let ckpt = common::checkpoint_path(tmp.path());
+    let opts = TrainOptions {
+        input_root: tmp.path().to_path_buf(),
+        val_ratio: 0.2,
+        batch_size: 2,
+        epochs: 2,
+        seed: Some(42),
+        drop_last: true,
+        real_val_dir: None,
+        status_file: Some(status_path.clone()),
+    };
+    let cfg = ToolConfig {
+        train_bin: tmp.path().join("train"),
+        ..ToolConfig::default()
+    };
+    let cmd = train_command_with_config(&cfg, &opts).unwrap();
+    let mut argv = vec!["train".to_string()];
+    argv.extend(cmd.args);
+    argv.extend([
+        "--checkpoint-out".into(),
+        ckpt.display().to_string(),
+        "--metrics-out".into(),
+        common::metrics_path(tmp.path()).display().to_string(),
+    ]);
+    run_train(parse_train_args(argv).unwrap()).unwrap();
+
+    let status = TrainStatus::read(&status_path).unwrap();
+    assert_eq!(status.state, TrainState::Completed);
+    assert_eq!(status.epochs, 2);
+    assert_eq!(status.epoch, 1);
+    // 4 training frames, batch 2, drop-last: 2 steps per epoch.
+    assert_eq!(status.step, 4);
+    assert_eq!(status.total_steps, Some(4));
+    assert!(status.loss.is_some_and(f32::is_finite));
+    assert_eq!(status.checkpoint.as_deref(), Some(ckpt.as_path()));
+}
+
+#[test]
+fn input_root_conflicts_with_input_source() {
+    let err = TrainArgs::try_parse_from([
+        "train",
+        "--input-root",
+        "runs/a",
+        "--input-source",
+        "warehouse",
+    ])
+    .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn failed_runs_report_the_error() {
+    let tmp = tempfile::tempdir().unwrap();
+    let status_path = tmp.path().join("status.json");
+    let manifest = tmp.path().join("missing.json");
+    let ckpt = tmp.path().join("ckpt.bin");
+    let argv = [
+        "train",
+        "--warehouse-manifest",
+        manifest.to_str().unwrap(),
+        "--checkpoint-out",
+        ckpt.to_str().unwrap(),
+        "--status-file",
+        status_path.to_str().unwrap(),
+    ];
+    assert!(run_train(TrainArgs::parse_from(argv)).is_err());
+    let status = TrainStatus::read(&status_path).unwrap();
+    assert_eq!(status.state, TrainState::Failed);
+    assert!(status.error.unwrap().contains("warehouse manifest"));
+}
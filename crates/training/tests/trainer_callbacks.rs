@@ -0,0 +1,95 @@
+mod common;
+
+use burn::backend::Autodiff;
+use burn::module::AutodiffModule;
+use burn::optim::AdamConfig;
+use training::ema::ModelEma;
+use training::trainer::{
+    BoxValidator, Control, EpochInfo, MultiboxLoss, SampleSource, StepInfo, TrainCallback, Trainer,
+    ValSplit,
+};
+use training::{MultiboxModel, MultiboxModelConfig, TrainBackend};
+
+type ADBackend = Autodiff<TrainBackend>;
+
+/// Records what the trainer reports and stops after `stop_after` epochs.
+#[derive(Default)]
+struct Recorder {
+    steps: Vec<StepInfo>,
+    epochs: Vec<EpochInfo>,
+    stop_after: usize,
+}
+
+impl<M: AutodiffModule<ADBackend>, O> TrainCallback<ADBackend, M, O> for Recorder {
+    fn on_step(&mut self, info: &StepInfo) -> anyhow::Result<()> {
+        self.steps.push(*info);
+        Ok(())
+    }
+
+    fn on_epoch_end(
+        &mut self,
+        info: &EpochInfo,
+        _model: &M,
+        _optim: &O,
+        _ema: Option<&ModelEma<ADBackend, M>>,
+    ) -> anyhow::Result<Control> {
+        self.epochs.push(info.clone());
+        if self.epochs.len() >= self.stop_after {
+            Ok(Control::Stop)
+        } else {
+            Ok(Control::Continue)
+        }
+    }
+}
+
+#[test]
+fn trainer_reports_steps_and_stops_on_callback() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 5);
+    let samples = common::load_samples(tmp.path());
+    let (train, val) = samples.split_at(4);
+    // Drop-last leaves two full batches of two per epoch.
+    let train = SampleSource::new(train, 2, 2).drop_last(true);
+    let val = SampleSource::new(val, 2, 2);
+
+    let device = <ADBackend as burn::tensor::backend::Backend>::Device::default();
+    let model = MultiboxModel::<ADBackend>::new(
+        MultiboxModelConfig {
+            max_boxes: 2,
+            input_dim: Some(models::input::MULTIBOX_INPUT_DIM),
+            ..Default::default()
+        },
+        &device,
+    );
+    let mut recorder = Recorder {
+        stop_after: 2,
+        ..Default::default()
+    };
+    Trainer::new(
+        model,
+        AdamConfig::new().init(),
+        MultiboxLoss::default(),
+        BoxValidator::new(0.3, 0.5),
+        5,
+        1e-3,
+    )
+    .resume_at(1, 10)
+    .callback(&mut recorder)
+    .fit(
+        &train,
+        &[ValSplit {
+            name: "real".to_string(),
+            source: &val,
+        }],
+        &device,
+    )
+    .unwrap();
+
+    let steps: Vec<u64> = recorder.steps.iter().map(|s| s.step).collect();
+    assert_eq!(steps, vec![11, 12, 13, 14]);
+    let epochs: Vec<usize> = recorder.epochs.iter().map(|e| e.epoch).collect();
+    assert_eq!(epochs, vec![1, 2]);
+    let val = &recorder.epochs[0].val[0];
+    assert_eq!((val.name.as_str(), val.frames), ("real", 1));
+    assert!(val.map50.is_some());
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::fs;
+
+use training::metrics::{nms, DetectionAccumulator, EpochMetrics};
+use training::split_samples;
+use training::util::run_train;
+
+#[test]
+fn accumulator_reports_precision_recall_and_ap() {
+    let mut acc = DetectionAccumulator::new(0.5, 0.5);
+    let gt = [0.1, 0.1, 0.4, 0.4];
+    // Frame 1: one hit, one duplicate of the same GT (FP), one low-score miss elsewhere.
+    acc.add_frame(
+        &[gt, [0.11, 0.1, 0.4, 0.41], [0.6, 0.6, 0.9, 0.9]],
+        &[0.9, 0.8, 0.2],
+        &[gt],
+    );
+    // Frame 2: a GT the model never finds.
+    acc.add_frame(&[], &[], &[[0.5, 0.5, 0.7, 0.7]]);
+
+    assert_eq!(acc.frames(), 2);
+    assert_eq!(acc.counts(), (1, 1, 1));
+    assert!((acc.precision() - 0.5).abs() < 1e-6);
+    assert!((acc.recall() - 0.5).abs() < 1e-6);
+    // Only the top-ranked prediction is a TP: recall 0.5 at precision 1.
+    assert!((acc.average_precision() - 0.5).abs() < 1e-6);
+}
+
+#[test]
+fn nms_keeps_highest_score_per_cluster() {
+    let boxes = [
+        [0.1, 0.1, 0.4, 0.4],
+        [0.12, 0.1, 0.4, 0.42],
+        [0.6, 0.6, 0.9, 0.9],
+    ];
+    assert_eq!(nms(&boxes, &[0.7, 0.9, 0.5], 0.5), vec![1, 2]);
+}
+
+#[test]
+fn split_samples_is_seeded_and_disjoint() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 10);
+    let (train, val) = split_samples(common::load_samples(tmp.path()), 0.3, Some(7));
+    assert_eq!((train.len(), val.len()), (7, 3));
+    assert!(val.iter().all(|v| train.iter().all(|t| t.image != v.image)));
+
+    let (_, again) = split_samples(common::load_samples(tmp.path()), 0.3, Some(7));
+    let ids = |s: &[training::RunSample]| s.iter().map(|r| r.image.clone()).collect::<Vec<_>>();
+    assert_eq!(ids(&val), ids(&again));
+}
+
+#[test]
+fn run_train_appends_epoch_metrics_with_validation() {
+    let tmp = tempfile::tempdir().unwrap();
+    common::write_capture_run(tmp.path(), 4);
+    let out = tmp.path().join("logs");
+    let metrics = common::metrics_path(&out);
+    let extra = [
+        "--model",
+        "big",
+        "--max-boxes",
+        "2",
+        "--epochs",
+        "2",
+        "--val-ratio",
+        "0.5",
+        "--seed",
+        "3",
+    ];
+    run_train(common::train_args(tmp.path(), &out, &extra)).unwrap();
+
+    let rows: Vec<EpochMetrics> = fs::read_to_string(&metrics)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1].epoch, 1);
+    let val = &rows[0].val_metrics[0];
+    assert_eq!(val.name, "val");
+    assert_eq!(val.frames, 2);
+    assert!(val.val_loss.is_finite());
+    let map50 = val.map50.expect("multibox reports mAP@0.5");
+    assert!((0.0..=1.0).contains(&map50));
+
+    // The TUI reads these keys generically.
+    let raw: serde_json::Value = serde_json::from_str(
+        fs::read_to_string(&metrics)
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap(),
+    )
+    .unwrap();
+    assert!(raw["val_metrics"].as_array().is_some_and(|a| a.len() == 1));
+}
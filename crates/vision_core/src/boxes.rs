@@ -0,0 +1,49 @@
+//! Box geometry shared by inference post-processing, ensembles, and training metrics.
+//!
+//! Boxes are normalized `[x0, y0, x1, y1]`, matching `DetectionResult::boxes`.
+
+/// Intersection-over-union of two `[x0, y0, x1, y1]` boxes (corner order is normalized, since
+/// raw predictions are not sorted).
+pub fn iou_xyxy(a: [f32; 4], b: [f32; 4]) -> f32 {
+    let ax0 = a[0].min(a[2]);
+    let ay0 = a[1].min(a[3]);
+    let ax1 = a[0].max(a[2]);
+    let ay1 = a[1].max(a[3]);
+    let bx0 = b[0].min(b[2]);
+    let by0 = b[1].min(b[3]);
+    let bx1 = b[0].max(b[2]);
+    let by1 = b[1].max(b[3]);
+
+    let inter_w = (ax1.min(bx1) - ax0.max(bx0)).max(0.0);
+    let inter_h = (ay1.min(by1) - ay0.max(by0)).max(0.0);
+    let inter_area = inter_w * inter_h;
+
+    let area_a = (ax1 - ax0).max(0.0) * (ay1 - ay0).max(0.0);
+    let area_b = (bx1 - bx0).max(0.0) * (by1 - by0).max(0.0);
+    let denom = area_a + area_b - inter_area;
+    if denom <= 0.0 {
+        0.0
+    } else {
+        inter_area / denom
+    }
+}
+
+/// Greedy class-agnostic NMS.
+///
+/// Returns indices into `boxes`/`scores` of the kept detections, sorted by descending score.
+/// A candidate is suppressed when its IoU with an already-kept box is above `iou_threshold`.
+pub fn nms(boxes: &[[f32; 4]], scores: &[f32], iou_threshold: f32) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..boxes.len().min(scores.len())).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    let mut keep: Vec<usize> = Vec::new();
+    for idx in order {
+        if keep
+            .iter()
+            .all(|&k| iou_xyxy(boxes[k], boxes[idx]) <= iou_threshold)
+        {
+            keep.push(idx);
+        }
+    }
+    keep
+}
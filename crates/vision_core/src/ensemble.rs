@@ -0,0 +1,312 @@
+//! Detector ensembles: run several detectors on each frame and fuse their boxes.
+//!
+//! Fusion works class by class on normalized `[x0, y0, x1, y1]` boxes. Each member has a
+//! weight: NMS and soft-NMS rank boxes by score times the member's weight relative to the
+//! heaviest member, and weighted box fusion averages clustered boxes by weight times score.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::boxes::{iou_xyxy, nms};
+use crate::interfaces::{DetectionResult, Detector, Frame};
+
+/// How the members' boxes are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FusionMethod {
+    /// Keep the best box of each overlapping group and drop the rest.
+    Nms,
+    /// Decay the scores of overlapping boxes (Gaussian) instead of dropping them.
+    SoftNms,
+    /// Average overlapping boxes into one, weighted by member weight and score.
+    #[default]
+    Wbf,
+}
+
+impl FromStr for FusionMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nms" => Ok(Self::Nms),
+            "soft-nms" => Ok(Self::SoftNms),
+            "wbf" => Ok(Self::Wbf),
+            other => Err(format!(
+                "unknown fusion method `{other}` (expected nms, soft-nms, or wbf)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FusionMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Nms => "nms",
+            Self::SoftNms => "soft-nms",
+            Self::Wbf => "wbf",
+        })
+    }
+}
+
+/// Fusion method and its parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FusionConfig {
+    pub method: FusionMethod,
+    /// Boxes overlapping above this IoU are suppressed (NMS) or merged (WBF).
+    pub iou_threshold: f32,
+    /// Width of the soft-NMS Gaussian decay `exp(-iou^2 / sigma)`.
+    pub soft_nms_sigma: f32,
+    /// Soft-NMS drops boxes whose decayed score falls below this.
+    pub min_score: f32,
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self {
+            method: FusionMethod::Wbf,
+            iou_threshold: 0.55,
+            soft_nms_sigma: 0.5,
+            min_score: 0.001,
+        }
+    }
+}
+
+/// One member's detections on a frame.
+#[derive(Debug, Clone, Copy)]
+pub struct MemberDetections<'a> {
+    pub boxes: &'a [[f32; 4]],
+    pub scores: &'a [f32],
+    /// Class of each box; empty means every box is class 0.
+    pub classes: &'a [u32],
+    pub weight: f32,
+}
+
+/// Fused detections of one frame, highest score first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FusedDetections {
+    pub boxes: Vec<[f32; 4]>,
+    pub scores: Vec<f32>,
+    /// Class of each fused box; boxes only fuse with boxes of the same class.
+    pub classes: Vec<u32>,
+}
+
+/// Fuse the members' detections of one frame class by class. Members with a non-positive
+/// weight are ignored.
+pub fn fuse_detections(members: &[MemberDetections], config: &FusionConfig) -> FusedDetections {
+    let members: Vec<&MemberDetections> = members.iter().filter(|m| m.weight > 0.0).collect();
+    let max_weight = members.iter().map(|m| m.weight).fold(0.0f32, f32::max);
+    let total_weight: f32 = members.iter().map(|m| m.weight).sum();
+    // (box, score, member weight, class)
+    let pooled: Vec<([f32; 4], f32, f32, u32)> = members
+        .iter()
+        .flat_map(|m| {
+            m.boxes
+                .iter()
+                .zip(m.scores)
+                .enumerate()
+                .filter(|(_, (_, score))| !score.is_nan())
+                .map(|(i, (b, &s))| (*b, s, m.weight, m.classes.get(i).copied().unwrap_or(0)))
+        })
+        .collect();
+    let mut classes: Vec<u32> = pooled.iter().map(|p| p.3).collect();
+    classes.sort_unstable();
+    classes.dedup();
+
+    let mut fused: Vec<([f32; 4], f32, u32)> = Vec::new();
+    for class in classes {
+        let class_pooled: Vec<([f32; 4], f32, f32)> = pooled
+            .iter()
+            .filter(|p| p.3 == class)
+            .map(|&(b, s, w, _)| (b, s, w))
+            .collect();
+        fused.extend(
+            fuse_class(class_pooled, config, max_weight, total_weight)
+                .into_iter()
+                .map(|(b, s)| (b, s, class)),
+        );
+    }
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1));
+    FusedDetections {
+        boxes: fused.iter().map(|f| f.0).collect(),
+        scores: fused.iter().map(|f| f.1).collect(),
+        classes: fused.iter().map(|f| f.2).collect(),
+    }
+}
+
+/// Fuse one class's pooled `(box, score, member weight)` detections.
+fn fuse_class(
+    mut pooled: Vec<([f32; 4], f32, f32)>,
+    config: &FusionConfig,
+    max_weight: f32,
+    total_weight: f32,
+) -> Vec<([f32; 4], f32)> {
+    match config.method {
+        FusionMethod::Nms => {
+            let boxes: Vec<[f32; 4]> = pooled.iter().map(|p| p.0).collect();
+            let scores: Vec<f32> = pooled.iter().map(|&(_, s, w)| s * w / max_weight).collect();
+            nms(&boxes, &scores, config.iou_threshold)
+                .into_iter()
+                .map(|i| (boxes[i], scores[i]))
+                .collect()
+        }
+        FusionMethod::SoftNms => {
+            let mut remaining: Vec<([f32; 4], f32)> = pooled
+                .iter()
+                .map(|&(b, s, w)| (b, s * w / max_weight))
+                .collect();
+            let mut keep = Vec::new();
+            while let Some(best) =
+                (0..remaining.len()).max_by(|&a, &b| remaining[a].1.total_cmp(&remaining[b].1))
+            {
+                let (b, s) = remaining.swap_remove(best);
+                if s < config.min_score {
+                    break;
+                }
+                keep.push((b, s));
+                for (other, score) in remaining.iter_mut() {
+                    let iou = iou_xyxy(b, *other);
+                    *score *= (-(iou * iou) / config.soft_nms_sigma.max(f32::EPSILON)).exp();
+                }
+            }
+            keep
+        }
+        FusionMethod::Wbf => {
+            pooled.sort_by(|a, b| (b.1 * b.2).total_cmp(&(a.1 * a.2)));
+            // (fused box, sum of weight * score, weight * score weighted coordinate sums)
+            let mut clusters: Vec<([f32; 4], f32, [f32; 4])> = Vec::new();
+            for (b, s, w) in pooled {
+                let ws = w * s;
+                let cluster = clusters
+                    .iter_mut()
+                    .find(|(fused, _, _)| iou_xyxy(*fused, b) > config.iou_threshold);
+                match cluster {
+                    Some((fused, sum_ws, coords)) => {
+                        *sum_ws += ws;
+                        for i in 0..4 {
+                            coords[i] += ws * b[i];
+                            fused[i] = coords[i] / sum_ws.max(f32::EPSILON);
+                        }
+                    }
+                    None => clusters.push((b, ws, b.map(|v| v * ws))),
+                }
+            }
+            // A box every member agrees on keeps its score; one member alone is diluted.
+            clusters
+                .into_iter()
+                .map(|(fused, sum_ws, _)| (fused, (sum_ws / total_weight).min(1.0)))
+                .collect()
+        }
+    }
+}
+
+/// Fuse several weighted results for one frame into one. Confidence is the weighted mean of the
+/// results' confidences; the frame is positive when the fused output has boxes or results holding
+/// at least half the weight say it is.
+pub fn fuse_results(
+    frame_id: u64,
+    results: &[(&DetectionResult, f32)],
+    config: &FusionConfig,
+) -> DetectionResult {
+    let detections: Vec<MemberDetections> = results
+        .iter()
+        .map(|(result, weight)| MemberDetections {
+            boxes: &result.boxes,
+            scores: &result.scores,
+            classes: &result.classes,
+            weight: *weight,
+        })
+        .collect();
+    let FusedDetections {
+        boxes,
+        scores,
+        classes,
+    } = fuse_detections(&detections, config);
+    let weighted = results.iter().filter(|(_, w)| *w > 0.0);
+    let total: f32 = weighted.clone().map(|(_, w)| w).sum();
+    let (confidence, votes) = if total > 0.0 {
+        (
+            weighted.clone().map(|(r, w)| r.confidence * w).sum::<f32>() / total,
+            weighted
+                .filter(|(r, _)| r.positive)
+                .map(|(_, w)| w)
+                .sum::<f32>()
+                / total,
+        )
+    } else {
+        (0.0, 0.0)
+    };
+    DetectionResult {
+        frame_id,
+        positive: !boxes.is_empty() || votes >= 0.5,
+        confidence,
+        boxes,
+        scores,
+        classes,
+    }
+}
+
+/// A detector that runs every member and fuses their outputs with [`fuse_results`].
+pub struct EnsembleDetector {
+    members: Vec<(Box<dyn Detector + Send + Sync>, f32)>,
+    fusion: FusionConfig,
+}
+
+impl EnsembleDetector {
+    pub fn new(fusion: FusionConfig) -> Self {
+        Self {
+            members: Vec::new(),
+            fusion,
+        }
+    }
+
+    /// Add a member with a fusion weight (non-positive weights leave it out of the fusion).
+    pub fn with_member(mut self, detector: Box<dyn Detector + Send + Sync>, weight: f32) -> Self {
+        self.members.push((detector, weight));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn fusion(&self) -> FusionConfig {
+        self.fusion
+    }
+}
+
+impl Detector for EnsembleDetector {
+    fn detect(&mut self, frame: &Frame) -> DetectionResult {
+        self.detect_batch(std::slice::from_ref(frame))
+            .pop()
+            .expect("one result per frame")
+    }
+
+    fn detect_batch(&mut self, frames: &[Frame]) -> Vec<DetectionResult> {
+        let outputs: Vec<(Vec<DetectionResult>, f32)> = self
+            .members
+            .iter_mut()
+            .map(|(detector, weight)| (detector.detect_batch(frames), *weight))
+            .collect();
+        frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                let results: Vec<(&DetectionResult, f32)> = outputs
+                    .iter()
+                    .filter_map(|(results, weight)| results.get(i).map(|r| (r, *weight)))
+                    .collect();
+                fuse_results(frame.id, &results, &self.fusion)
+            })
+            .collect()
+    }
+
+    /// Forwarded to every member; the fusion IoU stays as configured.
+    fn set_thresholds(&mut self, obj: f32, iou: f32) {
+        for (detector, _) in &mut self.members {
+            detector.set_thresholds(obj, iou);
+        }
+    }
+}
@@ -28,6 +28,9 @@ pub struct DetectionResult {
     pub boxes: Vec<[f32; 4]>,
     /// Per-box scores aligned with `boxes`.
     pub scores: Vec<f32>,
+    /// Per-box class ids aligned with `boxes` (all 0 for single-class models).
+    #[serde(default)]
+    pub classes: Vec<u32>,
 }
 
 /// Data passed to a recorder sink.
@@ -47,6 +50,12 @@ pub trait FrameSource {
 /// Runs inference on a frame.
 pub trait Detector {
     fn detect(&mut self, frame: &Frame) -> DetectionResult;
+    /// Runs inference on several frames, returning one result per frame in order.
+    ///
+    /// Defaults to `detect` per frame; batched backends override it with one forward pass.
+    fn detect_batch(&mut self, frames: &[Frame]) -> Vec<DetectionResult> {
+        frames.iter().map(|frame| self.detect(frame)).collect()
+    }
     /// Optional: adjust thresholds at runtime.
     fn set_thresholds(&mut self, _obj: f32, _iou: f32) {}
 }
@@ -4,6 +4,8 @@
 //! - `Detector`: Trait for running inference on frames.
 //! - `Recorder`: Trait for persisting frame records and labels.
 //! - `Frame`, `FrameRecord`, `Label`: Core data types for vision pipelines.
+//! - `boxes`: Box IoU and NMS shared by inference, ensembles, and training metrics.
+//! - `EnsembleDetector`: Runs several detectors and fuses their boxes (NMS, soft-NMS, WBF).
 //! - Capture resources and overlay utilities.
 //!
 //! ## Design Philosophy
@@ -17,7 +19,9 @@
 //! `DetectionResult`) are **stable** and follow semantic versioning. Breaking changes to these
 //! types will result in a major version bump.
 
+pub mod boxes;
 pub mod capture;
+pub mod ensemble;
 pub mod interfaces;
 pub mod overlay;
 
@@ -25,6 +29,7 @@ pub mod prelude {
     pub use crate::capture::{
         CaptureLimit, PrimaryCaptureCamera, PrimaryCaptureReadback, PrimaryCaptureTarget,
     };
+    pub use crate::ensemble::{EnsembleDetector, FusionConfig, FusionMethod};
     pub use crate::interfaces::*;
     pub use crate::overlay::*;
 }
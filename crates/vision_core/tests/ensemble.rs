@@ -0,0 +1,208 @@
+use vision_core::ensemble::{
+    fuse_detections, EnsembleDetector, FusedDetections, FusionConfig, FusionMethod,
+    MemberDetections,
+};
+use vision_core::interfaces::{DetectionResult, Detector, Frame};
+
+fn config(method: FusionMethod) -> FusionConfig {
+    FusionConfig {
+        method,
+        ..FusionConfig::default()
+    }
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-5
+}
+
+#[test]
+fn fusion_method_parses_and_prints() {
+    for method in [FusionMethod::Nms, FusionMethod::SoftNms, FusionMethod::Wbf] {
+        assert_eq!(method.to_string().parse::<FusionMethod>(), Ok(method));
+    }
+    assert!("mean".parse::<FusionMethod>().is_err());
+    assert_eq!(FusionConfig::default().method, FusionMethod::Wbf);
+}
+
+#[test]
+fn nms_keeps_the_best_weighted_box_of_each_group() {
+    let a_boxes = [[0.1, 0.1, 0.5, 0.5]];
+    let b_boxes = [[0.12, 0.1, 0.5, 0.52], [0.6, 0.6, 0.9, 0.9]];
+    let members = [
+        MemberDetections {
+            boxes: &a_boxes,
+            scores: &[0.9],
+            classes: &[],
+            weight: 1.0,
+        },
+        MemberDetections {
+            boxes: &b_boxes,
+            scores: &[0.8, 0.4],
+            classes: &[],
+            weight: 2.0,
+        },
+    ];
+    let FusedDetections { boxes, scores, .. } =
+        fuse_detections(&members, &config(FusionMethod::Nms));
+    // Member b is the heaviest, so its 0.8 outranks a's 0.9 * 0.5.
+    assert_eq!(boxes, vec![b_boxes[0], b_boxes[1]]);
+    assert!(close(scores[0], 0.8) && close(scores[1], 0.4));
+}
+
+#[test]
+fn soft_nms_decays_overlapping_scores_instead_of_dropping_them() {
+    let boxes = [[0.1, 0.1, 0.5, 0.5], [0.1, 0.1, 0.5, 0.5]];
+    let members = [MemberDetections {
+        boxes: &boxes,
+        scores: &[0.9, 0.8],
+        classes: &[],
+        weight: 1.0,
+    }];
+    let FusedDetections {
+        boxes: fused,
+        scores,
+        ..
+    } = fuse_detections(&members, &config(FusionMethod::SoftNms));
+    assert_eq!(fused.len(), 2);
+    assert!(close(scores[0], 0.9));
+    assert!(close(scores[1], 0.8 * (-1.0f32 / 0.5).exp()));
+}
+
+#[test]
+fn wbf_averages_agreeing_boxes_and_dilutes_lone_ones() {
+    let a_boxes = [[0.1, 0.1, 0.5, 0.5], [0.7, 0.7, 0.9, 0.9]];
+    let b_boxes = [[0.2, 0.1, 0.6, 0.5]];
+    let members = [
+        MemberDetections {
+            boxes: &a_boxes,
+            scores: &[0.6, 0.5],
+            classes: &[],
+            weight: 1.0,
+        },
+        MemberDetections {
+            boxes: &b_boxes,
+            scores: &[0.6],
+            classes: &[],
+            weight: 1.0,
+        },
+    ];
+    let FusedDetections { boxes, scores, .. } =
+        fuse_detections(&members, &config(FusionMethod::Wbf));
+    assert_eq!(boxes.len(), 2);
+    let expected = [0.15, 0.1, 0.55, 0.5];
+    assert!(boxes[0].iter().zip(expected).all(|(a, b)| close(*a, b)));
+    assert!(close(scores[0], 0.6));
+    assert_eq!(boxes[1], a_boxes[1]);
+    assert!(close(scores[1], 0.25));
+}
+
+#[test]
+fn zero_weight_members_are_ignored() {
+    let boxes = [[0.1, 0.1, 0.5, 0.5]];
+    let members = [MemberDetections {
+        boxes: &boxes,
+        scores: &[0.9],
+        classes: &[],
+        weight: 0.0,
+    }];
+    for method in [FusionMethod::Nms, FusionMethod::SoftNms, FusionMethod::Wbf] {
+        assert!(fuse_detections(&members, &config(method)).boxes.is_empty());
+    }
+}
+
+#[test]
+fn boxes_only_fuse_within_their_class() {
+    let a_boxes = [[0.1, 0.1, 0.5, 0.5]];
+    let b_boxes = [[0.1, 0.1, 0.5, 0.5], [0.11, 0.1, 0.5, 0.5]];
+    let members = [
+        MemberDetections {
+            boxes: &a_boxes,
+            scores: &[0.8],
+            classes: &[1],
+            weight: 1.0,
+        },
+        MemberDetections {
+            boxes: &b_boxes,
+            scores: &[0.6, 0.7],
+            classes: &[1, 2],
+            weight: 1.0,
+        },
+    ];
+    for method in [FusionMethod::Nms, FusionMethod::Wbf] {
+        let fused = fuse_detections(&members, &config(method));
+        let mut classes = fused.classes.clone();
+        classes.sort_unstable();
+        assert_eq!(classes, vec![1, 2], "{method}");
+    }
+}
+
+/// Reports the same detection for every frame.
+struct Fixed {
+    result: DetectionResult,
+}
+
+impl Detector for Fixed {
+    fn detect(&mut self, frame: &Frame) -> DetectionResult {
+        DetectionResult {
+            frame_id: frame.id,
+            ..self.result.clone()
+        }
+    }
+}
+
+fn fixed(positive: bool, confidence: f32, boxes: Vec<[f32; 4]>) -> Box<Fixed> {
+    let scores = vec![confidence; boxes.len()];
+    let classes = vec![0; boxes.len()];
+    Box::new(Fixed {
+        result: DetectionResult {
+            frame_id: 0,
+            positive,
+            confidence,
+            boxes,
+            scores,
+            classes,
+        },
+    })
+}
+
+fn frame(id: u64) -> Frame {
+    Frame {
+        id,
+        timestamp: 0.0,
+        rgba: None,
+        size: (4, 4),
+        path: None,
+    }
+}
+
+#[test]
+fn ensemble_detector_fuses_members_per_frame() {
+    let mut ensemble = EnsembleDetector::new(FusionConfig::default())
+        .with_member(fixed(true, 0.8, vec![[0.1, 0.1, 0.5, 0.5]]), 3.0)
+        .with_member(fixed(false, 0.2, Vec::new()), 1.0);
+    assert_eq!(ensemble.len(), 2);
+
+    let results = ensemble.detect_batch(&[frame(4), frame(5)]);
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[1].frame_id, 5);
+    let result = &results[0];
+    assert!(result.positive);
+    assert!(close(result.confidence, 0.65));
+    assert_eq!(result.boxes, vec![[0.1, 0.1, 0.5, 0.5]]);
+    assert!(close(result.scores[0], 0.6));
+    assert_eq!(ensemble.detect(&frame(6)).frame_id, 6);
+}
+
+#[test]
+fn ensemble_detector_votes_by_weight_without_boxes() {
+    let mut ensemble = EnsembleDetector::new(FusionConfig::default())
+        .with_member(fixed(true, 0.9, Vec::new()), 1.0)
+        .with_member(fixed(false, 0.1, Vec::new()), 3.0);
+    assert!(!ensemble.detect(&frame(0)).positive);
+
+    let mut ensemble = EnsembleDetector::new(FusionConfig::default())
+        .with_member(fixed(true, 0.9, Vec::new()), 1.0)
+        .with_member(fixed(false, 0.1, Vec::new()), 1.0);
+    assert!(ensemble.detect(&frame(0)).positive);
+    assert!(EnsembleDetector::new(FusionConfig::default()).is_empty());
+}
@@ -20,8 +20,10 @@ use bevy::tasks::{AsyncComputeTaskPool, Task};
 use bevy_camera::{ImageRenderTarget, RenderTarget};
 use futures_lite::future::{block_on, poll_once};
 use image::RgbaImage;
-use inference::InferenceThresholds;
+use inference::{InferenceFactory, InferenceThresholds, ModelKind};
 use sim_core::{ModeSet, SimRunMode};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use vision_core::capture::{PrimaryCaptureCamera, PrimaryCaptureReadback, PrimaryCaptureTarget};
 use vision_core::interfaces::{self, Frame};
 use vision_core::overlay::draw_rect;
@@ -87,6 +89,8 @@ pub struct DetectionOverlayState {
     pub size: (u32, u32),
     pub fallback: Option<String>,
     pub inference_ms: Option<f32>,
+    /// Label of the active detector (set when switching via `DetectorBank`).
+    pub model_label: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Resource)]
@@ -106,6 +110,8 @@ pub struct RuntimeDetectionResult {
     pub confidence: f32,
     pub boxes: Vec<[f32; 4]>,
     pub scores: Vec<f32>,
+    /// Per-box class ids aligned with `boxes`.
+    pub classes: Vec<u32>,
 }
 
 /// Resource managing async inference task state.
@@ -134,6 +140,92 @@ pub struct DetectorHandle {
     pub kind: DetectorKind,
 }
 
+impl DetectorHandle {
+    /// Build a handle from an optional checkpoint, falling back to the heuristic detector.
+    ///
+    /// The architecture comes from the checkpoint's metadata; pass `model` to require one.
+    pub fn from_checkpoint(
+        weights: Option<&Path>,
+        thresh: InferenceThresholds,
+        model: Option<ModelKind>,
+    ) -> Self {
+        let factory = InferenceFactory;
+        match weights.map(|path| factory.load(thresh, path, model)) {
+            Some(Ok(det)) => Self {
+                detector: Box::new(det),
+                kind: DetectorKind::Burn,
+            },
+            Some(Err(err)) => {
+                warn!("{err:#}; using heuristic detector");
+                Self::heuristic(thresh)
+            }
+            None => Self::heuristic(thresh),
+        }
+    }
+
+    fn heuristic(thresh: InferenceThresholds) -> Self {
+        Self {
+            detector: InferenceFactory.build(thresh, None),
+            kind: DetectorKind::Heuristic,
+        }
+    }
+}
+
+/// A detector parked in a `DetectorBank`, waiting to be swapped into `DetectorHandle`.
+pub struct DetectorBankEntry {
+    pub label: String,
+    pub detector: Box<dyn interfaces::Detector + Send + Sync>,
+    pub kind: DetectorKind,
+}
+
+/// Alternate detectors for side-by-side comparison; `Tab` rotates them through `DetectorHandle`.
+#[derive(Resource, Default)]
+pub struct DetectorBank {
+    pub entries: VecDeque<DetectorBankEntry>,
+    /// Label of the detector currently in `DetectorHandle`.
+    pub active_label: String,
+}
+
+impl DetectorBank {
+    /// Load one Burn detector per checkpoint, each with the architecture its metadata describes.
+    pub fn from_checkpoints(
+        paths: &[PathBuf],
+        thresh: InferenceThresholds,
+    ) -> anyhow::Result<Self> {
+        let factory = InferenceFactory;
+        let mut entries = VecDeque::new();
+        for path in paths {
+            let det = factory.load(thresh, path, None)?;
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("model");
+            entries.push_back(DetectorBankEntry {
+                label: format!("{}:{stem}", det.kind()),
+                detector: Box::new(det),
+                kind: DetectorKind::Burn,
+            });
+        }
+        Ok(Self {
+            entries,
+            active_label: String::new(),
+        })
+    }
+
+    /// Move the first banked detector into `handle`, parking the current one at the back.
+    pub fn rotate_into(&mut self, handle: &mut DetectorHandle) -> bool {
+        let Some(next) = self.entries.pop_front() else {
+            return false;
+        };
+        let prev_detector = std::mem::replace(&mut handle.detector, next.detector);
+        let prev_kind = std::mem::replace(&mut handle.kind, next.kind);
+        let prev_label = std::mem::replace(&mut self.active_label, next.label);
+        self.entries.push_back(DetectorBankEntry {
+            label: prev_label,
+            detector: prev_detector,
+            kind: prev_kind,
+        });
+        true
+    }
+}
+
 struct DefaultTestDetector;
 
 impl interfaces::Detector for DefaultTestDetector {
@@ -144,6 +236,7 @@ impl interfaces::Detector for DefaultTestDetector {
             confidence: 0.8,
             boxes: Vec::new(),
             scores: Vec::new(),
+            classes: Vec::new(),
         }
     }
 }
@@ -368,6 +461,33 @@ pub fn threshold_hotkeys(
     }
 }
 
+/// Cycle `DetectorHandle` through the `DetectorBank` on `Tab` (only between inference jobs, since a
+/// pending job owns the active detector).
+pub fn cycle_detector_hotkey(
+    mode: Res<SimRunMode>,
+    keys: Res<ButtonInput<KeyCode>>,
+    jobs: Res<AsyncInferenceState>,
+    bank: Option<ResMut<DetectorBank>>,
+    handle: Option<ResMut<DetectorHandle>>,
+    mut overlay: ResMut<DetectionOverlayState>,
+    mut burn_loaded: ResMut<ModelLoadedFlag>,
+) {
+    if !matches!(*mode, SimRunMode::Inference) || !keys.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    let (Some(mut bank), Some(mut handle)) = (bank, handle) else {
+        return;
+    };
+    if jobs.pending.is_some() {
+        return;
+    }
+    if bank.rotate_into(&mut handle) {
+        burn_loaded.model_loaded = matches!(handle.kind, DetectorKind::Burn);
+        overlay.model_label = Some(bank.active_label.clone());
+        info!("Switched detector to {}", bank.active_label);
+    }
+}
+
 /// Bevy plugin managing runtime inference coordination.
 ///
 /// Handles async inference scheduling, model state tracking, detection overlays,
@@ -386,6 +506,7 @@ impl Plugin for InferenceRuntimePlugin {
                     schedule_burn_inference,
                     poll_inference_task,
                     threshold_hotkeys,
+                    cycle_detector_hotkey,
                 )
                     .in_set(ModeSet::Inference),
             );
@@ -405,9 +526,10 @@ pub fn recorder_draw_rect(
 
 pub mod prelude {
     pub use super::{
-        AsyncInferenceState, CapturePlugin, DetectionOverlayState, DetectorHandle, DetectorKind,
-        InferenceRuntimePlugin, InferenceThresholdsResource, ModelLoadedFlag, PrimaryCameraFrame,
-        PrimaryCameraFrameBuffer, PrimaryCameraState, RuntimeDetectionResult,
+        AsyncInferenceState, CapturePlugin, DetectionOverlayState, DetectorBank, DetectorBankEntry,
+        DetectorHandle, DetectorKind, InferenceRuntimePlugin, InferenceThresholdsResource,
+        ModelLoadedFlag, PrimaryCameraFrame, PrimaryCameraFrameBuffer, PrimaryCameraState,
+        RuntimeDetectionResult,
     };
 }
 pub fn poll_inference_task(
@@ -440,6 +562,7 @@ pub fn poll_inference_task(
             confidence: result.confidence,
             boxes: result.boxes,
             scores: result.scores,
+            classes: result.classes,
         });
     } else {
         // Task not finished; put it back.
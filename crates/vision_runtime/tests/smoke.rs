@@ -17,6 +17,7 @@ impl Detector for DummyDetector {
             confidence: 0.9,
             boxes: vec![[0.1, 0.1, 0.2, 0.2]],
             scores: vec![0.9],
+            classes: vec![0],
         }
     }
 }
@@ -58,6 +59,7 @@ fn inference_plugin_smoke_updates_overlay() {
                     confidence: 0.8,
                     boxes: vec![[0.1, 0.1, 0.2, 0.2]],
                     scores: vec![0.8],
+                    classes: vec![0],
                 },
                 1.0f32,
                 (64, 64),
@@ -75,3 +77,32 @@ fn inference_plugin_smoke_updates_overlay() {
     let overlay = app.world().get_resource::<DetectionOverlayState>().unwrap();
     assert_eq!(overlay.boxes.len(), 1);
 }
+
+#[test]
+fn detector_bank_rotates_through_handle() {
+    use vision_runtime::prelude::{DetectorBank, DetectorBankEntry};
+
+    let mut handle = DetectorHandle {
+        detector: Box::new(DummyDetector),
+        kind: DetectorKind::Heuristic,
+    };
+    let mut bank = DetectorBank {
+        active_label: "heuristic".into(),
+        ..Default::default()
+    };
+    bank.entries.push_back(DetectorBankEntry {
+        label: "multibox:a".into(),
+        detector: Box::new(DummyDetector),
+        kind: DetectorKind::Burn,
+    });
+
+    assert!(bank.rotate_into(&mut handle));
+    assert_eq!(handle.kind, DetectorKind::Burn);
+    assert_eq!(bank.active_label, "multibox:a");
+    assert_eq!(bank.entries.len(), 1);
+    assert_eq!(bank.entries[0].label, "heuristic");
+
+    assert!(bank.rotate_into(&mut handle));
+    assert_eq!(handle.kind, DetectorKind::Heuristic);
+    assert!(!DetectorBank::default().rotate_into(&mut handle));
+}
@@ -0,0 +1,220 @@
+use clap::Parser;
+use rayon::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use capture_utils::{build_inference_metadata, generate_overlays};
+use cli_support::common::ThresholdOpts;
+use cortenforge_tools::pseudo_label::{collect_inputs, model_labels, InferItem};
+use data_contracts::RunManifest;
+use inference::prelude::{
+    FusionConfig, FusionMethod, InferenceFactory, InferenceThresholds, ModelKind, TtaConfig,
+    TtaDetector,
+};
+use inference::CheckpointMetadata;
+use vision_core::interfaces::{Detector, Frame};
+
+#[derive(Parser, Debug)]
+#[command(
+    author,
+    version,
+    about = "Run a detector checkpoint over a capture run or image folder and write model-sourced labels"
+)]
+struct Args {
+    /// Capture run (with a `labels/` directory) or folder of PNG/JPEG images.
+    #[arg(long)]
+    input: PathBuf,
+    /// Output run directory; label JSON goes to `<out>/labels`.
+    #[arg(long)]
+    out: PathBuf,
+    /// Multibox detector checkpoint; repeat to fuse several checkpoints into an ensemble.
+    #[arg(long, required = true)]
+    weights: Vec<PathBuf>,
+    /// Fusion weight of each `--weights` checkpoint, in order (default 1 for every member).
+    #[arg(long)]
+    member_weight: Vec<f32>,
+    /// How ensemble members' boxes are fused: nms, soft-nms, or wbf.
+    #[arg(long, default_value_t = FusionMethod::Wbf)]
+    fusion: FusionMethod,
+    /// IoU above which ensemble boxes are suppressed (nms) or merged (wbf).
+    #[arg(long, default_value_t = 0.55)]
+    fusion_iou: f32,
+    /// Test-time augmentation: also run mirrored (and `--tta-scales`) copies and fuse the boxes.
+    #[arg(long)]
+    tta: bool,
+    /// Scales for `--tta` (1.0 is the original image).
+    #[arg(long, requires = "tta", value_delimiter = ',', default_value = "1.0")]
+    tta_scales: Vec<f32>,
+    /// Frames per forward pass.
+    #[arg(long, default_value_t = 8)]
+    batch_size: usize,
+    /// Objectness threshold.
+    #[arg(long, default_value_t = 0.3)]
+    infer_objectness_threshold: f32,
+    /// IoU threshold for NMS.
+    #[arg(long, default_value_t = 0.5)]
+    infer_iou_threshold: f32,
+    /// Also render the predicted boxes into `<out>/overlays`.
+    #[arg(long)]
+    overlays: bool,
+}
+
+/// A decoded input image ready for the detector.
+fn decode(item: &InferItem) -> anyhow::Result<Frame> {
+    let img = image::open(&item.image)
+        .map_err(|e| anyhow::anyhow!("failed to decode {}: {e}", item.image.display()))?
+        .into_rgba8();
+    let size = img.dimensions();
+    Ok(Frame {
+        id: item.frame_id,
+        timestamp: item.sim_time,
+        rgba: Some(img.into_raw()),
+        size,
+        // Absolute, so the labels resolve no matter where the output run lives.
+        path: Some(fs::canonicalize(&item.image)?),
+    })
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    if args.batch_size == 0 {
+        anyhow::bail!("--batch-size must be at least 1");
+    }
+    let thresh_opts = ThresholdOpts::new(args.infer_objectness_threshold, args.infer_iou_threshold);
+    let thresh = InferenceThresholds {
+        objectness_threshold: thresh_opts.objectness_threshold,
+        iou_threshold: thresh_opts.iou_threshold,
+    };
+    let mut detector = load_detector(&args, thresh)?;
+    if args.tta {
+        let config = TtaConfig {
+            scales: args.tta_scales.clone(),
+            ..TtaConfig::default()
+        };
+        detector = Box::new(TtaDetector::new(detector, config)?);
+    }
+
+    let items = collect_inputs(&args.input)?;
+    if items.is_empty() {
+        anyhow::bail!("no frames found under {}", args.input.display());
+    }
+    let labels_dir = args.out.join("labels");
+    fs::create_dir_all(&labels_dir)?;
+    if fs::canonicalize(&args.input)? == fs::canonicalize(&args.out)? {
+        anyhow::bail!("--out must differ from --input (it would overwrite the run's labels)");
+    }
+    copy_run_manifest(&args.input, &args.out, detector_class_names(&args.weights)?)?;
+
+    let (mut written, mut boxes, mut skipped) = (0, 0, 0);
+    for chunk in items.chunks(args.batch_size) {
+        let decoded: Vec<anyhow::Result<Frame>> = chunk.par_iter().map(decode).collect();
+        let mut batch = Vec::with_capacity(chunk.len());
+        let mut batch_items = Vec::with_capacity(chunk.len());
+        for (item, frame) in chunk.iter().zip(decoded) {
+            match frame {
+                Ok(frame) => {
+                    batch.push(frame);
+                    batch_items.push(item);
+                }
+                Err(err) => {
+                    eprintln!("skipping: {err:#}");
+                    skipped += 1;
+                }
+            }
+        }
+        let results = detector.detect_batch(&batch);
+        for ((item, frame), result) in batch_items.into_iter().zip(batch).zip(results) {
+            let labels = model_labels(&result, frame.size);
+            boxes += labels.len();
+            let meta = build_inference_metadata(
+                frame,
+                &labels,
+                item.camera_active,
+                item.label_seed,
+                item.unix_time,
+            );
+            let path = labels_dir.join(&item.label_file);
+            fs::write(&path, serde_json::to_vec_pretty(&meta)?)
+                .map_err(|e| anyhow::anyhow!("failed to write {}: {e}", path.display()))?;
+            written += 1;
+        }
+    }
+    println!(
+        "wrote {written} label files ({boxes} boxes) to {}{}",
+        labels_dir.display(),
+        if skipped > 0 {
+            format!("; skipped {skipped} undecodable images")
+        } else {
+            String::new()
+        }
+    );
+
+    if args.overlays {
+        generate_overlays(&args.out)?;
+        println!("wrote overlays to {}", args.out.join("overlays").display());
+    }
+    Ok(())
+}
+
+/// One checkpoint, or an ensemble fusing all of them.
+fn load_detector(
+    args: &Args,
+    thresh: InferenceThresholds,
+) -> anyhow::Result<Box<dyn Detector + Send + Sync>> {
+    if let [weights] = args.weights.as_slice() {
+        // Linear classifiers score whole frames and have no boxes to turn into labels.
+        let detector = InferenceFactory.load(thresh, weights, Some(ModelKind::Multibox))?;
+        return Ok(Box::new(detector));
+    }
+    let member_weights = match args.member_weight.as_slice() {
+        [] => vec![1.0; args.weights.len()],
+        weights if weights.len() == args.weights.len() => weights.to_vec(),
+        weights => anyhow::bail!(
+            "got {} --member-weight values for {} --weights checkpoints",
+            weights.len(),
+            args.weights.len()
+        ),
+    };
+    let members: Vec<(PathBuf, f32)> = args.weights.iter().cloned().zip(member_weights).collect();
+    let fusion = FusionConfig {
+        method: args.fusion,
+        iou_threshold: args.fusion_iou,
+        ..FusionConfig::default()
+    };
+    let ensemble = InferenceFactory.load_ensemble(thresh, &members, fusion)?;
+    println!("fusing {} checkpoints with {}", ensemble.len(), args.fusion);
+    Ok(Box::new(ensemble))
+}
+
+/// Class names of the `--weights` checkpoints, which every member must agree on.
+fn detector_class_names(weights: &[PathBuf]) -> anyhow::Result<Option<Vec<String>>> {
+    let mut names = weights.iter().map(|path| {
+        CheckpointMetadata::load(path)
+            .ok()
+            .and_then(|meta| meta.class_names)
+    });
+    let first = names.next().flatten();
+    if names.any(|other| other != first) {
+        anyhow::bail!("--weights checkpoints disagree on class_names");
+    }
+    Ok(first)
+}
+
+/// Keep the source run's manifest (seed, ...) with its pseudo-labels. The class table is replaced
+/// by the detector's, since that is what the written `class_id`s index.
+fn copy_run_manifest(
+    input: &Path,
+    out: &Path,
+    class_names: Option<Vec<String>>,
+) -> anyhow::Result<()> {
+    let path = input.join("run_manifest.json");
+    if path.is_file() {
+        let mut manifest: RunManifest = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        manifest.class_names = class_names;
+        fs::write(
+            out.join("run_manifest.json"),
+            serde_json::to_string_pretty(&manifest)?,
+        )?;
+    }
+    Ok(())
+}
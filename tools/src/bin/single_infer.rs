@@ -1,11 +1,13 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use image::ImageReader;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use cli_support::common::ThresholdOpts;
-use inference::prelude::{InferenceFactory, InferenceThresholds};
-use vision_core::interfaces::Frame;
+use inference::prelude::{
+    InferenceFactory, InferenceThresholds, ModelKind, TtaConfig, TtaDetector,
+};
+use vision_core::interfaces::{Detector, Frame};
 use vision_core::overlay::{draw_rect, normalize_box};
 
 #[derive(Parser, Debug)]
@@ -27,8 +29,49 @@ struct Args {
     /// IoU threshold for NMS.
     #[arg(long, default_value_t = 0.5)]
     infer_iou_threshold: f32,
+    /// Detector checkpoint(s); repeat to compare models on the same image (heuristic if omitted).
+    #[arg(long = "weights")]
+    weights: Vec<PathBuf>,
+    /// Model architecture to require (auto = read from each checkpoint's metadata).
+    #[arg(long, value_enum, default_value_t = ModelArg::Auto)]
+    model: ModelArg,
+    /// Load checkpoints without a metadata sidecar as `--model` with its default
+    /// hyperparameters (otherwise they are rejected). Only for weights trained that way.
+    #[arg(long)]
+    legacy_checkpoint: bool,
+    /// Test-time augmentation: also run mirrored (and `--tta-scales`) copies and fuse the boxes.
+    #[arg(long)]
+    tta: bool,
+    /// Scales for `--tta` (1.0 is the original image).
+    #[arg(long, requires = "tta", value_delimiter = ',', default_value = "1.0")]
+    tta_scales: Vec<f32>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum ModelArg {
+    Auto,
+    Linear,
+    Multibox,
 }
 
+impl ModelArg {
+    fn kind(self) -> Option<ModelKind> {
+        match self {
+            ModelArg::Auto => None,
+            ModelArg::Linear => Some(ModelKind::LinearClassifier),
+            ModelArg::Multibox => Some(ModelKind::Multibox),
+        }
+    }
+}
+
+/// Box colors per compared model (first model keeps the original highlight color).
+const MODEL_COLORS: [[u8; 4]; 4] = [
+    [255, 64, 192, 255],
+    [64, 192, 255, 255],
+    [255, 200, 40, 255],
+    [80, 230, 120, 255],
+];
+
 fn default_out_path(input: &Path) -> PathBuf {
     let parent = input.parent().unwrap_or_else(|| Path::new("."));
     let stem = input
@@ -56,7 +99,34 @@ fn main() -> anyhow::Result<()> {
         iou_threshold: thresh_opts.iou_threshold,
     };
     let factory = InferenceFactory;
-    let mut detector = factory.build(thresh, None);
+    let mut detectors = Vec::new();
+    if args.weights.is_empty() {
+        detectors.push(("heuristic".to_string(), factory.build(thresh, None)));
+    }
+    for path in &args.weights {
+        let det = match (args.legacy_checkpoint, args.model.kind()) {
+            (true, Some(kind)) => factory.load_legacy(thresh, path, kind)?,
+            (true, None) => anyhow::bail!("--legacy-checkpoint needs --model linear|multibox"),
+            (false, kind) => factory.load(thresh, path, kind)?,
+        };
+        detectors.push((
+            format!("{} ({})", path.display(), det.kind()),
+            Box::new(det) as Box<dyn Detector + Send + Sync>,
+        ));
+    }
+    if args.tta {
+        detectors = detectors
+            .into_iter()
+            .map(|(label, det)| {
+                let config = TtaConfig {
+                    scales: args.tta_scales.clone(),
+                    ..TtaConfig::default()
+                };
+                let det: Box<dyn Detector + Send + Sync> = Box::new(TtaDetector::new(det, config)?);
+                Ok((format!("{label} +tta"), det))
+            })
+            .collect::<anyhow::Result<_>>()?;
+    }
 
     let ts = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -70,19 +140,35 @@ fn main() -> anyhow::Result<()> {
         path: Some(in_path.clone()),
     };
 
-    let result = detector.detect(&frame);
     let mut boxed = img.clone();
-    if result.boxes.is_empty() {
-        eprintln!("no detections (confidence {})", result.confidence);
-    } else {
+    let compare = detectors.len() > 1;
+    let mut total_boxes = 0;
+    for (model_idx, (label, detector)) in detectors.iter_mut().enumerate() {
+        // The batched path, as in batch_infer: TTA variants and ensemble members share it.
+        let result = detector
+            .detect_batch(std::slice::from_ref(&frame))
+            .pop()
+            .expect("one result per frame");
+        total_boxes += result.boxes.len();
+        if compare {
+            println!(
+                "{label}: {} boxes (confidence {:.3})",
+                result.boxes.len(),
+                result.confidence
+            );
+        } else if result.boxes.is_empty() {
+            eprintln!("no detections (confidence {})", result.confidence);
+        }
         for (i, bbox) in result.boxes.iter().enumerate() {
-            let color = if i == 0 {
-                image::Rgba([255, 64, 192, 255])
+            let color = if compare {
+                MODEL_COLORS[model_idx % MODEL_COLORS.len()]
+            } else if i == 0 {
+                MODEL_COLORS[0]
             } else {
-                image::Rgba([64, 192, 255, 255])
+                MODEL_COLORS[1]
             };
             if let Some(px_box) = normalize_box(*bbox, (w, h)) {
-                draw_rect(&mut boxed, px_box, color, 2);
+                draw_rect(&mut boxed, px_box, image::Rgba(color), 2);
             }
         }
     }
@@ -90,7 +176,7 @@ fn main() -> anyhow::Result<()> {
     println!(
         "saved boxed image to {} ({} boxes)",
         out_path.display(),
-        result.boxes.len()
+        total_boxes
     );
     Ok(())
 }
@@ -3,11 +3,13 @@ use std::io;
 use std::path::Path;
 use std::time::{Duration, Instant};
 
+use cortenforge_tools::{services, ToolConfig};
 use crossterm::{
     event::{self, Event, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use data_contracts::TrainStatus;
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout},
@@ -15,9 +17,6 @@ use ratatui::{
     widgets::{Block, Borders, List, ListItem, Paragraph},
     Terminal,
 };
-use serde_json::json;
-
-use cortenforge_tools::{services, ToolConfig};
 
 #[derive(Clone, Copy)]
 struct Theme {
@@ -55,7 +54,7 @@ struct AppState {
     metrics: Vec<String>,
     datagen_pid: Option<u32>,
     train_pid: Option<u32>,
-    train_status: Option<serde_json::Value>,
+    train_status: Option<TrainStatus>,
 }
 
 fn main() -> io::Result<()> {
@@ -172,8 +171,7 @@ fn handle_key(code: KeyCode, state: &mut AppState) -> io::Result<bool> {
                 Ok(child) => {
                     state.train_pid = Some(child.id());
                     state.status = format!("Started train (pid {})", child.id());
-                    state.train_status =
-                        Some(json!({"status":"running","epoch":0,"epochs":opts.epochs}));
+                    state.train_status = Some(TrainStatus::new(opts.epochs, 0.0));
                 }
                 Err(err) => state.status = format!("Train start failed: {err}"),
             }
@@ -223,6 +221,9 @@ fn tick(state: &mut AppState) {
                 .cloned()
                 .unwrap_or(last.clone());
             state.metrics = vec![format!("epoch {epoch}: {val}")];
+            if let Some(gap) = last.get("domain_gap") {
+                state.metrics.push(format!("domain gap: {gap}"));
+            }
         }
     }
     if let Ok(lines) = services::read_log_tail(&state.cfg.train_log_path, 5) {
@@ -233,13 +234,10 @@ fn tick(state: &mut AppState) {
     }
 }
 
-fn read_train_status(cfg: &ToolConfig) -> Option<serde_json::Value> {
-    for path in &cfg.train_status_paths {
-        if let Some(status) = services::read_status(path) {
-            return Some(status);
-        }
-    }
-    None
+fn read_train_status(cfg: &ToolConfig) -> Option<TrainStatus> {
+    cfg.train_status_paths
+        .iter()
+        .find_map(|path| TrainStatus::read(path).ok())
 }
 
 fn draw_ui(f: &mut ratatui::Frame<'_>, state: &AppState) {
@@ -321,21 +319,22 @@ fn draw_ui(f: &mut ratatui::Frame<'_>, state: &AppState) {
     }
     if let Some(s) = &state.train_status {
         status_lines.push("Train status:".into());
-        if let Some(epoch) = s.get("epoch").and_then(|v| v.as_u64()) {
-            let epochs = s.get("epochs").and_then(|v| v.as_u64()).unwrap_or(0);
-            let step = s.get("step").and_then(|v| v.as_u64()).unwrap_or(0);
-            let lr = s.get("lr").and_then(|v| v.as_f64()).unwrap_or(0.0);
-            let loss = s.get("loss").and_then(|v| v.as_f64()).unwrap_or(0.0);
-            let status = s
-                .get("status")
-                .and_then(|v| v.as_str())
-                .unwrap_or("unknown");
-            status_lines.push(format!(
-                "{} epoch {}/{} step {} loss {:.4} lr {:.3e}",
-                status, epoch, epochs, step, loss, lr
-            ));
-        } else {
-            status_lines.push(format!("{}", s));
+        let eta = s
+            .eta_secs
+            .map(|secs| format!(" eta {secs:.0}s"))
+            .unwrap_or_default();
+        status_lines.push(format!(
+            "{} epoch {}/{} step {} loss {:.4} lr {:.3e}{eta}",
+            s.state.as_str(),
+            // `epoch` is zero-based; show the epoch in progress out of `epochs`.
+            s.epoch + 1,
+            s.epochs,
+            s.step,
+            s.loss.unwrap_or(0.0),
+            s.lr
+        ));
+        if let Some(err) = &s.error {
+            status_lines.push(format!("error: {err}"));
         }
     }
     if let Some(detail) = selected_run_detail(state) {
@@ -1,8 +1,8 @@
 use anyhow::Context;
 use burn_dataset::{
-    index_runs, load_sample_for_etl, summarize_root_with_thresholds, CacheableTransformConfig,
-    DatasetConfig, DatasetSample, Endianness, ResizeMode, ShardDType, ShardMetadata,
-    ValidationThresholds, WarehouseManifest,
+    index_runs, load_class_names, load_sample_for_etl, summarize_root_with_thresholds,
+    CacheableTransformConfig, DatasetConfig, DatasetSample, Endianness, ResizeMode, ShardDType,
+    ShardMetadata, ValidationThresholds, WarehouseManifest, SHARD_VERSION_CLASSES,
 };
 use clap::Parser;
 use cortenforge_tools::ToolConfig;
@@ -179,6 +179,7 @@ fn main() -> anyhow::Result<()> {
     if indices.is_empty() {
         anyhow::bail!("No label files found under {}", input_root.display());
     }
+    let class_names = load_class_names(&indices)?;
 
     let mut shards = Vec::<ShardMetadata>::new();
     let mut shard_counter = 0usize;
@@ -250,7 +251,7 @@ fn main() -> anyhow::Result<()> {
         shard_counter += 1;
     }
 
-    let manifest = WarehouseManifest::new(
+    let mut manifest = WarehouseManifest::new(
         input_root.to_path_buf(),
         CacheableTransformConfig {
             target_size: Some(args.target_size),
@@ -265,6 +266,7 @@ fn main() -> anyhow::Result<()> {
         report.summary,
         thresholds,
     );
+    manifest.class_names = class_names;
     manifest.save(&manifest_path)?;
     println!(
         "Wrote manifest {} with {} shards",
@@ -316,7 +318,7 @@ fn write_shard(
 
     // Header
     write_bytes(&mut file, &mut hasher, b"TWH1")?;
-    write_u32(&mut file, &mut hasher, 1)?; // shard_version
+    write_u32(&mut file, &mut hasher, SHARD_VERSION_CLASSES)?; // shard_version
     write_u32(
         &mut file,
         &mut hasher,
@@ -345,18 +347,18 @@ fn write_shard(
     let box_elems = samples_len * max_boxes * 4;
     let box_bytes = box_elems * std::mem::size_of::<f32>();
     let mask_elems = samples_len * max_boxes;
-    let _mask_bytes = mask_elems * std::mem::size_of::<f32>();
+    let mask_bytes = mask_elems * std::mem::size_of::<f32>();
 
     let image_offset = header_size;
     let boxes_offset = image_offset + image_bytes as u64;
     let mask_offset = boxes_offset + box_bytes as u64;
-    let meta_offset = 0u64;
+    let classes_offset = mask_offset + mask_bytes as u64;
     let checksum_offset = 0u64;
 
     write_u64(&mut file, &mut hasher, image_offset)?;
     write_u64(&mut file, &mut hasher, boxes_offset)?;
     write_u64(&mut file, &mut hasher, mask_offset)?;
-    write_u64(&mut file, &mut hasher, meta_offset)?;
+    write_u64(&mut file, &mut hasher, classes_offset)?;
     write_u64(&mut file, &mut hasher, checksum_offset)?;
 
     // Payload: images
@@ -388,6 +390,13 @@ fn write_shard(
             write_f32(&mut file, &mut hasher, m)?;
         }
     }
+    // Payload: class ids (0 in padding)
+    for sample in samples.iter() {
+        for i in 0..max_boxes {
+            let class_id = sample.classes.get(i).copied().unwrap_or(0);
+            write_f32(&mut file, &mut hasher, class_id as f32)?;
+        }
+    }
     file.flush()?;
     let checksum = hasher.finalize();
     let checksum_hex = format!("{:x}", checksum);
@@ -395,7 +404,7 @@ fn write_shard(
     let meta = ShardMetadata {
         id: format!("{:05}", shard_counter),
         relative_path: fname,
-        shard_version: 1,
+        shard_version: SHARD_VERSION_CLASSES,
         samples: samples_len,
         width,
         height,
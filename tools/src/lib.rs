@@ -1,6 +1,7 @@
 pub mod config;
 pub mod gpu_probe;
 pub mod overlay;
+pub mod pseudo_label;
 pub mod recorder;
 pub mod services;
 pub mod warehouse_commands;
@@ -0,0 +1,143 @@
+//! Helpers for `batch_infer`: finding the frames to label and turning detections into
+//! model-sourced capture labels.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use data_contracts::capture::CaptureMetadata;
+use vision_core::interfaces::{DetectionResult, Label, LabelSource};
+
+use crate::services::ServiceError;
+
+/// Image extensions picked up from plain image folders.
+const IMAGE_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+/// One frame to run the detector on, with the capture fields carried into its label file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InferItem {
+    pub frame_id: u64,
+    pub sim_time: f64,
+    pub unix_time: f64,
+    pub camera_active: bool,
+    pub label_seed: u64,
+    /// Image to decode.
+    pub image: PathBuf,
+    /// File name of the label JSON to write.
+    pub label_file: String,
+}
+
+/// Frames under `input`: a capture run (a `labels/` directory of `CaptureMetadata` JSON) or a
+/// folder of images. Capture frames keep their ids and timestamps; images are numbered in
+/// file-name order and labeled `<stem>.json`, so two images sharing a stem (`a.png`, `a.jpg`)
+/// are an error. Frames whose image is missing are skipped.
+pub fn collect_inputs(input: &Path) -> Result<Vec<InferItem>, ServiceError> {
+    let labels_dir = input.join("labels");
+    if labels_dir.is_dir() {
+        let mut items = Vec::new();
+        for path in sorted_files(&labels_dir, |ext| ext == "json")? {
+            let meta: CaptureMetadata = serde_json::from_slice(&fs::read(&path)?)?;
+            let image = input.join(&meta.image);
+            if !meta.image_present || !image.is_file() {
+                continue;
+            }
+            items.push(InferItem {
+                frame_id: meta.frame_id,
+                sim_time: meta.sim_time,
+                unix_time: meta.unix_time,
+                camera_active: meta.camera_active,
+                label_seed: meta.label_seed,
+                image,
+                label_file: file_name(&path),
+            });
+        }
+        return Ok(items);
+    }
+
+    let images = sorted_files(input, |ext| {
+        IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+    })?;
+    let items: Vec<InferItem> = images
+        .into_iter()
+        .enumerate()
+        .map(|(i, image)| {
+            let unix_time = fs::metadata(&image)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+                .map_or(0.0, |d| d.as_secs_f64());
+            let stem = image
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| format!("frame_{i:05}"));
+            InferItem {
+                frame_id: i as u64,
+                sim_time: 0.0,
+                unix_time,
+                camera_active: true,
+                label_seed: 0,
+                image,
+                label_file: format!("{stem}.json"),
+            }
+        })
+        .collect();
+    let mut labeled: HashMap<&str, &Path> = HashMap::new();
+    for item in &items {
+        if let Some(first) = labeled.insert(&item.label_file, &item.image) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} and {} would both be labeled {}",
+                    first.display(),
+                    item.image.display(),
+                    item.label_file
+                ),
+            )
+            .into());
+        }
+    }
+    Ok(items)
+}
+
+/// Labels for a detection on a `(width, height)` image: one per box, sourced from the model with
+/// its score as `source_confidence` and its class as `class_id` (0 when the detector has none).
+pub fn model_labels(result: &DetectionResult, (width, height): (u32, u32)) -> Vec<Label> {
+    let (w, h) = (width as f32, height as f32);
+    result
+        .boxes
+        .iter()
+        .zip(&result.scores)
+        .enumerate()
+        .map(|(i, (bbox, score))| {
+            let norm = bbox.map(|v| v.clamp(0.0, 1.0));
+            Label {
+                center_world: [0.0; 3],
+                bbox_px: Some([norm[0] * w, norm[1] * h, norm[2] * w, norm[3] * h]),
+                bbox_norm: Some(norm),
+                source: Some(LabelSource::Model),
+                source_confidence: Some(score.clamp(0.0, 1.0)),
+                class_id: result.classes.get(i).copied().unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+fn sorted_files(dir: &Path, keep: impl Fn(&str) -> bool) -> Result<Vec<PathBuf>, ServiceError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().and_then(|e| e.to_str()).is_some_and(&keep) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
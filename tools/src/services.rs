@@ -271,8 +271,10 @@ pub fn is_process_running(pid: u32) -> bool {
     sys.process(pid).is_some()
 }
 
+/// Read a `train --status-file` as loose JSON.
 #[cfg(any(feature = "tui", feature = "scheduler"))]
+#[deprecated(note = "use `data_contracts::TrainStatus::read` for a typed status")]
 pub fn read_status(path: &Path) -> Option<serde_json::Value> {
-    let data = std::fs::read(path).ok()?;
-    serde_json::from_slice(&data).ok()
+    let status = data_contracts::TrainStatus::read(path).ok()?;
+    serde_json::to_value(status).ok()
 }
@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::Path;
+
+use cortenforge_tools::pseudo_label::{collect_inputs, model_labels};
+use data_contracts::capture::CaptureMetadata;
+use vision_core::interfaces::{DetectionResult, LabelSource};
+
+fn write_png(path: &Path) {
+    image::RgbaImage::new(4, 2).save(path).expect("write png");
+}
+
+fn write_meta(labels_dir: &Path, frame_id: u64, image: &str, image_present: bool) {
+    let meta = CaptureMetadata {
+        frame_id,
+        sim_time: frame_id as f64 * 0.5,
+        unix_time: 1000.0 + frame_id as f64,
+        image: image.to_string(),
+        image_present,
+        camera_active: true,
+        label_seed: 7,
+        labels: Vec::new(),
+    };
+    fs::write(
+        labels_dir.join(format!("frame_{frame_id:05}.json")),
+        serde_json::to_vec(&meta).unwrap(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn capture_runs_keep_frame_fields_and_skip_missing_images() {
+    let tmp = tempfile::tempdir().unwrap();
+    let run = tmp.path();
+    let labels = run.join("labels");
+    fs::create_dir_all(&labels).unwrap();
+    write_png(&run.join("frame_00003.png"));
+    write_meta(&labels, 3, "frame_00003.png", true);
+    write_meta(&labels, 4, "frame_00004.png", true); // image missing on disk
+    write_meta(&labels, 5, "frame_00005.png", false);
+
+    let items = collect_inputs(run).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].frame_id, 3);
+    assert_eq!(items[0].sim_time, 1.5);
+    assert_eq!(items[0].label_seed, 7);
+    assert_eq!(items[0].image, run.join("frame_00003.png"));
+    assert_eq!(items[0].label_file, "frame_00003.json");
+}
+
+#[test]
+fn image_folders_are_numbered_in_name_order() {
+    let tmp = tempfile::tempdir().unwrap();
+    // Only names matter here; nothing is decoded.
+    for name in ["b.png", "a.JPG", "c.jpeg"] {
+        fs::write(tmp.path().join(name), b"").unwrap();
+    }
+    fs::write(tmp.path().join("notes.txt"), "not an image").unwrap();
+
+    let items = collect_inputs(tmp.path()).unwrap();
+    let files: Vec<(u64, &str)> = items
+        .iter()
+        .map(|item| (item.frame_id, item.label_file.as_str()))
+        .collect();
+    assert_eq!(files, vec![(0, "a.json"), (1, "b.json"), (2, "c.json")]);
+}
+
+#[test]
+fn image_folders_reject_shared_stems() {
+    let tmp = tempfile::tempdir().unwrap();
+    for name in ["a.png", "a.jpg"] {
+        fs::write(tmp.path().join(name), b"").unwrap();
+    }
+    let err = collect_inputs(tmp.path()).unwrap_err();
+    assert!(err.to_string().contains("both be labeled a.json"), "{err}");
+}
+
+#[test]
+fn detections_become_valid_model_labels() {
+    let result = DetectionResult {
+        frame_id: 0,
+        positive: true,
+        confidence: 0.9,
+        boxes: vec![[0.1, 0.2, 0.5, 1.2]],
+        scores: vec![0.9],
+        classes: vec![2],
+    };
+    let labels = model_labels(&result, (200, 100));
+    assert_eq!(labels.len(), 1);
+    let label = &labels[0];
+    assert_eq!(label.source, Some(LabelSource::Model));
+    assert_eq!(label.source_confidence, Some(0.9));
+    assert_eq!(label.class_id, 2);
+    assert_eq!(label.bbox_norm, Some([0.1, 0.2, 0.5, 1.0]));
+    let px = label.bbox_px.unwrap();
+    assert!((px[0] - 20.0).abs() < 1e-4 && (px[3] - 100.0).abs() < 1e-4);
+    label.validate().unwrap();
+}